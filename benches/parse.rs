@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use smpp::pdu::AnyPdu;
+use smpp::pdu_reader::PduReader;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::{Pdu, PduBody, SubmitSmPdu};
+use tokio::runtime::Runtime;
+
+/// The longest value a TLV can hold
+const PAYLOAD_LEN: usize = 65_535;
+
+fn submit_sm_with_payload(payload: &[u8]) -> Pdu {
+    let body = SubmitSmPdu::new(
+        "",
+        0,
+        0,
+        "447000123123",
+        0,
+        0,
+        "447111222222",
+        0,
+        0x34,
+        1,
+        "",
+        "",
+        1,
+        0,
+        0x08,
+        0,
+        b"",
+        Tlvs::from(&[Tlv::new(KnownTlvTag::message_payload, payload)][..]),
+    )
+    .unwrap();
+    Pdu::new(0, 1, body.into()).unwrap()
+}
+
+async fn read_one(bytes: &[u8]) -> AnyPdu {
+    PduReader::new(bytes).read_pdu().await.unwrap().unwrap()
+}
+
+fn parse_large_message_payload(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let payload: Vec<u8> = (0..PAYLOAD_LEN).map(|i| i as u8).collect();
+    let mut bytes: Vec<u8> = Vec::new();
+    runtime
+        .block_on(submit_sm_with_payload(&payload).write(&mut bytes))
+        .unwrap();
+
+    // Check once that we are measuring what we think we are
+    match runtime.block_on(read_one(&bytes)) {
+        AnyPdu::Pdu(pdu) => match pdu.body() {
+            PduBody::SubmitSm(body) => assert_eq!(
+                body.0.tlvs.get(KnownTlvTag::message_payload).unwrap().value,
+                payload
+            ),
+            other => panic!("Expected a submit_sm, got {:?}", other),
+        },
+        other => panic!("Expected a submit_sm, got {:?}", other),
+    }
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("submit_sm with 64KB message_payload", |b| {
+        b.iter(|| runtime.block_on(read_one(&bytes)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_large_message_payload);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+mod smpp_client;
+
+pub use smpp_client::{ClientError, SmppClient};
@@ -0,0 +1,172 @@
+use log::*;
+use smpp_pdu::pdu::{
+    BindReceiverPdu, BindTransceiverPdu, BindTransmitterPdu, Pdu, PduBody,
+    PduStatus, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::collections::VecDeque;
+use std::error;
+use std::fmt::{Display, Formatter};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+use crate::async_result::AsyncResult;
+use crate::pdu::{write_bind_pdu, AnyPdu, INTERFACE_VERSION_34};
+use crate::pdu_reader::PduReader;
+use crate::pending_requests::SequenceNumberAllocator;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The SMSC rejected our request with this command_status
+    ErrorStatus(u32),
+    /// The SMSC closed the connection before responding
+    ConnectionClosed,
+}
+
+impl Display for ClientError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ClientError::ErrorStatus(command_status) => write!(
+                formatter,
+                "SMSC responded with command_status {:#010X}.",
+                command_status
+            ),
+            ClientError::ConnectionClosed => formatter
+                .write_str("SMSC closed the connection before responding."),
+        }
+    }
+}
+
+impl error::Error for ClientError {}
+
+/// A minimal ESME: connects to an SMSC, binds, and sends requests, waiting
+/// for the response to each one.
+pub struct SmppClient {
+    reader: PduReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    sequence_numbers: SequenceNumberAllocator,
+    /// PDUs that arrived while we were waiting for a response, e.g.
+    /// deliver_sm, to be returned by read_pdu().
+    received: VecDeque<AnyPdu>,
+}
+
+impl SmppClient {
+    pub async fn connect(addr: &str) -> AsyncResult<Self> {
+        let (read, writer) = TcpStream::connect(addr).await?.into_split();
+        Ok(Self {
+            reader: PduReader::new(read),
+            writer,
+            sequence_numbers: SequenceNumberAllocator::new(),
+            received: VecDeque::new(),
+        })
+    }
+
+    pub async fn bind_transmitter(
+        &mut self,
+        system_id: &str,
+        password: &str,
+    ) -> AsyncResult<()> {
+        let body = BindTransmitterPdu::new(
+            system_id,
+            password,
+            "",
+            INTERFACE_VERSION_34,
+            0,
+            0,
+            "",
+        )?;
+        self.request(body.into()).await.map(|_| ())
+    }
+
+    pub async fn bind_receiver(
+        &mut self,
+        system_id: &str,
+        password: &str,
+    ) -> AsyncResult<()> {
+        let body = BindReceiverPdu::new(
+            system_id,
+            password,
+            "",
+            INTERFACE_VERSION_34,
+            0,
+            0,
+            "",
+        )?;
+        self.request(body.into()).await.map(|_| ())
+    }
+
+    pub async fn bind_transceiver(
+        &mut self,
+        system_id: &str,
+        password: &str,
+    ) -> AsyncResult<()> {
+        let body = BindTransceiverPdu::new(
+            system_id,
+            password,
+            "",
+            INTERFACE_VERSION_34,
+            0,
+            0,
+            "",
+        )?;
+        self.request(body.into()).await.map(|_| ())
+    }
+
+    /// Send a submit_sm and wait for its submit_sm_resp.  If the SMSC
+    /// rejects it, returns ClientError::ErrorStatus.
+    pub async fn submit_sm(
+        &mut self,
+        body: SubmitSmPdu,
+    ) -> AsyncResult<SubmitSmRespPdu> {
+        let resp = self.request(body.into()).await?;
+        match resp.body() {
+            PduBody::SubmitSmResp(body) => match body.message_id() {
+                Some(message_id) => Ok(SubmitSmRespPdu::new(&message_id)?),
+                None => Ok(SubmitSmRespPdu::new_error()),
+            },
+            _ => Err(format!("Expected submit_sm_resp, got {:?}", resp).into()),
+        }
+    }
+
+    /// Return the next PDU the SMSC sent us that was not a response to one
+    /// of our requests, or None if the connection has closed.
+    pub async fn read_pdu(&mut self) -> AsyncResult<Option<AnyPdu>> {
+        if let Some(pdu) = self.received.pop_front() {
+            return Ok(Some(pdu));
+        }
+        Ok(self.reader.read_pdu().await?)
+    }
+
+    /// Send a request with a new sequence_number, and wait for the
+    /// response with the same sequence_number.
+    async fn request(&mut self, body: PduBody) -> AsyncResult<Pdu> {
+        let sequence_number = self.sequence_numbers.next();
+        let pdu = Pdu::new(PduStatus::ESME_ROK as u32, sequence_number, body)?;
+        debug!("=> {:?}", pdu);
+        write_bind_pdu(&pdu, &mut self.writer).await?;
+
+        loop {
+            match self.reader.read_pdu().await? {
+                Some(AnyPdu::Pdu(pdu))
+                    if pdu.sequence_number.value == sequence_number
+                        && is_response(&pdu) =>
+                {
+                    debug!("<= {:?}", pdu);
+                    let command_status = pdu.command_status.value;
+                    if command_status != PduStatus::ESME_ROK as u32 {
+                        return Err(
+                            ClientError::ErrorStatus(command_status).into()
+                        );
+                    }
+                    return Ok(pdu);
+                }
+                Some(other) => self.received.push_back(other),
+                None => return Err(ClientError::ConnectionClosed.into()),
+            }
+        }
+    }
+}
+
+/// Responses (including generic_nack) have the top bit of command_id set
+fn is_response(pdu: &Pdu) -> bool {
+    pdu.command_id().value & 0x80000000 != 0
+}
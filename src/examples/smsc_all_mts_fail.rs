@@ -17,6 +17,12 @@ impl AllMtsFail {
     }
 }
 
+impl Default for AllMtsFail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl SmscLogic for AllMtsFail {
     async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
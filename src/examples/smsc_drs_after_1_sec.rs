@@ -1,5 +1,6 @@
-//! Logic for an SMSC that returns DRs after approximately 1 second's delay
-//! and allows you to bind if system_id==password.
+//! Logic for an SMSC that returns DRs after approximately 1 second's delay,
+//! if the submit_sm asked for one, and allows you to bind if
+//! system_id==password.
 
 use async_trait::async_trait;
 use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
@@ -12,6 +13,7 @@ use tokio::sync::Mutex;
 use tokio::time;
 
 use crate::message_unique_key::MessageUniqueKey;
+use crate::pdu::RegisteredDelivery;
 use crate::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
 
 pub struct DrsAfter1Sec {}
@@ -22,6 +24,12 @@ impl DrsAfter1Sec {
     }
 }
 
+impl Default for DrsAfter1Sec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl SmscLogic for DrsAfter1Sec {
     async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError> {
@@ -39,16 +47,19 @@ impl SmscLogic for DrsAfter1Sec {
         sequence_number: u32,
     ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
         let message_id = "abc"; // TODO
-        let deliver_sm = create_deliver_sm(message_id, sequence_number, pdu);
-        tokio::spawn(async move {
-            time::sleep(Duration::from_secs(1)).await;
-            smsc.lock()
-                .await
-                .receive_pdu("MySupplier", deliver_sm)
-                .await
-        });
+        if pdu.wants_delivery_receipt_for(true) {
+            let deliver_sm =
+                create_deliver_sm(message_id, sequence_number, pdu);
+            tokio::spawn(async move {
+                time::sleep(Duration::from_secs(1)).await;
+                smsc.lock()
+                    .await
+                    .receive_pdu("MySupplier", deliver_sm)
+                    .await
+            });
+        }
         Ok((
-            SubmitSmRespPdu::new(message_id).unwrap().into(),
+            SubmitSmRespPdu::new(message_id).unwrap(),
             MessageUniqueKey::new(
                 String::from("MySupplier"),
                 String::from(message_id),
@@ -1,6 +1,11 @@
 pub mod async_result;
+pub mod client;
 pub mod examples;
 pub mod message_unique_key;
+pub mod pdu;
+pub mod pdu_reader;
+pub mod pending_requests;
+pub mod session_state;
 pub mod smpp_connection;
 pub mod smsc;
 mod unittest_utils;
@@ -1,8 +1,10 @@
 use smpp_pdu::pdu::DeliverSmPdu;
 
+use crate::pdu::DataSmPdu;
+
 /// A way to identify this message based on the message ID provided by
 /// some remove system.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct MessageUniqueKey {
     /// An identifier for the system which generated the message_id.  For
     /// systems that produce sufficiently unique IDs, this serves as a
@@ -45,4 +47,15 @@ impl MessageUniqueKey {
             destination_addr,
         })
     }
+
+    /// Like from_dr, for a DR sent as a data_sm
+    pub fn from_data_sm(namespace_id: String, pdu: &DataSmPdu) -> Option<Self> {
+        let destination_addr = pdu.source_addr();
+        let message_id = pdu.extract_receipted_message_id();
+        message_id.map(|message_id| Self {
+            namespace_id,
+            message_id,
+            destination_addr,
+        })
+    }
 }
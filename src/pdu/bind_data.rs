@@ -0,0 +1,114 @@
+use smpp_pdu::pdu::data::bind_data::BindData;
+use smpp_pdu::pdu::{Pdu, PduBody};
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::pdu::{write_pdu, InterfaceVersion};
+
+/// The interface_version for SMPP 3.4.  Lower values mean 3.3 or earlier.
+/// See https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.4
+pub const INTERFACE_VERSION_34: u8 = 0x34;
+
+pub trait BindDataExt {
+    /// The version the ESME says it speaks
+    fn interface_version(&self) -> InterfaceVersion;
+
+    /// True if the ESME says it speaks SMPP 3.4
+    fn is_v34(&self) -> bool;
+}
+
+impl BindDataExt for BindData {
+    fn interface_version(&self) -> InterfaceVersion {
+        InterfaceVersion::from_u8(self.interface_version.value)
+    }
+
+    fn is_v34(&self) -> bool {
+        self.interface_version() == InterfaceVersion::V34
+    }
+}
+
+/// Write a bind_transmitter, bind_receiver or bind_transceiver PDU.  We
+/// can't use Pdu::write for these because smpp-pdu does not implement
+/// writing BindData.  Any other PDU is written with write_pdu.
+pub async fn write_bind_pdu<W: AsyncWrite + Unpin + ?Sized>(
+    pdu: &Pdu,
+    stream: &mut W,
+) -> io::Result<()> {
+    let bind_data = match pdu.body() {
+        PduBody::BindReceiver(body) => &body.0,
+        PduBody::BindTransceiver(body) => &body.0,
+        PduBody::BindTransmitter(body) => &body.0,
+        _ => return write_pdu(pdu, stream).await,
+    };
+
+    // command_length goes here, so that we write the PDU with one
+    // write_all(), and other writers can't get between its parts
+    let mut buf = vec![0; 4];
+    pdu.command_id().write(&mut buf).await?;
+    pdu.command_status.write(&mut buf).await?;
+    pdu.sequence_number.write(&mut buf).await?;
+    bind_data.system_id.write(&mut buf).await?;
+    bind_data.password.write(&mut buf).await?;
+    bind_data.system_type.write(&mut buf).await?;
+    bind_data.interface_version.write(&mut buf).await?;
+    bind_data.addr_ton.write(&mut buf).await?;
+    bind_data.addr_npi.write(&mut buf).await?;
+    bind_data.address_range.write(&mut buf).await?;
+    let command_length = buf.len() as u32;
+    buf[..4].copy_from_slice(&command_length.to_be_bytes());
+    stream.write_all(&buf).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::BindTransmitterPdu;
+
+    fn bind_data(interface_version: u8) -> BindData {
+        BindData::new("id", "pw", "", interface_version, 0, 0, "").unwrap()
+    }
+
+    #[test]
+    fn only_0x34_is_v34() {
+        assert!(bind_data(0x34).is_v34());
+        assert!(!bind_data(0x33).is_v34());
+        assert!(!bind_data(0x00).is_v34());
+        assert!(!bind_data(0x50).is_v34());
+    }
+
+    #[test]
+    fn interface_version_is_exposed_as_enum() {
+        assert_eq!(bind_data(0x50).interface_version(), InterfaceVersion::V50);
+        assert_eq!(
+            bind_data(0x01).interface_version(),
+            InterfaceVersion::Unknown(0x01)
+        );
+    }
+
+    #[tokio::test]
+    async fn bind_transmitter_round_trips() {
+        let pdu = Pdu::new(
+            0,
+            0x07,
+            BindTransmitterPdu::new(
+                "esmeid", "password", "type", 0x34, 0, 0, "",
+            )
+            .unwrap()
+            .into(),
+        )
+        .unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        write_bind_pdu(&pdu, &mut bytes).await.unwrap();
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            esmeid\0password\0type\0\x34\x00\x00\0"
+        );
+        assert_eq!(
+            Pdu::parse(&mut io::BufReader::new(&bytes[..])).unwrap(),
+            pdu
+        );
+    }
+}
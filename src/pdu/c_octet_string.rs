@@ -0,0 +1,162 @@
+//! The C-Octet String (Decimal) and C-Octet String (Hex) types from
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 3.1, which smpp-pdu
+//! does not provide.  Both are C-Octet Strings restricted to certain
+//! characters, so they wrap COctetString and check its contents when they
+//! are created or read.
+
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io;
+
+/// A C-Octet String containing only the digits 0-9
+#[derive(Clone, Debug, PartialEq)]
+pub struct COctetStringDecimal(COctetString);
+
+/// A C-Octet String containing only the hex digits 0-9, A-F and a-f
+#[derive(Clone, Debug, PartialEq)]
+pub struct COctetStringHex(COctetString);
+
+impl COctetStringDecimal {
+    pub fn from_str(
+        value: &str,
+        max_len: usize,
+    ) -> Result<Self, PduParseError> {
+        let value = COctetString::from_str(value, max_len)?;
+        only(&value, |c| c.is_ascii_digit(), "decimal").map(Self)
+    }
+
+    pub fn read(
+        bytes: &mut dyn io::BufRead,
+        max_len: usize,
+    ) -> Result<Self, PduParseError> {
+        let value = COctetString::read(bytes, max_len)?;
+        only(&value, |c| c.is_ascii_digit(), "decimal").map(Self)
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.0.write(stream).await
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.value.as_str()
+    }
+}
+
+impl COctetStringHex {
+    pub fn from_str(
+        value: &str,
+        max_len: usize,
+    ) -> Result<Self, PduParseError> {
+        let value = COctetString::from_str(value, max_len)?;
+        only(&value, |c| c.is_ascii_hexdigit(), "hex").map(Self)
+    }
+
+    pub fn read(
+        bytes: &mut dyn io::BufRead,
+        max_len: usize,
+    ) -> Result<Self, PduParseError> {
+        let value = COctetString::read(bytes, max_len)?;
+        only(&value, |c| c.is_ascii_hexdigit(), "hex").map(Self)
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.0.write(stream).await
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.value.as_str()
+    }
+}
+
+impl From<COctetStringDecimal> for COctetString {
+    fn from(value: COctetStringDecimal) -> Self {
+        value.0
+    }
+}
+
+impl From<COctetStringHex> for COctetString {
+    fn from(value: COctetStringHex) -> Self {
+        value.0
+    }
+}
+
+/// Check every character of value satisfies allowed, and return it if so.
+/// An empty string is allowed, since that is how optional fields are left
+/// out.
+fn only(
+    value: &COctetString,
+    allowed: fn(&char) -> bool,
+    kind: &str,
+) -> Result<COctetString, PduParseError> {
+    match value.value.as_str().chars().position(|c| !allowed(&c)) {
+        None => Ok(value.clone()),
+        Some(position) => Err(PduParseError::new(
+            PduParseErrorBody::OtherIoError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "C-Octet String ({}) contains a character that is not \
+                    allowed at position {}: \"{}\".",
+                    kind,
+                    position,
+                    value.value.as_str()
+                ),
+            )),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::operations::fld;
+
+    #[test]
+    fn decimal_digits_are_accepted() {
+        let value = COctetStringDecimal::from_str("0123456789", 65).unwrap();
+        assert_eq!(value.as_str(), "0123456789");
+
+        let mut bytes = io::Cursor::new(b"42\x00");
+        let value = COctetStringDecimal::read(&mut bytes, 65).unwrap();
+        assert_eq!(value.as_str(), "42");
+    }
+
+    #[test]
+    fn hex_digits_are_accepted() {
+        let value = COctetStringHex::from_str("09afAF", 65).unwrap();
+        assert_eq!(value.as_str(), "09afAF");
+
+        let mut bytes = io::Cursor::new(b"7fFF\x00");
+        let value = COctetStringHex::read(&mut bytes, 65).unwrap();
+        assert_eq!(value.as_str(), "7fFF");
+    }
+
+    #[test]
+    fn non_digits_are_rejected_with_the_field_name() {
+        let mut bytes = io::Cursor::new(b"12a4\x00");
+        let e = fld("message_id", COctetStringDecimal::read(&mut bytes, 65))
+            .unwrap_err();
+        assert!(
+            e.to_string().ends_with(
+                "field_name=message_id): IO error: C-Octet String (decimal) contains a character that is not \
+                allowed at position 2: \"12a4\"."
+            ),
+            "{}",
+            e
+        );
+
+        assert!(COctetStringHex::from_str("12g4", 65).is_err());
+        assert!(COctetStringDecimal::from_str("-1", 65).is_err());
+    }
+
+    #[test]
+    fn empty_strings_are_accepted() {
+        assert_eq!(COctetStringDecimal::from_str("", 65).unwrap().as_str(), "");
+        let mut bytes = io::Cursor::new(b"\x00");
+        assert_eq!(COctetStringHex::read(&mut bytes, 65).unwrap().as_str(), "");
+    }
+
+    #[test]
+    fn too_long_strings_are_rejected() {
+        assert!(COctetStringDecimal::from_str("1234", 4).is_err());
+    }
+}
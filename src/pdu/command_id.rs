@@ -0,0 +1,159 @@
+//! The command_id of every PDU type we support, whether through smpp-pdu or
+//! ExtraPdu.  See https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.1.2.1
+
+pub const GENERIC_NACK: u32 = 0x80000000;
+pub const BIND_RECEIVER: u32 = 0x00000001;
+pub const BIND_RECEIVER_RESP: u32 = 0x80000001;
+pub const BIND_TRANSMITTER: u32 = 0x00000002;
+pub const BIND_TRANSMITTER_RESP: u32 = 0x80000002;
+pub const QUERY_SM: u32 = 0x00000003;
+pub const QUERY_SM_RESP: u32 = 0x80000003;
+pub const SUBMIT_SM: u32 = 0x00000004;
+pub const SUBMIT_SM_RESP: u32 = 0x80000004;
+pub const DELIVER_SM: u32 = 0x00000005;
+pub const DELIVER_SM_RESP: u32 = 0x80000005;
+pub const UNBIND: u32 = 0x00000006;
+pub const UNBIND_RESP: u32 = 0x80000006;
+pub const CANCEL_SM: u32 = 0x00000008;
+pub const CANCEL_SM_RESP: u32 = 0x80000008;
+pub const BIND_TRANSCEIVER: u32 = 0x00000009;
+pub const OUTBIND: u32 = 0x0000000b;
+pub const BIND_TRANSCEIVER_RESP: u32 = 0x80000009;
+pub const ENQUIRE_LINK: u32 = 0x00000015;
+pub const ENQUIRE_LINK_RESP: u32 = 0x80000015;
+pub const SUBMIT_MULTI: u32 = 0x00000021;
+pub const SUBMIT_MULTI_RESP: u32 = 0x80000021;
+pub const ALERT_NOTIFICATION: u32 = 0x00000102;
+pub const DATA_SM: u32 = 0x00000103;
+pub const DATA_SM_RESP: u32 = 0x80000103;
+
+/// Every PDU type we support, with its name from the spec, in command_id
+/// order.
+pub const ALL: &[(u32, &str)] = &[
+    (BIND_RECEIVER, "bind_receiver"),
+    (BIND_TRANSMITTER, "bind_transmitter"),
+    (QUERY_SM, "query_sm"),
+    (SUBMIT_SM, "submit_sm"),
+    (DELIVER_SM, "deliver_sm"),
+    (UNBIND, "unbind"),
+    (CANCEL_SM, "cancel_sm"),
+    (BIND_TRANSCEIVER, "bind_transceiver"),
+    (OUTBIND, "outbind"),
+    (ENQUIRE_LINK, "enquire_link"),
+    (SUBMIT_MULTI, "submit_multi"),
+    (ALERT_NOTIFICATION, "alert_notification"),
+    (DATA_SM, "data_sm"),
+    (GENERIC_NACK, "generic_nack"),
+    (BIND_RECEIVER_RESP, "bind_receiver_resp"),
+    (BIND_TRANSMITTER_RESP, "bind_transmitter_resp"),
+    (QUERY_SM_RESP, "query_sm_resp"),
+    (SUBMIT_SM_RESP, "submit_sm_resp"),
+    (DELIVER_SM_RESP, "deliver_sm_resp"),
+    (UNBIND_RESP, "unbind_resp"),
+    (CANCEL_SM_RESP, "cancel_sm_resp"),
+    (BIND_TRANSCEIVER_RESP, "bind_transceiver_resp"),
+    (ENQUIRE_LINK_RESP, "enquire_link_resp"),
+    (SUBMIT_MULTI_RESP, "submit_multi_resp"),
+    (DATA_SM_RESP, "data_sm_resp"),
+];
+
+/// The command_ids of every PDU type we support, in order
+pub fn all_command_ids() -> impl Iterator<Item = u32> {
+    ALL.iter().map(|(command_id, _)| *command_id)
+}
+
+/// The spec's name for the PDU type with this command_id, e.g. "submit_sm",
+/// or None if we don't support it.
+pub fn command_name(command_id: u32) -> Option<&'static str> {
+    ALL.iter()
+        .find(|(id, _)| *id == command_id)
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::Pdu;
+    use std::collections::HashSet;
+    use std::io;
+
+    use crate::pdu::ExtraPdu;
+
+    #[test]
+    fn every_constant_is_listed_once() {
+        let constants = [
+            GENERIC_NACK,
+            BIND_RECEIVER,
+            BIND_RECEIVER_RESP,
+            BIND_TRANSMITTER,
+            BIND_TRANSMITTER_RESP,
+            QUERY_SM,
+            QUERY_SM_RESP,
+            SUBMIT_SM,
+            SUBMIT_SM_RESP,
+            DELIVER_SM,
+            DELIVER_SM_RESP,
+            UNBIND,
+            UNBIND_RESP,
+            CANCEL_SM,
+            CANCEL_SM_RESP,
+            BIND_TRANSCEIVER,
+            BIND_TRANSCEIVER_RESP,
+            OUTBIND,
+            ENQUIRE_LINK,
+            ENQUIRE_LINK_RESP,
+            SUBMIT_MULTI,
+            SUBMIT_MULTI_RESP,
+            ALERT_NOTIFICATION,
+            DATA_SM,
+            DATA_SM_RESP,
+        ];
+        let listed: HashSet<u32> = all_command_ids().collect();
+
+        assert_eq!(listed.len(), ALL.len(), "Duplicate command_id in ALL");
+        assert_eq!(listed, constants.iter().copied().collect());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let names: HashSet<&str> = ALL.iter().map(|(_, name)| *name).collect();
+        assert_eq!(names.len(), ALL.len());
+    }
+
+    #[test]
+    fn listed_in_command_id_order() {
+        let ids: Vec<u32> = all_command_ids().collect();
+        let mut sorted = ids.clone();
+        sorted.sort_unstable();
+        assert_eq!(ids, sorted);
+    }
+
+    #[test]
+    fn every_listed_command_id_is_recognised_by_a_parser() {
+        for (command_id, name) in ALL {
+            // smpp-pdu can write generic_nack but not parse it
+            if *command_id == GENERIC_NACK {
+                continue;
+            }
+            let mut bytes = vec![0x00, 0x00, 0x00, 0x10];
+            bytes.extend(&command_id.to_be_bytes());
+            bytes.extend(&[0, 0, 0, 0, 0, 0, 0, 1]);
+            let mut reader = io::BufReader::new(&bytes[..]);
+
+            let status = if ExtraPdu::handles(*command_id) {
+                ExtraPdu::parse(&mut reader).err().map(|e| e.status())
+            } else {
+                Pdu::parse(&mut reader).err().map(|e| e.status())
+            };
+            // Parsing a header-only PDU may fail, but not with
+            // ESME_RINVCMDID
+            assert_ne!(status, Some(0x00000003), "{} not recognised", name);
+        }
+    }
+
+    #[test]
+    fn command_name_finds_names() {
+        assert_eq!(command_name(SUBMIT_SM), Some("submit_sm"));
+        assert_eq!(command_name(0x00000007), None);
+    }
+}
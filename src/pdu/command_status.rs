@@ -0,0 +1,266 @@
+//! command_status values that smpp-pdu's PduStatus does not include, the
+//! symbolic name of every command_status, and the mapping from our error
+//! types to command_status.  See
+//! https://smpp.org/SMPPv50.pdf section 4.7.6
+
+use smpp_pdu::pdu::{PduParseError, PduStatus};
+
+/// Invalid Data Coding Scheme: the short_message is not valid in the
+/// declared data_coding
+pub const ESME_RINVDCS: u32 = 0x00000104;
+
+/// An error that we report to the peer as the command_status of a
+/// response.
+pub trait ToCommandStatus {
+    fn command_status(&self) -> u32;
+}
+
+impl ToCommandStatus for PduParseError {
+    fn command_status(&self) -> u32 {
+        self.status()
+    }
+}
+
+/// Every command_status in the SMPP v3.4 spec, plus ESME_RINVDCS, with its
+/// symbolic name, in command_status order.
+pub const ALL: &[(u32, &str)] = &[
+    (PduStatus::ESME_ROK as u32, "ESME_ROK"),
+    (PduStatus::ESME_RINVMSGLEN as u32, "ESME_RINVMSGLEN"),
+    (PduStatus::ESME_RINVCMDLEN as u32, "ESME_RINVCMDLEN"),
+    (PduStatus::ESME_RINVCMDID as u32, "ESME_RINVCMDID"),
+    (PduStatus::ESME_RINVBNDSTS as u32, "ESME_RINVBNDSTS"),
+    (PduStatus::ESME_RALYBND as u32, "ESME_RALYBND"),
+    (PduStatus::ESME_RINVPRTFLG as u32, "ESME_RINVPRTFLG"),
+    (PduStatus::ESME_RINVREGDLVFLG as u32, "ESME_RINVREGDLVFLG"),
+    (PduStatus::ESME_RSYSERR as u32, "ESME_RSYSERR"),
+    (PduStatus::ESME_RINVSRCADR as u32, "ESME_RINVSRCADR"),
+    (PduStatus::ESME_RINVDSTADR as u32, "ESME_RINVDSTADR"),
+    (PduStatus::ESME_RINVMSGID as u32, "ESME_RINVMSGID"),
+    (PduStatus::ESME_RBINDFAIL as u32, "ESME_RBINDFAIL"),
+    (PduStatus::ESME_RINVPASWD as u32, "ESME_RINVPASWD"),
+    (PduStatus::ESME_RINVSYSID as u32, "ESME_RINVSYSID"),
+    (PduStatus::ESME_RCANCELFAIL as u32, "ESME_RCANCELFAIL"),
+    (PduStatus::ESME_RREPLACEFAIL as u32, "ESME_RREPLACEFAIL"),
+    (PduStatus::ESME_RMSGQFUL as u32, "ESME_RMSGQFUL"),
+    (PduStatus::ESME_RINVSERTYP as u32, "ESME_RINVSERTYP"),
+    (PduStatus::ESME_RINVNUMDESTS as u32, "ESME_RINVNUMDESTS"),
+    (PduStatus::ESME_RINVDLNAME as u32, "ESME_RINVDLNAME"),
+    (PduStatus::ESME_RINVDESTFLAG as u32, "ESME_RINVDESTFLAG"),
+    (PduStatus::ESME_RINVSUBREP as u32, "ESME_RINVSUBREP"),
+    (PduStatus::ESME_RINVESMCLASS as u32, "ESME_RINVESMCLASS"),
+    (PduStatus::ESME_RCNTSUBDL as u32, "ESME_RCNTSUBDL"),
+    (PduStatus::ESME_RSUBMITFAIL as u32, "ESME_RSUBMITFAIL"),
+    (PduStatus::ESME_RINVSRCTON as u32, "ESME_RINVSRCTON"),
+    (PduStatus::ESME_RINVSRCNPI as u32, "ESME_RINVSRCNPI"),
+    (PduStatus::ESME_RINVDSTTON as u32, "ESME_RINVDSTTON"),
+    (PduStatus::ESME_RINVDSTNPI as u32, "ESME_RINVDSTNPI"),
+    (PduStatus::ESME_RINVSYSTYP as u32, "ESME_RINVSYSTYP"),
+    (PduStatus::ESME_RINVREPFLAG as u32, "ESME_RINVREPFLAG"),
+    (PduStatus::ESME_RINVNUMMSGS as u32, "ESME_RINVNUMMSGS"),
+    (PduStatus::ESME_RTHROTTLED as u32, "ESME_RTHROTTLED"),
+    (PduStatus::ESME_RINVSCHED as u32, "ESME_RINVSCHED"),
+    (PduStatus::ESME_RINVEXPIRY as u32, "ESME_RINVEXPIRY"),
+    (PduStatus::ESME_RINVDFTMSGID as u32, "ESME_RINVDFTMSGID"),
+    (PduStatus::ESME_RX_T_APPN as u32, "ESME_RX_T_APPN"),
+    (PduStatus::ESME_RX_P_APPN as u32, "ESME_RX_P_APPN"),
+    (PduStatus::ESME_RX_R_APPN as u32, "ESME_RX_R_APPN"),
+    (PduStatus::ESME_RQUERYFAIL as u32, "ESME_RQUERYFAIL"),
+    (
+        PduStatus::ESME_RINVOPTPARSTREAM as u32,
+        "ESME_RINVOPTPARSTREAM",
+    ),
+    (
+        PduStatus::ESME_ROPTPARNOTALLWD as u32,
+        "ESME_ROPTPARNOTALLWD",
+    ),
+    (PduStatus::ESME_RINVPARLEN as u32, "ESME_RINVPARLEN"),
+    (
+        PduStatus::ESME_RMISSINGOPTPARAM as u32,
+        "ESME_RMISSINGOPTPARAM",
+    ),
+    (
+        PduStatus::ESME_RINVOPTPARAMVAL as u32,
+        "ESME_RINVOPTPARAMVAL",
+    ),
+    (
+        PduStatus::ESME_RDELIVERYFAILURE as u32,
+        "ESME_RDELIVERYFAILURE",
+    ),
+    (PduStatus::ESME_RUNKNOWNERR as u32, "ESME_RUNKNOWNERR"),
+    (ESME_RINVDCS, "ESME_RINVDCS"),
+];
+
+/// The symbolic name of this command_status, e.g. "ESME_RINVCMDID", or None
+/// if the spec does not define it.
+pub fn status_name(command_status: u32) -> Option<&'static str> {
+    ALL.iter()
+        .find(|(status, _)| *status == command_status)
+        .map(|(_, name)| *name)
+}
+
+/// The PduStatus with this value, or None if smpp-pdu does not define one,
+/// which includes our ESME_RINVDCS.  PduStatus is not Clone, so this is how
+/// to get a second copy of one.
+pub fn status_from_u32(command_status: u32) -> Option<PduStatus> {
+    match command_status {
+        0x00000000 => Some(PduStatus::ESME_ROK),
+        0x00000001 => Some(PduStatus::ESME_RINVMSGLEN),
+        0x00000002 => Some(PduStatus::ESME_RINVCMDLEN),
+        0x00000003 => Some(PduStatus::ESME_RINVCMDID),
+        0x00000004 => Some(PduStatus::ESME_RINVBNDSTS),
+        0x00000005 => Some(PduStatus::ESME_RALYBND),
+        0x00000006 => Some(PduStatus::ESME_RINVPRTFLG),
+        0x00000007 => Some(PduStatus::ESME_RINVREGDLVFLG),
+        0x00000008 => Some(PduStatus::ESME_RSYSERR),
+        0x0000000A => Some(PduStatus::ESME_RINVSRCADR),
+        0x0000000B => Some(PduStatus::ESME_RINVDSTADR),
+        0x0000000C => Some(PduStatus::ESME_RINVMSGID),
+        0x0000000D => Some(PduStatus::ESME_RBINDFAIL),
+        0x0000000E => Some(PduStatus::ESME_RINVPASWD),
+        0x0000000F => Some(PduStatus::ESME_RINVSYSID),
+        0x00000011 => Some(PduStatus::ESME_RCANCELFAIL),
+        0x00000013 => Some(PduStatus::ESME_RREPLACEFAIL),
+        0x00000014 => Some(PduStatus::ESME_RMSGQFUL),
+        0x00000015 => Some(PduStatus::ESME_RINVSERTYP),
+        0x00000033 => Some(PduStatus::ESME_RINVNUMDESTS),
+        0x00000034 => Some(PduStatus::ESME_RINVDLNAME),
+        0x00000040 => Some(PduStatus::ESME_RINVDESTFLAG),
+        0x00000042 => Some(PduStatus::ESME_RINVSUBREP),
+        0x00000043 => Some(PduStatus::ESME_RINVESMCLASS),
+        0x00000044 => Some(PduStatus::ESME_RCNTSUBDL),
+        0x00000045 => Some(PduStatus::ESME_RSUBMITFAIL),
+        0x00000048 => Some(PduStatus::ESME_RINVSRCTON),
+        0x00000049 => Some(PduStatus::ESME_RINVSRCNPI),
+        0x00000050 => Some(PduStatus::ESME_RINVDSTTON),
+        0x00000051 => Some(PduStatus::ESME_RINVDSTNPI),
+        0x00000053 => Some(PduStatus::ESME_RINVSYSTYP),
+        0x00000054 => Some(PduStatus::ESME_RINVREPFLAG),
+        0x00000055 => Some(PduStatus::ESME_RINVNUMMSGS),
+        0x00000058 => Some(PduStatus::ESME_RTHROTTLED),
+        0x00000061 => Some(PduStatus::ESME_RINVSCHED),
+        0x00000062 => Some(PduStatus::ESME_RINVEXPIRY),
+        0x00000063 => Some(PduStatus::ESME_RINVDFTMSGID),
+        0x00000064 => Some(PduStatus::ESME_RX_T_APPN),
+        0x00000065 => Some(PduStatus::ESME_RX_P_APPN),
+        0x00000066 => Some(PduStatus::ESME_RX_R_APPN),
+        0x00000067 => Some(PduStatus::ESME_RQUERYFAIL),
+        0x000000C0 => Some(PduStatus::ESME_RINVOPTPARSTREAM),
+        0x000000C1 => Some(PduStatus::ESME_ROPTPARNOTALLWD),
+        0x000000C2 => Some(PduStatus::ESME_RINVPARLEN),
+        0x000000C3 => Some(PduStatus::ESME_RMISSINGOPTPARAM),
+        0x000000C4 => Some(PduStatus::ESME_RINVOPTPARAMVAL),
+        0x000000FE => Some(PduStatus::ESME_RDELIVERYFAILURE),
+        0x000000FF => Some(PduStatus::ESME_RUNKNOWNERR),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::PduParseErrorBody;
+    use std::collections::HashSet;
+
+    use crate::smsc::{BindError, CancelSmError, QuerySmError, SubmitSmError};
+
+    fn via_from<E: Into<PduStatus>>(e: E) -> u32 {
+        e.into() as u32
+    }
+
+    #[test]
+    fn pdu_parse_error_status_is_unchanged() {
+        for body in [
+            PduParseErrorBody::LengthTooLong(0x10001),
+            PduParseErrorBody::IncorrectLength(3, String::from("x")),
+            PduParseErrorBody::UnknownCommandId,
+            PduParseErrorBody::NotEnoughBytes,
+        ] {
+            let e = PduParseError::new(body);
+            assert_eq!(e.command_status(), e.status());
+        }
+        assert_eq!(
+            PduParseError::new(PduParseErrorBody::UnknownCommandId)
+                .command_status(),
+            PduStatus::ESME_RINVCMDID as u32
+        );
+    }
+
+    #[test]
+    fn logic_error_statuses_are_unchanged() {
+        for e in [
+            BindError::IncorrectPassword,
+            BindError::IncorrectInterfaceVersion,
+            BindError::NotAllowed,
+            BindError::InternalError,
+        ] {
+            assert_eq!(e.command_status(), via_from(e));
+        }
+        assert_eq!(
+            SubmitSmError::InternalError.command_status(),
+            via_from(SubmitSmError::InternalError)
+        );
+        for e in [QuerySmError::QueryFailed, QuerySmError::InternalError] {
+            assert_eq!(e.command_status(), via_from(e));
+        }
+        for e in [CancelSmError::CancelFailed, CancelSmError::InternalError] {
+            assert_eq!(e.command_status(), via_from(e));
+        }
+        assert_eq!(
+            BindError::IncorrectPassword.command_status(),
+            PduStatus::ESME_RINVPASWD as u32
+        );
+    }
+
+    #[test]
+    fn statuses_round_trip_through_u32() {
+        for status in [
+            PduStatus::ESME_ROK,
+            PduStatus::ESME_RINVCMDID,
+            PduStatus::ESME_RINVPASWD,
+            PduStatus::ESME_RSYSERR,
+            PduStatus::ESME_RINVESMCLASS,
+            PduStatus::ESME_RDELIVERYFAILURE,
+        ] {
+            let value = status as u32;
+            assert_eq!(status_from_u32(value).map(|s| s as u32), Some(value));
+        }
+    }
+
+    #[test]
+    fn every_listed_status_is_a_pdu_status_except_ours() {
+        for (status, name) in ALL {
+            let expected = *status != ESME_RINVDCS;
+            assert_eq!(
+                status_from_u32(*status).is_some(),
+                expected,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_statuses_have_no_name() {
+        assert!(status_from_u32(0x00000009).is_none());
+        assert!(status_from_u32(ESME_RINVDCS).is_none());
+        assert_eq!(status_name(0x00000009), None);
+        assert_eq!(status_name(0x00000400), None);
+    }
+
+    #[test]
+    fn statuses_are_named() {
+        assert_eq!(status_name(0x00000003), Some("ESME_RINVCMDID"));
+        assert_eq!(status_name(ESME_RINVDCS), Some("ESME_RINVDCS"));
+        let names: HashSet<&str> = ALL.iter().map(|(_, name)| *name).collect();
+        assert_eq!(names.len(), ALL.len());
+    }
+
+    #[test]
+    fn listed_in_command_status_order() {
+        let statuses: Vec<u32> =
+            ALL.iter().map(|(status, _)| *status).collect();
+        let mut sorted = statuses.clone();
+        sorted.sort_unstable();
+        assert_eq!(statuses, sorted);
+    }
+}
@@ -0,0 +1,247 @@
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::pdu::gsm7;
+
+/// The common values of the data_coding field.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.19
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DataCoding {
+    /// SMSC default alphabet, which we take to be GSM 7-bit, packed
+    Gsm7 = 0x00,
+    /// IA5 (CCITT T.50), i.e. ASCII
+    Ia5 = 0x01,
+    /// ISO-8859-1
+    Latin1 = 0x03,
+    /// UCS2 (ISO/IEC-10646), big-endian
+    Ucs2 = 0x08,
+}
+
+impl TryFrom<u8> for DataCoding {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(DataCoding::Gsm7),
+            0x01 => Ok(DataCoding::Ia5),
+            0x03 => Ok(DataCoding::Latin1),
+            0x08 => Ok(DataCoding::Ucs2),
+            _ => Err(DecodeError::UnsupportedDataCoding(value)),
+        }
+    }
+}
+
+impl DataCoding {
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, DecodeError> {
+        match self {
+            DataCoding::Gsm7 => Ok(gsm7::decode_septets(&gsm7::unpack(bytes))),
+            DataCoding::Ia5 => {
+                if bytes.is_ascii() {
+                    Ok(bytes.iter().map(|b| *b as char).collect())
+                } else {
+                    Err(DecodeError::InvalidAscii)
+                }
+            }
+            DataCoding::Latin1 => {
+                Ok(bytes.iter().map(|b| *b as char).collect())
+            }
+            DataCoding::Ucs2 => {
+                if !bytes.len().is_multiple_of(2) {
+                    return Err(DecodeError::InvalidUcs2);
+                }
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).map_err(|_| DecodeError::InvalidUcs2)
+            }
+        }
+    }
+
+    /// Check that the bytes are valid in this data_coding.  Unlike
+    /// decode(), this rejects GSM 7-bit escapes that have no meaning.
+    pub fn validate(&self, bytes: &[u8]) -> Result<(), DecodeError> {
+        match self {
+            DataCoding::Gsm7 => gsm7::check_septets(&gsm7::unpack(bytes))
+                .map_err(|_| DecodeError::InvalidGsm7),
+            _ => self.decode(bytes).map(|_| ()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    UnsupportedDataCoding(u8),
+    InvalidGsm7,
+    InvalidAscii,
+    InvalidUcs2,
+}
+
+impl Display for DecodeError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        let s = match self {
+            DecodeError::UnsupportedDataCoding(data_coding) => format!(
+                "Unable to decode short_message with unsupported \
+                data_coding {:#04X}.",
+                data_coding
+            ),
+            DecodeError::InvalidGsm7 => String::from(
+                "short_message was not valid GSM 7-bit, but data_coding \
+                was the SMSC default alphabet.",
+            ),
+            DecodeError::InvalidAscii => String::from(
+                "short_message contained non-ASCII bytes, but data_coding \
+                was IA5.",
+            ),
+            DecodeError::InvalidUcs2 => String::from(
+                "short_message was not valid UCS2, but data_coding was UCS2.",
+            ),
+        };
+        formatter.write_str(&s)
+    }
+}
+
+impl error::Error for DecodeError {}
+
+/// Interpret the short_message of a PDU as text, according to its
+/// data_coding
+pub trait DecodedText {
+    fn decoded_text(&self) -> Result<String, DecodeError>;
+}
+
+fn decode_sm_data(sm_data: &SmData) -> Result<String, DecodeError> {
+    DataCoding::try_from(sm_data.data_coding.value)?
+        .decode(&sm_data.short_message.value)
+}
+
+impl DecodedText for SubmitSmPdu {
+    fn decoded_text(&self) -> Result<String, DecodeError> {
+        decode_sm_data(&self.0)
+    }
+}
+
+impl DecodedText for DeliverSmPdu {
+    fn decoded_text(&self) -> Result<String, DecodeError> {
+        decode_sm_data(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::Tlvs;
+    use smpp_pdu::pdu::{Pdu, SubmitEsmClass};
+    use std::io;
+
+    fn submit_sm(data_coding: u8, short_message: &[u8]) -> SubmitSmPdu {
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "src",
+            0,
+            0,
+            "dest",
+            SubmitEsmClass::Default as u8,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            data_coding,
+            0,
+            short_message,
+            Tlvs::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decode_gsm7_packed_message() {
+        let pdu = submit_sm(0x00, b"\xe8\x32\x9b\xfd\x46\x97\xd9\xec\x37");
+        assert_eq!(pdu.decoded_text().unwrap(), "hellohello");
+    }
+
+    #[test]
+    fn decode_ucs2_message() {
+        let pdu = submit_sm(0x08, b"\x00H\x00i\x20\xac\xd8\x3d\xde\x00");
+        assert_eq!(pdu.decoded_text().unwrap(), "Hi€😀");
+    }
+
+    #[test]
+    fn decode_latin1_message() {
+        let pdu = submit_sm(0x03, b"caf\xe9 \xa3");
+        assert_eq!(pdu.decoded_text().unwrap(), "café £");
+    }
+
+    #[test]
+    fn decode_ia5_message() {
+        assert_eq!(submit_sm(0x01, b"hi").decoded_text().unwrap(), "hi");
+        assert_eq!(
+            submit_sm(0x01, b"h\xe9").decoded_text().unwrap_err(),
+            DecodeError::InvalidAscii
+        );
+    }
+
+    #[test]
+    fn odd_length_ucs2_is_an_error() {
+        let pdu = submit_sm(0x08, b"\x00H\x00");
+        assert_eq!(pdu.decoded_text().unwrap_err(), DecodeError::InvalidUcs2);
+    }
+
+    #[test]
+    fn unsupported_data_coding_is_an_error() {
+        let pdu = submit_sm(0x04, b"\x01\x02");
+        let err = pdu.decoded_text().unwrap_err();
+        assert_eq!(err, DecodeError::UnsupportedDataCoding(0x04));
+        assert_eq!(
+            err.to_string(),
+            "Unable to decode short_message with unsupported data_coding 0x04."
+        );
+    }
+
+    #[test]
+    fn validate_rejects_unknown_gsm7_escapes() {
+        // "1" followed by an escape into the extension table, then "A"
+        let valid = gsm7::pack(&[0x31, 0x1b, 0x65, 0x41]);
+        let invalid = gsm7::pack(&[0x31, 0x1b, 0x41]);
+        assert_eq!(DataCoding::Gsm7.validate(&valid), Ok(()));
+        assert_eq!(
+            DataCoding::Gsm7.validate(&invalid),
+            Err(DecodeError::InvalidGsm7)
+        );
+        assert_eq!(
+            DataCoding::Ucs2.validate(b"\x00H\x00"),
+            Err(DecodeError::InvalidUcs2)
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_sm_with_non_ascii_short_message_round_trips() {
+        let pdu =
+            Pdu::new(0, 0x15, submit_sm(0x03, b"dr \xffpls").into()).unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        pdu.write(&mut bytes).await.unwrap();
+
+        // Every fixed field, then sm_length and short_message, in the order
+        // they are parsed
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x2f\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x15\
+            \x00\x00\x00src\x00\x00\x00dest\x00\x00\x34\x01\x00\x00\x01\x00\x03\x00\
+            \x07dr \xffpls"
+        );
+        assert_eq!(
+            Pdu::parse(&mut io::BufReader::new(&bytes[..])).unwrap(),
+            pdu
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! The text of a delivery receipt (DR), as suggested in
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf Appendix B, e.g.
+//! "id:123 sub:001 dlvrd:001 submit date:2103301649 done date:2103301650
+//! stat:DELIVRD err:000 text:hello"
+
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::str;
+
+use crate::pdu::{EsmClass, MessageState};
+
+/// How a message failed, which decides the stat a DR reports it with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FailureKind {
+    /// Delivery failed this time, but might succeed if retried (e.g. the
+    /// handset was switched off).  Reported as UNDELIV.
+    Transient,
+    /// Delivery can never succeed (e.g. the number does not exist).
+    /// Reported as REJECTD.
+    Permanent,
+}
+
+impl FailureKind {
+    pub fn message_state(&self) -> MessageState {
+        match self {
+            FailureKind::Transient => MessageState::Undeliverable,
+            FailureKind::Permanent => MessageState::Rejected,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeliveryReceipt {
+    pub id: String,
+    /// Number of messages originally submitted
+    pub sub: u32,
+    /// Number of messages delivered
+    pub dlvrd: u32,
+    /// When the message was submitted, as YYMMDDhhmm
+    pub submit_date: String,
+    /// When the message reached its final state, as YYMMDDhhmm
+    pub done_date: String,
+    pub stat: MessageState,
+    /// A network or SMSC specific error code
+    pub err: u16,
+    /// The start of the original message's text
+    pub text: String,
+}
+
+/// The DR includes at most this many characters of the original text
+const MAX_TEXT_CHARS: usize = 20;
+
+impl DeliveryReceipt {
+    /// A DR saying the message with this id was delivered
+    pub fn delivered(
+        id: &str,
+        submit_date: &str,
+        done_date: &str,
+        text: &str,
+    ) -> Self {
+        Self::new(
+            id,
+            1,
+            submit_date,
+            done_date,
+            MessageState::Delivered,
+            0,
+            text,
+        )
+    }
+
+    /// A DR saying the message with this id failed, with a stat saying
+    /// whether the failure was transient or permanent.
+    pub fn failed(
+        id: &str,
+        failure_kind: FailureKind,
+        err: u16,
+        submit_date: &str,
+        done_date: &str,
+        text: &str,
+    ) -> Self {
+        Self::new(
+            id,
+            0,
+            submit_date,
+            done_date,
+            failure_kind.message_state(),
+            err,
+            text,
+        )
+    }
+
+    fn new(
+        id: &str,
+        dlvrd: u32,
+        submit_date: &str,
+        done_date: &str,
+        stat: MessageState,
+        err: u16,
+        text: &str,
+    ) -> Self {
+        Self {
+            id: String::from(id),
+            sub: 1,
+            dlvrd,
+            submit_date: String::from(submit_date),
+            done_date: String::from(done_date),
+            stat,
+            err,
+            text: text.chars().take(MAX_TEXT_CHARS).collect(),
+        }
+    }
+
+    /// Read DR text such as "id:123 sub:001 dlvrd:001 submit date:2103301649
+    /// done date:2103301650 stat:DELIVRD err:000 text:hello".  Field names
+    /// are matched ignoring case.  id and stat are required; sub, dlvrd and
+    /// err default to 0, and the dates and text to empty, since some SMSCs
+    /// leave them out.  text is everything after "text:".
+    pub fn parse(short_message: &[u8]) -> Result<Self, ReceiptParseError> {
+        let receipt = str::from_utf8(short_message)
+            .map_err(|_| ReceiptParseError::NotText)?;
+        let (fields, text) = match find_key(receipt, "text:") {
+            Some((start, end)) => (&receipt[..start], &receipt[end..]),
+            None => (receipt, ""),
+        };
+
+        let id =
+            field(fields, "id:").ok_or(ReceiptParseError::Missing("id"))?;
+        let stat =
+            field(fields, "stat:").ok_or(ReceiptParseError::Missing("stat"))?;
+        Ok(Self {
+            id: String::from(id),
+            sub: number(fields, "sub:")?,
+            dlvrd: number(fields, "dlvrd:")?,
+            submit_date: String::from(
+                field(fields, "submit date:").unwrap_or(""),
+            ),
+            done_date: String::from(field(fields, "done date:").unwrap_or("")),
+            stat: MessageState::from_stat(stat)
+                .ok_or_else(|| ReceiptParseError::invalid("stat", stat))?,
+            err: number(fields, "err:")?,
+            text: String::from(text.trim_end()),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReceiptParseError {
+    NotText,
+    Missing(&'static str),
+    Invalid(&'static str, String),
+}
+
+impl ReceiptParseError {
+    fn invalid(field_name: &'static str, value: &str) -> Self {
+        ReceiptParseError::Invalid(field_name, String::from(value))
+    }
+}
+
+impl Display for ReceiptParseError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        let s = match self {
+            ReceiptParseError::NotText => {
+                String::from("Delivery receipt is not valid UTF-8 text.")
+            }
+            ReceiptParseError::Missing(field_name) => {
+                format!("Delivery receipt has no {} field.", field_name)
+            }
+            ReceiptParseError::Invalid(field_name, value) => format!(
+                "Delivery receipt has an invalid {} field: \"{}\".",
+                field_name, value
+            ),
+        };
+        formatter.write_str(&s)
+    }
+}
+
+impl error::Error for ReceiptParseError {}
+
+/// The start and end of the first "key" in receipt that is at its start or
+/// follows whitespace, ignoring case
+fn find_key(receipt: &str, key: &str) -> Option<(usize, usize)> {
+    // ASCII lowercasing keeps every byte where it was
+    let lower = receipt.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(key) {
+        let start = from + found;
+        if start == 0 || lower[..start].ends_with(char::is_whitespace) {
+            return Some((start, start + key.len()));
+        }
+        from = start + key.len();
+    }
+    None
+}
+
+/// The value following key, up to the next whitespace
+fn field<'a>(fields: &'a str, key: &str) -> Option<&'a str> {
+    let (_, end) = find_key(fields, key)?;
+    fields[end..].split(char::is_whitespace).next()
+}
+
+fn number<T: str::FromStr + Default>(
+    fields: &str,
+    key: &'static str,
+) -> Result<T, ReceiptParseError> {
+    let field_name = key.trim_end_matches(':');
+    match field(fields, key) {
+        None => Ok(T::default()),
+        Some(value) => value
+            .parse()
+            .map_err(|_| ReceiptParseError::invalid(field_name, value)),
+    }
+}
+
+/// Implemented for deliver_sm, which carries DRs from an SMSC to an ESME
+pub trait DeliveryReceiptExt {
+    /// The DR this PDU carries, or None if its esm_class says it is not a
+    /// DR, or its short_message cannot be parsed as one.
+    fn delivery_receipt(&self) -> Option<DeliveryReceipt>;
+}
+
+impl DeliveryReceiptExt for DeliverSmPdu {
+    fn delivery_receipt(&self) -> Option<DeliveryReceipt> {
+        let esm_class =
+            EsmClass::for_deliver_sm(self.0.esm_class.value).ok()?;
+        if !esm_class.is_delivery_receipt() {
+            return None;
+        }
+        DeliveryReceipt::parse(&self.0.short_message.value).ok()
+    }
+}
+
+/// The text to send as the short_message of a deliver_sm
+impl Display for DeliveryReceipt {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "id:{} sub:{:03} dlvrd:{:03} submit date:{} done date:{} \
+            stat:{} err:{:03} text:{}",
+            self.id,
+            self.sub,
+            self.dlvrd,
+            self.submit_date,
+            self.done_date,
+            self.stat,
+            self.err,
+            self.text
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::DeliverSmPduBuilder;
+    use smpp_pdu::pdu::DeliverEsmClass;
+
+    #[test]
+    fn delivered_dr_reports_delivrd() {
+        let dr = DeliveryReceipt::delivered(
+            "ab87J",
+            "2103301649",
+            "2103301650",
+            "hello",
+        );
+        assert_eq!(
+            dr.to_string(),
+            "id:ab87J sub:001 dlvrd:001 submit date:2103301649 \
+            done date:2103301650 stat:DELIVRD err:000 text:hello"
+        );
+    }
+
+    #[test]
+    fn transient_failure_dr_reports_undeliv() {
+        let dr = DeliveryReceipt::failed(
+            "ab87J",
+            FailureKind::Transient,
+            27,
+            "2103301649",
+            "2103301650",
+            "hello",
+        );
+        assert_eq!(
+            dr.to_string(),
+            "id:ab87J sub:001 dlvrd:000 submit date:2103301649 \
+            done date:2103301650 stat:UNDELIV err:027 text:hello"
+        );
+    }
+
+    #[test]
+    fn permanent_failure_dr_reports_rejectd() {
+        let dr = DeliveryReceipt::failed(
+            "ab87J",
+            FailureKind::Permanent,
+            1,
+            "2103301649",
+            "2103301650",
+            "hello",
+        );
+        assert_eq!(
+            dr.to_string(),
+            "id:ab87J sub:001 dlvrd:000 submit date:2103301649 \
+            done date:2103301650 stat:REJECTD err:001 text:hello"
+        );
+    }
+
+    #[test]
+    fn only_the_start_of_the_text_is_included() {
+        let dr = DeliveryReceipt::delivered(
+            "1",
+            "2103301649",
+            "2103301650",
+            "The quick brown fox jumps over the lazy dog",
+        );
+        assert_eq!(dr.text, "The quick brown fox ");
+    }
+
+    #[test]
+    fn full_receipt_is_parsed() {
+        let dr = DeliveryReceipt::parse(
+            b"id:ab87J sub:001 dlvrd:001 submit date:2103301649 \
+            done date:2103301650 stat:DELIVRD err:000 text:hello world",
+        )
+        .unwrap();
+        assert_eq!(
+            dr,
+            DeliveryReceipt {
+                id: String::from("ab87J"),
+                sub: 1,
+                dlvrd: 1,
+                submit_date: String::from("2103301649"),
+                done_date: String::from("2103301650"),
+                stat: MessageState::Delivered,
+                err: 0,
+                text: String::from("hello world"),
+            }
+        );
+    }
+
+    #[test]
+    fn built_receipts_parse_back_to_themselves() {
+        let dr = DeliveryReceipt::failed(
+            "ab87J",
+            FailureKind::Permanent,
+            27,
+            "2103301649",
+            "2103301650",
+            "id:1 stat:DELIVRD",
+        );
+        assert_eq!(DeliveryReceipt::parse(dr.to_string().as_bytes()), Ok(dr));
+    }
+
+    #[test]
+    fn minimal_receipt_is_parsed_with_defaults() {
+        let dr = DeliveryReceipt::parse(b"ID:12345 Stat:undeliv").unwrap();
+        assert_eq!(dr.id, "12345");
+        assert_eq!(dr.stat, MessageState::Undeliverable);
+        assert_eq!((dr.sub, dr.dlvrd, dr.err), (0, 0, 0));
+        assert_eq!(dr.submit_date, "");
+        assert_eq!(dr.text, "");
+    }
+
+    #[test]
+    fn non_receipts_are_rejected() {
+        assert_eq!(
+            DeliveryReceipt::parse(b"hello, are you free later?"),
+            Err(ReceiptParseError::Missing("id"))
+        );
+        assert_eq!(
+            DeliveryReceipt::parse(b"id:1 stat:GONE"),
+            Err(ReceiptParseError::invalid("stat", "GONE"))
+        );
+        assert_eq!(
+            DeliveryReceipt::parse(b"id:1 stat:DELIVRD err:x1"),
+            Err(ReceiptParseError::invalid("err", "x1"))
+        );
+        assert_eq!(
+            DeliveryReceipt::parse(b"\xff"),
+            Err(ReceiptParseError::NotText)
+        );
+    }
+
+    fn deliver_sm(
+        esm_class: DeliverEsmClass,
+        short_message: &[u8],
+    ) -> DeliverSmPdu {
+        DeliverSmPduBuilder::new()
+            .esm_class(esm_class as u8)
+            .short_message(short_message)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn deliver_sm_receipt_is_found_only_if_esm_class_says_so() {
+        let text = b"id:7 sub:001 dlvrd:001 submit date:2103301649 \
+            done date:2103301650 stat:DELIVRD err:000 text:";
+        let dr = deliver_sm(DeliverEsmClass::SmscDeliveryReceipt, text)
+            .delivery_receipt()
+            .unwrap();
+        assert_eq!(dr.id, "7");
+
+        assert_eq!(
+            deliver_sm(DeliverEsmClass::Default, text).delivery_receipt(),
+            None
+        );
+        assert_eq!(
+            deliver_sm(DeliverEsmClass::SmscDeliveryReceipt, b"hello")
+                .delivery_receipt(),
+            None
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::formats::{Integer1, OctetString};
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::pdu::{gsm7, DataCoding};
+
+/// short_message may be at most 254 octets.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.4.1
+pub(crate) const MAX_LENGTH_SHORT_MESSAGE: usize = 254;
+
+/// Encode text in the GSM 7-bit default alphabet (plus its extension
+/// table), packed 8 septets to every 7 octets.
+pub fn encode_gsm7(text: &str) -> Result<Vec<u8>, EncodeError> {
+    gsm7::encode_septets(text)
+        .map(|septets| gsm7::pack(&septets))
+        .map_err(|c| EncodeError::UnsupportedCharacter(c, DataCoding::Gsm7))
+}
+
+impl DataCoding {
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodeError> {
+        let unsupported = |c| EncodeError::UnsupportedCharacter(c, *self);
+        match self {
+            DataCoding::Gsm7 => encode_gsm7(text),
+            DataCoding::Ia5 => match text.chars().find(|c| !c.is_ascii()) {
+                Some(c) => Err(unsupported(c)),
+                None => Ok(text.as_bytes().to_vec()),
+            },
+            DataCoding::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(u32::from(c)).map_err(|_| unsupported(c)))
+                .collect(),
+            DataCoding::Ucs2 => {
+                Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect())
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EncodeError {
+    UnsupportedCharacter(char, DataCoding),
+    TooLong(usize),
+}
+
+impl Display for EncodeError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        let s = match self {
+            EncodeError::UnsupportedCharacter(c, data_coding) => format!(
+                "Unable to encode character {:?} using data_coding {:?}.",
+                c, data_coding
+            ),
+            EncodeError::TooLong(len) => format!(
+                "Encoded text is {} octets long, but short_message must be \
+                at most {} octets.",
+                len, MAX_LENGTH_SHORT_MESSAGE
+            ),
+        };
+        formatter.write_str(&s)
+    }
+}
+
+impl error::Error for EncodeError {}
+
+/// Set the short_message and data_coding of a PDU together, from text.
+/// Intended to be chained onto a constructor, e.g.
+/// `SubmitSmPdu::new(..., b"", ...)?.with_text("hi", DataCoding::Gsm7)`.
+pub trait WithText: Sized {
+    fn with_text(
+        self,
+        text: &str,
+        data_coding: DataCoding,
+    ) -> Result<Self, EncodeError>;
+}
+
+impl WithText for SmData {
+    fn with_text(
+        mut self,
+        text: &str,
+        data_coding: DataCoding,
+    ) -> Result<Self, EncodeError> {
+        let value = data_coding.encode(text)?;
+        if value.len() > MAX_LENGTH_SHORT_MESSAGE {
+            return Err(EncodeError::TooLong(value.len()));
+        }
+        self.data_coding = Integer1::new(data_coding as u8);
+        self.short_message = OctetString { value };
+        Ok(self)
+    }
+}
+
+impl WithText for SubmitSmPdu {
+    fn with_text(
+        self,
+        text: &str,
+        data_coding: DataCoding,
+    ) -> Result<Self, EncodeError> {
+        self.0.with_text(text, data_coding).map(Self)
+    }
+}
+
+impl WithText for DeliverSmPdu {
+    fn with_text(
+        self,
+        text: &str,
+        data_coding: DataCoding,
+    ) -> Result<Self, EncodeError> {
+        self.0.with_text(text, data_coding).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::DecodedText;
+    use smpp_pdu::pdu::tlvs::Tlvs;
+    use smpp_pdu::pdu::SubmitEsmClass;
+
+    fn submit_sm() -> SubmitSmPdu {
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "src",
+            0,
+            0,
+            "dest",
+            SubmitEsmClass::Default as u8,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            0x04,
+            0,
+            b"",
+            Tlvs::new(),
+        )
+        .unwrap()
+    }
+
+    fn round_trip(text: &str, data_coding: DataCoding) -> String {
+        let pdu = submit_sm().with_text(text, data_coding).unwrap();
+        assert_eq!(pdu.0.data_coding.value, data_coding as u8);
+        pdu.decoded_text().unwrap()
+    }
+
+    #[test]
+    fn gsm7_round_trips_common_strings() {
+        for text in [
+            "",
+            "Hello world!",
+            "Price: 5€ (or £4.50) @ 10% off",
+            "{[~|^]}\\ ÆØÅ æøå ΔΣΩ",
+            "abcdefg",
+            "abcdefg@",
+        ] {
+            assert_eq!(round_trip(text, DataCoding::Gsm7), text);
+        }
+    }
+
+    #[test]
+    fn euro_sign_uses_the_extension_table() {
+        assert_eq!(encode_gsm7("€").unwrap(), vec![0x9b, 0x32]);
+    }
+
+    #[test]
+    fn other_codings_round_trip() {
+        assert_eq!(round_trip("hello", DataCoding::Ia5), "hello");
+        assert_eq!(round_trip("café £5", DataCoding::Latin1), "café £5");
+        assert_eq!(round_trip("Hi €😀", DataCoding::Ucs2), "Hi €😀");
+    }
+
+    #[test]
+    fn unsupported_character_is_an_error() {
+        assert_eq!(
+            encode_gsm7("hi 😀").unwrap_err(),
+            EncodeError::UnsupportedCharacter('😀', DataCoding::Gsm7)
+        );
+        assert_eq!(
+            submit_sm()
+                .with_text("€", DataCoding::Latin1)
+                .unwrap_err()
+                .to_string(),
+            "Unable to encode character '€' using data_coding Latin1."
+        );
+    }
+
+    #[test]
+    fn text_too_long_for_short_message_is_an_error() {
+        let text = "x".repeat(255);
+        assert_eq!(
+            submit_sm().with_text(&text, DataCoding::Ia5).unwrap_err(),
+            EncodeError::TooLong(255)
+        );
+        // 291 septets pack into 255 octets
+        let text = "x".repeat(291);
+        assert_eq!(
+            submit_sm().with_text(&text, DataCoding::Gsm7).unwrap_err(),
+            EncodeError::TooLong(255)
+        );
+    }
+}
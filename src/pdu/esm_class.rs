@@ -0,0 +1,137 @@
+//! Checking the esm_class of a submit_sm or deliver_sm against
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.12.  smpp-pdu
+//! accepts any value, and its SubmitEsmClass and DeliverEsmClass enums
+//! only name the commonest ones.
+
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io;
+
+use crate::pdu::UDHI;
+
+/// Bits 5-2 of esm_class: the message type
+const MESSAGE_TYPE: u8 = 0b00111100;
+
+/// The message type of an SMSC delivery receipt
+const SMSC_DELIVERY_RECEIPT: u8 = 0b000100;
+
+/// Bit 7 of esm_class: set reply path
+const REPLY_PATH: u8 = 0b10000000;
+
+/// The message types a submit_sm may have: default, ESME delivery
+/// acknowledgement and ESME manual/user acknowledgement
+const SUBMIT_MESSAGE_TYPES: [u8; 3] = [0b000000, 0b001000, 0b010000];
+
+/// The message types a deliver_sm may have: default, SMSC delivery
+/// receipt, SME delivery acknowledgement, SME manual/user acknowledgement,
+/// conversation abort and intermediate delivery notification
+const DELIVER_MESSAGE_TYPES: [u8; 6] = [
+    0b000000,
+    SMSC_DELIVERY_RECEIPT,
+    0b001000,
+    0b010000,
+    0b011000,
+    0b100000,
+];
+
+/// An esm_class that has been checked to use no reserved message type.
+/// All messaging modes and GSM features are defined for submit_sm, and
+/// deliver_sm ignores its messaging mode bits, so only the message type
+/// can be invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EsmClass(u8);
+
+impl EsmClass {
+    pub fn for_submit_sm(value: u8) -> Result<Self, PduParseError> {
+        check(value, &SUBMIT_MESSAGE_TYPES, "submit_sm")
+    }
+
+    pub fn for_deliver_sm(value: u8) -> Result<Self, PduParseError> {
+        check(value, &DELIVER_MESSAGE_TYPES, "deliver_sm")
+    }
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether short_message starts with a User Data Header
+    pub fn udhi(&self) -> bool {
+        self.0 & UDHI != 0
+    }
+
+    pub fn reply_path(&self) -> bool {
+        self.0 & REPLY_PATH != 0
+    }
+
+    /// Whether a deliver_sm with this esm_class carries an SMSC delivery
+    /// receipt
+    pub fn is_delivery_receipt(&self) -> bool {
+        self.0 & MESSAGE_TYPE == SMSC_DELIVERY_RECEIPT
+    }
+}
+
+fn check(
+    value: u8,
+    message_types: &[u8],
+    pdu_name: &str,
+) -> Result<EsmClass, PduParseError> {
+    if message_types.contains(&(value & MESSAGE_TYPE)) {
+        Ok(EsmClass(value))
+    } else {
+        Err(PduParseError::new(PduParseErrorBody::OtherIoError(
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "esm_class {:#04X} has a message type that is reserved \
+                    in a {}.",
+                    value, pdu_name
+                ),
+            ),
+        ))
+        .into_with_field_name("esm_class"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::{DeliverEsmClass, SubmitEsmClass};
+
+    #[test]
+    fn default_esm_class_is_valid_in_both() {
+        let submit = EsmClass::for_submit_sm(SubmitEsmClass::Default as u8);
+        assert_eq!(submit.unwrap().value(), 0);
+        let deliver = EsmClass::for_deliver_sm(DeliverEsmClass::Default as u8);
+        assert_eq!(deliver.unwrap().value(), 0);
+    }
+
+    #[test]
+    fn delivery_receipt_is_valid_only_in_deliver_sm() {
+        let receipt = DeliverEsmClass::SmscDeliveryReceipt as u8;
+        assert!(EsmClass::for_deliver_sm(receipt).is_ok());
+        assert!(EsmClass::for_submit_sm(receipt).is_err());
+    }
+
+    #[test]
+    fn reserved_message_type_is_rejected_naming_esm_class() {
+        // Message type 0b1111 is reserved, even with store and forward mode
+        // and UDHI, which are both fine
+        let e = EsmClass::for_submit_sm(0b01111111).unwrap_err();
+        assert!(
+            e.to_string().ends_with(
+                "field_name=esm_class): IO error: esm_class 0x7F has a \
+                message type that is reserved in a submit_sm."
+            ),
+            "{}",
+            e
+        );
+        assert!(EsmClass::for_deliver_sm(0b00101100).is_err());
+    }
+
+    #[test]
+    fn gsm_features_are_read_from_the_top_bits() {
+        let esm_class = EsmClass::for_submit_sm(0b11000011).unwrap();
+        assert!(esm_class.udhi());
+        assert!(esm_class.reply_path());
+        assert!(!EsmClass::for_submit_sm(0x00).unwrap().udhi());
+    }
+}
@@ -0,0 +1,848 @@
+use smpp_pdu::pdu::formats::Integer4;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{Pdu, PduParseError, PduParseErrorBody};
+use std::io;
+use std::io::Read;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::pdu::command_id::*;
+use crate::pdu::operations::alert_notification::AlertNotificationPdu;
+use crate::pdu::operations::cancel_sm::CancelSmPdu;
+use crate::pdu::operations::data_sm::{DataSmPdu, DataSmRespPdu};
+use crate::pdu::operations::deliver_sm_resp::DeliverSmRespPdu;
+use crate::pdu::operations::outbind::OutbindPdu;
+use crate::pdu::operations::query_sm::QuerySmPdu;
+use crate::pdu::operations::query_sm_resp::QuerySmRespPdu;
+use crate::pdu::operations::submit_multi::{
+    SubmitMultiPdu, SubmitMultiRespPdu,
+};
+use crate::pdu::pdu_ext::{clone_tlvs, write_pdu};
+
+/// The bodies of PDUs that the smpp-pdu crate does not support yet.
+#[derive(Debug, PartialEq)]
+pub enum ExtraPduBody {
+    AlertNotification(AlertNotificationPdu),
+    CancelSm(CancelSmPdu),
+    CancelSmResp,
+    DataSm(DataSmPdu),
+    /// None if command_status is not zero, in which case we ignore any
+    /// body the client sent
+    DataSmResp(Option<DataSmRespPdu>),
+    /// None if command_status is not zero, in which case we ignore any
+    /// body the client sent
+    DeliverSmResp(Option<DeliverSmRespPdu>),
+    /// An enquire_link_resp carrying TLVs (e.g. our banner), which
+    /// smpp-pdu's EnquireLinkRespPdu cannot.  We still read
+    /// enquire_link_resp with smpp-pdu: see ExtraPdu::handles.
+    EnquireLinkResp(Tlvs),
+    Outbind(OutbindPdu),
+    QuerySm(QuerySmPdu),
+    /// None if command_status is not zero, in which case we ignore any
+    /// body the SMSC sent
+    QuerySmResp(Option<QuerySmRespPdu>),
+    SubmitMulti(SubmitMultiPdu),
+    /// None if command_status is not zero, in which case we ignore any
+    /// body the SMSC sent
+    SubmitMultiResp(Option<SubmitMultiRespPdu>),
+    Unbind,
+    UnbindResp,
+}
+
+// Not derived, because EnquireLinkResp's Tlvs is not Clone
+impl Clone for ExtraPduBody {
+    fn clone(&self) -> Self {
+        match self {
+            ExtraPduBody::AlertNotification(body) => {
+                ExtraPduBody::AlertNotification(body.clone())
+            }
+            ExtraPduBody::CancelSm(body) => {
+                ExtraPduBody::CancelSm(body.clone())
+            }
+            ExtraPduBody::CancelSmResp => ExtraPduBody::CancelSmResp,
+            ExtraPduBody::DataSm(body) => ExtraPduBody::DataSm(body.clone()),
+            ExtraPduBody::DataSmResp(body) => {
+                ExtraPduBody::DataSmResp(body.clone())
+            }
+            ExtraPduBody::DeliverSmResp(body) => {
+                ExtraPduBody::DeliverSmResp(body.clone())
+            }
+            ExtraPduBody::EnquireLinkResp(tlvs) => {
+                ExtraPduBody::EnquireLinkResp(clone_tlvs(tlvs))
+            }
+            ExtraPduBody::Outbind(body) => ExtraPduBody::Outbind(body.clone()),
+            ExtraPduBody::QuerySm(body) => ExtraPduBody::QuerySm(body.clone()),
+            ExtraPduBody::QuerySmResp(body) => {
+                ExtraPduBody::QuerySmResp(body.clone())
+            }
+            ExtraPduBody::SubmitMulti(body) => {
+                ExtraPduBody::SubmitMulti(body.clone())
+            }
+            ExtraPduBody::SubmitMultiResp(body) => {
+                ExtraPduBody::SubmitMultiResp(body.clone())
+            }
+            ExtraPduBody::Unbind => ExtraPduBody::Unbind,
+            ExtraPduBody::UnbindResp => ExtraPduBody::UnbindResp,
+        }
+    }
+}
+
+/// Vendor-specific TLV tag (0x1400 to 0x3FFF are reserved for SMSC
+/// vendors) for the identification string we add to enquire_link_resp.
+pub const BANNER_TLV_TAG: u16 = 0x1400;
+
+/// A PDU as read from a connection: either one smpp-pdu understands, or
+/// one of ours.
+#[derive(Debug)]
+pub enum AnyPdu {
+    Pdu(Pdu),
+    Extra(ExtraPdu),
+}
+
+impl AnyPdu {
+    pub fn command_id(&self) -> u32 {
+        match self {
+            AnyPdu::Pdu(pdu) => pdu.command_id().value,
+            AnyPdu::Extra(pdu) => pdu.command_id().value,
+        }
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        match self {
+            AnyPdu::Pdu(pdu) => pdu.sequence_number.value,
+            AnyPdu::Extra(pdu) => pdu.sequence_number.value,
+        }
+    }
+
+    pub fn set_sequence_number(&mut self, sequence_number: u32) {
+        let sequence_number = Integer4::new(sequence_number);
+        match self {
+            AnyPdu::Pdu(pdu) => pdu.sequence_number = sequence_number,
+            AnyPdu::Extra(pdu) => pdu.sequence_number = sequence_number,
+        }
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin + ?Sized>(
+        &self,
+        stream: &mut W,
+    ) -> io::Result<()> {
+        match self {
+            AnyPdu::Pdu(pdu) => write_pdu(pdu, stream).await,
+            AnyPdu::Extra(pdu) => pdu.write(stream).await,
+        }
+    }
+}
+
+impl From<Pdu> for AnyPdu {
+    fn from(pdu: Pdu) -> Self {
+        AnyPdu::Pdu(pdu)
+    }
+}
+
+impl From<ExtraPdu> for AnyPdu {
+    fn from(pdu: ExtraPdu) -> Self {
+        AnyPdu::Extra(pdu)
+    }
+}
+
+/// A PDU whose body is an ExtraPduBody.  Mirrors smpp_pdu::pdu::Pdu.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtraPdu {
+    pub command_status: Integer4,
+    pub sequence_number: Integer4,
+    body: ExtraPduBody,
+}
+
+impl ExtraPdu {
+    pub fn new(
+        command_status: u32,
+        sequence_number: u32,
+        body: ExtraPduBody,
+    ) -> Self {
+        Self {
+            command_status: Integer4::new(command_status),
+            sequence_number: Integer4::new(sequence_number),
+            body,
+        }
+    }
+
+    /// True if PDUs with this command_id should be parsed with
+    /// ExtraPdu::parse rather than Pdu::parse.
+    pub fn handles(command_id: u32) -> bool {
+        matches!(
+            command_id,
+            QUERY_SM
+                | QUERY_SM_RESP
+                | UNBIND
+                | UNBIND_RESP
+                | CANCEL_SM
+                | CANCEL_SM_RESP
+                | DELIVER_SM_RESP
+                | OUTBIND
+                | SUBMIT_MULTI
+                | SUBMIT_MULTI_RESP
+                | ALERT_NOTIFICATION
+                | DATA_SM
+                | DATA_SM_RESP
+        )
+    }
+
+    /// Parse a PDU whose length has already been checked, e.g. with
+    /// Pdu::check.
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let command_length = Integer4::read(bytes)?;
+        let mut bytes =
+            bytes.take(u64::from(command_length.value.saturating_sub(4)));
+        let command_id = Integer4::read(&mut bytes)?;
+        let command_status = Integer4::read(&mut bytes).map_err(|e| {
+            PduParseError::from(e).into_with_header(
+                Some(command_id.value),
+                None,
+                None,
+            )
+        })?;
+        let sequence_number = Integer4::read(&mut bytes).map_err(|e| {
+            PduParseError::from(e).into_with_header(
+                Some(command_id.value),
+                Some(command_status.value),
+                None,
+            )
+        })?;
+
+        let body =
+            parse_body(&mut bytes, command_id.value, command_status.value)
+                .and_then(|body| {
+                    // There should be no bytes left over
+                    let mut buf = [0; 1];
+                    if bytes.read(&mut buf)? == 0 {
+                        Ok(body)
+                    } else {
+                        Err(PduParseError::new(
+                            PduParseErrorBody::LengthLongerThanPdu(
+                                command_length.value,
+                            ),
+                        ))
+                    }
+                })
+                .map_err(|e| {
+                    e.into_with_header(
+                        Some(command_id.value),
+                        Some(command_status.value),
+                        Some(sequence_number.value),
+                    )
+                })?;
+
+        Ok(Self {
+            command_status,
+            sequence_number,
+            body,
+        })
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin + ?Sized>(
+        &self,
+        stream: &mut W,
+    ) -> io::Result<()> {
+        // command_length goes here, so that we write the PDU with one
+        // write_all(), and other writers can't get between its parts
+        let mut buf = vec![0; 4];
+        self.command_id().write(&mut buf).await?;
+        self.command_status.write(&mut buf).await?;
+        self.sequence_number.write(&mut buf).await?;
+        match &self.body {
+            ExtraPduBody::AlertNotification(body) => {
+                body.write(&mut buf).await?
+            }
+            ExtraPduBody::CancelSm(body) => body.write(&mut buf).await?,
+            ExtraPduBody::DataSm(body) => body.write(&mut buf).await?,
+            ExtraPduBody::DataSmResp(Some(body)) => {
+                body.write(&mut buf).await?
+            }
+            ExtraPduBody::DeliverSmResp(Some(body)) => {
+                body.write(&mut buf).await?
+            }
+            ExtraPduBody::EnquireLinkResp(tlvs) => tlvs.write(&mut buf).await?,
+            ExtraPduBody::Outbind(body) => body.write(&mut buf).await?,
+            ExtraPduBody::QuerySm(body) => body.write(&mut buf).await?,
+            ExtraPduBody::QuerySmResp(Some(body)) => {
+                body.write(&mut buf).await?
+            }
+            ExtraPduBody::SubmitMulti(body) => body.write(&mut buf).await?,
+            ExtraPduBody::SubmitMultiResp(Some(body)) => {
+                body.write(&mut buf).await?
+            }
+            // Header only
+            ExtraPduBody::CancelSmResp
+            | ExtraPduBody::DataSmResp(None)
+            | ExtraPduBody::DeliverSmResp(None)
+            | ExtraPduBody::QuerySmResp(None)
+            | ExtraPduBody::SubmitMultiResp(None)
+            | ExtraPduBody::Unbind
+            | ExtraPduBody::UnbindResp => {}
+        }
+        let command_length = buf.len() as u32;
+        buf[..4].copy_from_slice(&command_length.to_be_bytes());
+        stream.write_all(&buf).await
+    }
+
+    pub fn command_id(&self) -> Integer4 {
+        Integer4::new(match self.body {
+            ExtraPduBody::AlertNotification(_) => ALERT_NOTIFICATION,
+            ExtraPduBody::CancelSm(_) => CANCEL_SM,
+            ExtraPduBody::CancelSmResp => CANCEL_SM_RESP,
+            ExtraPduBody::DataSm(_) => DATA_SM,
+            ExtraPduBody::DataSmResp(_) => DATA_SM_RESP,
+            ExtraPduBody::DeliverSmResp(_) => DELIVER_SM_RESP,
+            ExtraPduBody::EnquireLinkResp(_) => ENQUIRE_LINK_RESP,
+            ExtraPduBody::Outbind(_) => OUTBIND,
+            ExtraPduBody::QuerySm(_) => QUERY_SM,
+            ExtraPduBody::QuerySmResp(_) => QUERY_SM_RESP,
+            ExtraPduBody::SubmitMulti(_) => SUBMIT_MULTI,
+            ExtraPduBody::SubmitMultiResp(_) => SUBMIT_MULTI_RESP,
+            ExtraPduBody::Unbind => UNBIND,
+            ExtraPduBody::UnbindResp => UNBIND_RESP,
+        })
+    }
+
+    pub fn body(&self) -> &ExtraPduBody {
+        &self.body
+    }
+}
+
+fn parse_body(
+    bytes: &mut dyn io::BufRead,
+    command_id: u32,
+    command_status: u32,
+) -> Result<ExtraPduBody, PduParseError> {
+    match command_id {
+        QUERY_SM => QuerySmPdu::parse(bytes).map(ExtraPduBody::QuerySm),
+        QUERY_SM_RESP => {
+            if command_status == 0x00000000 {
+                QuerySmRespPdu::parse(bytes)
+                    .map(|body| ExtraPduBody::QuerySmResp(Some(body)))
+            } else {
+                skip_body(bytes).map(|_| ExtraPduBody::QuerySmResp(None))
+            }
+        }
+        UNBIND => Ok(ExtraPduBody::Unbind),
+        UNBIND_RESP => Ok(ExtraPduBody::UnbindResp),
+        CANCEL_SM => CancelSmPdu::parse(bytes).map(ExtraPduBody::CancelSm),
+        CANCEL_SM_RESP => Ok(ExtraPduBody::CancelSmResp),
+        DELIVER_SM_RESP => {
+            if command_status == 0x00000000 {
+                DeliverSmRespPdu::parse(bytes)
+                    .map(|body| ExtraPduBody::DeliverSmResp(Some(body)))
+            } else {
+                skip_body(bytes).map(|_| ExtraPduBody::DeliverSmResp(None))
+            }
+        }
+        ENQUIRE_LINK_RESP => {
+            Ok(Tlvs::read(bytes).map(ExtraPduBody::EnquireLinkResp)?)
+        }
+        OUTBIND => OutbindPdu::parse(bytes).map(ExtraPduBody::Outbind),
+        SUBMIT_MULTI => {
+            SubmitMultiPdu::parse(bytes).map(ExtraPduBody::SubmitMulti)
+        }
+        SUBMIT_MULTI_RESP => {
+            if command_status == 0x00000000 {
+                SubmitMultiRespPdu::parse(bytes)
+                    .map(|body| ExtraPduBody::SubmitMultiResp(Some(body)))
+            } else {
+                skip_body(bytes).map(|_| ExtraPduBody::SubmitMultiResp(None))
+            }
+        }
+        ALERT_NOTIFICATION => AlertNotificationPdu::parse(bytes)
+            .map(ExtraPduBody::AlertNotification),
+        DATA_SM => DataSmPdu::parse(bytes).map(ExtraPduBody::DataSm),
+        DATA_SM_RESP => {
+            if command_status == 0x00000000 {
+                DataSmRespPdu::parse(bytes)
+                    .map(|body| ExtraPduBody::DataSmResp(Some(body)))
+            } else {
+                skip_body(bytes).map(|_| ExtraPduBody::DataSmResp(None))
+            }
+        }
+        _ => Err(PduParseError::new(PduParseErrorBody::UnknownCommandId)),
+    }
+}
+
+/// Read and discard the rest of a response body.  Responses with a
+/// non-zero command_status may or may not carry their mandatory fields
+/// (e.g. a NULL message_id), so we accept either.
+fn skip_body(bytes: &mut dyn io::BufRead) -> Result<(), PduParseError> {
+    io::copy(bytes, &mut io::sink())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::{
+        AlertNotificationPdu, DestAddress, MessageState, UnsuccessSme,
+    };
+    use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+
+    async fn write(pdu: &ExtraPdu) -> Vec<u8> {
+        let mut buf: Vec<u8> = Vec::new();
+        pdu.write(&mut buf).await.unwrap();
+        buf
+    }
+
+    fn parse(bytes: &[u8]) -> Result<ExtraPdu, PduParseError> {
+        ExtraPdu::parse(&mut io::BufReader::new(bytes))
+    }
+
+    #[tokio::test]
+    async fn write_unbind() {
+        assert_eq!(
+            write(&ExtraPdu::new(0, 0x12, ExtraPduBody::Unbind)).await,
+            b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x12"
+        );
+    }
+
+    #[tokio::test]
+    async fn query_sm_resp_reporting_delivered_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x22,
+            ExtraPduBody::QuerySmResp(Some(
+                QuerySmRespPdu::new(
+                    "msg1",
+                    "210330164900000+",
+                    MessageState::Delivered,
+                    0,
+                )
+                .unwrap(),
+            )),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x28\x80\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x22\
+            msg1\x00210330164900000+\x00\x02\x00"
+        );
+        let parsed = parse(&bytes).unwrap();
+        assert_eq!(parsed, pdu);
+        match parsed.body() {
+            ExtraPduBody::QuerySmResp(Some(body)) => {
+                assert_eq!(body.message_state, MessageState::Delivered);
+                assert_eq!(body.error_code.value, 0);
+            }
+            _ => panic!("Expected query_sm_resp"),
+        }
+    }
+
+    #[tokio::test]
+    async fn data_sm_carrying_a_receipt_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x24,
+            ExtraPduBody::DataSm(
+                DataSmPdu::new(
+                    "",
+                    1,
+                    1,
+                    "447000123123",
+                    0,
+                    0,
+                    "MyCompany",
+                    0x04,
+                    0,
+                    0,
+                    Tlvs::from(
+                        &[Tlv::new(
+                            KnownTlvTag::receipted_message_id,
+                            b"msg1\0",
+                        )][..],
+                    ),
+                )
+                .unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x38\x00\x00\x01\x03\x00\x00\x00\x00\x00\x00\x00\x24\
+            \x00\x01\x01447000123123\x00\x00\x00MyCompany\x00\x04\x00\x00\
+            \x00\x1e\x00\x05msg1\x00"
+        );
+        let parsed = parse(&bytes).unwrap();
+        assert_eq!(parsed, pdu);
+        match parsed.body() {
+            ExtraPduBody::DataSm(body) => assert_eq!(
+                body.extract_receipted_message_id().as_deref(),
+                Some("msg1")
+            ),
+            _ => panic!("Expected data_sm"),
+        }
+    }
+
+    #[tokio::test]
+    async fn data_sm_resp_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x24,
+            ExtraPduBody::DataSmResp(Some(
+                DataSmRespPdu::new("msg2", Tlvs::new()).unwrap(),
+            )),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x15\x80\x00\x01\x03\x00\x00\x00\x00\x00\x00\x00\x24\
+            msg2\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[test]
+    fn failed_data_sm_resp_with_body_parses() {
+        let pdu = parse(
+            b"\x00\x00\x00\x11\x80\x00\x01\x03\x00\x00\x00\x08\x00\x00\x00\x24\
+            \x00",
+        )
+        .unwrap();
+        assert_eq!(pdu.command_status.value, 0x08);
+        assert_eq!(pdu.body(), &ExtraPduBody::DataSmResp(None));
+    }
+
+    #[tokio::test]
+    async fn query_sm_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x21,
+            ExtraPduBody::QuerySm(
+                QuerySmPdu::new("msg1", 1, 1, "447000123123").unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x24\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x21\
+            msg1\x00\x01\x01447000123123\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn cancel_sm_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x23,
+            ExtraPduBody::CancelSm(
+                CancelSmPdu::new(
+                    "",
+                    "msg1",
+                    1,
+                    1,
+                    "447000123123",
+                    1,
+                    1,
+                    "447111222222",
+                )
+                .unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x34\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x23\
+            \x00msg1\x00\x01\x01447000123123\x00\x01\x01447111222222\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn cancel_sm_resp_round_trips() {
+        let pdu = ExtraPdu::new(0, 0x23, ExtraPduBody::CancelSmResp);
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x10\x80\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x23"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn submit_multi_to_two_destinations_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x24,
+            ExtraPduBody::SubmitMulti(
+                SubmitMultiPdu::new(
+                    "",
+                    1,
+                    1,
+                    "447000123123",
+                    vec![
+                        DestAddress::sme_address(1, 1, "447111222222").unwrap(),
+                        DestAddress::distribution_list("friends").unwrap(),
+                    ],
+                    0,
+                    0,
+                    0,
+                    "",
+                    "",
+                    1,
+                    0,
+                    0,
+                    0,
+                    b"hi",
+                    Tlvs::new(),
+                )
+                .unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x46\x00\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x24\
+            \x00\x01\x01447000123123\x00\
+            \x02\
+            \x01\x01\x01447111222222\x00\
+            \x02friends\x00\
+            \x00\x00\x00\x00\x00\x01\x00\x00\x00\x02hi"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[test]
+    fn extra_pdus_with_tlvs_can_be_cloned() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x25,
+            ExtraPduBody::EnquireLinkResp(Tlvs::from(&[Tlv::new_unknown(
+                BANNER_TLV_TAG,
+                b"rust-smpp",
+            )])),
+        );
+        assert_eq!(pdu.clone(), pdu);
+    }
+
+    #[tokio::test]
+    async fn submit_multi_resp_with_one_failed_destination_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x24,
+            ExtraPduBody::SubmitMultiResp(Some(
+                SubmitMultiRespPdu::new(
+                    "msg1",
+                    vec![UnsuccessSme::new(1, 1, "447111222222", 0x0000000b)
+                        .unwrap()],
+                )
+                .unwrap(),
+            )),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x29\x80\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x24\
+            msg1\x00\
+            \x01\
+            \x01\x01447111222222\x00\x00\x00\x00\x0b"
+        );
+        let parsed = parse(&bytes).unwrap();
+        assert_eq!(parsed, pdu);
+        match parsed.body() {
+            ExtraPduBody::SubmitMultiResp(Some(body)) => {
+                assert_eq!(body.unsuccess_smes.len(), 1);
+                assert_eq!(
+                    body.unsuccess_smes[0].error_status_code.value,
+                    0x0000000b
+                );
+            }
+            _ => panic!("Expected submit_multi_resp"),
+        }
+    }
+
+    #[test]
+    fn failed_submit_multi_resp_with_unsuccess_smes_parses() {
+        let pdu = parse(
+            b"\x00\x00\x00\x29\x80\x00\x00\x21\x00\x00\x00\x0b\x00\x00\x00\x24\
+            msg1\x00\
+            \x01\
+            \x01\x01447111222222\x00\x00\x00\x00\x0b",
+        )
+        .unwrap();
+        assert_eq!(
+            pdu,
+            ExtraPdu::new(0x0b, 0x24, ExtraPduBody::SubmitMultiResp(None))
+        );
+    }
+
+    #[test]
+    fn submit_multi_with_unknown_dest_flag_fails_to_parse() {
+        let err = parse(
+            b"\x00\x00\x00\x17\x00\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x24\
+            \x00\x00\x00\x00\x01\x03\x00",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("dest_flag"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn alert_notification_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x25,
+            ExtraPduBody::AlertNotification(
+                AlertNotificationPdu::new(
+                    1,
+                    1,
+                    "447000123123",
+                    1,
+                    1,
+                    "447111222222",
+                    Some(0),
+                )
+                .unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x33\x00\x00\x01\x02\x00\x00\x00\x00\x00\x00\x00\x25\
+            \x01\x01447000123123\x00\x01\x01447111222222\x00\
+            \x04\x22\x00\x01\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn alert_notification_without_tlv_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x25,
+            ExtraPduBody::AlertNotification(
+                AlertNotificationPdu::new(1, 1, "4470", 1, 1, "4471", None)
+                    .unwrap(),
+            ),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x1e\x00\x00\x01\x02\x00\x00\x00\x00\x00\x00\x00\x25\
+            \x01\x014470\x00\x01\x014471\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn outbind_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x01,
+            ExtraPduBody::Outbind(OutbindPdu::new("rust_smpp", "pw").unwrap()),
+        );
+        let bytes = write(&pdu).await;
+
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x1d\x00\x00\x00\x0b\x00\x00\x00\x00\x00\x00\x00\x01\
+            rust_smpp\x00pw\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[test]
+    fn query_sm_resp_with_unknown_message_state_fails_to_parse() {
+        let err = parse(
+            b"\x00\x00\x00\x18\x80\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x22\
+            msg1\x00\x00\x09\x00",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("message_state"), "{}", err);
+    }
+
+    #[test]
+    fn failed_query_sm_resp_with_body_parses() {
+        let pdu = parse(
+            b"\x00\x00\x00\x18\x80\x00\x00\x03\x00\x00\x00\x67\x00\x00\x00\x22\
+            msg1\x00\x00\x00\x00",
+        )
+        .unwrap();
+        assert_eq!(
+            pdu,
+            ExtraPdu::new(0x67, 0x22, ExtraPduBody::QuerySmResp(None))
+        );
+    }
+
+    #[test]
+    fn unknown_command_id_fails_to_parse() {
+        let err = parse(
+            b"\x00\x00\x00\x10\x00\x00\x01\x06\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .unwrap_err();
+        assert_eq!(err.command_id, Some(0x00000106));
+        assert_eq!(err.status(), 0x00000003);
+    }
+
+    #[tokio::test]
+    async fn enquire_link_resp_with_banner_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x26,
+            ExtraPduBody::EnquireLinkResp(Tlvs::from(&[Tlv::new_unknown(
+                BANNER_TLV_TAG,
+                b"smsc 1.0",
+            )])),
+        );
+        let bytes = write(&pdu).await;
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x1c\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x26\
+            \x14\x00\x00\x08smsc 1.0"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[tokio::test]
+    async fn deliver_sm_resp_round_trips() {
+        let pdu = ExtraPdu::new(
+            0,
+            0x27,
+            ExtraPduBody::DeliverSmResp(Some(
+                DeliverSmRespPdu::new("").unwrap(),
+            )),
+        );
+        let bytes = write(&pdu).await;
+        assert_eq!(
+            bytes,
+            b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x27\x00"
+        );
+        assert_eq!(parse(&bytes).unwrap(), pdu);
+    }
+
+    #[test]
+    fn rejected_deliver_sm_resp_with_null_message_id_parses() {
+        let pdu = parse(
+            b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x64\x00\x00\x00\x07\x00",
+        )
+        .unwrap();
+        assert_eq!(
+            pdu,
+            ExtraPdu::new(0x64, 0x07, ExtraPduBody::DeliverSmResp(None))
+        );
+    }
+
+    #[test]
+    fn rejected_deliver_sm_resp_without_body_parses() {
+        let pdu = parse(
+            b"\x00\x00\x00\x10\x80\x00\x00\x05\x00\x00\x00\x64\x00\x00\x00\x07",
+        )
+        .unwrap();
+        assert_eq!(
+            pdu,
+            ExtraPdu::new(0x64, 0x07, ExtraPduBody::DeliverSmResp(None))
+        );
+    }
+}
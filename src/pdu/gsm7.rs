@@ -0,0 +1,209 @@
+//! The GSM 7-bit default alphabet, as described in 3GPP TS 23.038
+//! section 6.2.1.
+
+/// Prefix for a character from the extension table
+const ESCAPE: u8 = 0x1b;
+
+const CR: u8 = 0x0d;
+
+#[rustfmt::skip]
+const DEFAULT_ALPHABET: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì',
+    'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å',
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ',
+    'Σ', 'Θ', 'Ξ', '\u{1b}', 'Æ', 'æ', 'ß', 'É',
+    ' ', '!', '"', '#', '¤', '%', '&', '\'',
+    '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', ':', ';', '<', '=', '>', '?',
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G',
+    'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W',
+    'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§',
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g',
+    'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w',
+    'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à',
+];
+
+const EXTENSION_TABLE: [(u8, char); 10] = [
+    (0x0a, '\u{0c}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2f, '\\'),
+    (0x3c, '['),
+    (0x3d, '~'),
+    (0x3e, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// Unpack septets that were packed 8 to every 7 octets, least significant
+/// bit first.
+pub fn unpack(bytes: &[u8]) -> Vec<u8> {
+    let num_septets = bytes.len() * 8 / 7;
+    let mut septets = Vec::with_capacity(num_septets);
+    for i in 0..num_septets {
+        let bit = i * 7;
+        let byte = bit / 8;
+        let shift = bit % 8;
+        let mut septet = bytes[byte] >> shift;
+        if shift > 1 {
+            septet |= bytes[byte + 1] << (8 - shift);
+        }
+        septets.push(septet & 0x7f);
+    }
+
+    // If the octets have room for one more septet than was sent, the
+    // final 7 bits are padding, which should be a CR.  See 3GPP TS 23.038
+    // section 6.1.2.3.1.
+    if bytes.len().is_multiple_of(7) && septets.last() == Some(&CR) {
+        septets.pop();
+    }
+
+    septets
+}
+
+/// Pack septets 8 to every 7 octets, least significant bit first.  If this
+/// leaves 7 bits spare at the end, they are filled with a CR so they are
+/// not mistaken for an '@'.
+pub fn pack(septets: &[u8]) -> Vec<u8> {
+    let mut padded = septets.to_vec();
+    if padded.len() % 8 == 7 {
+        padded.push(CR);
+    }
+
+    let mut ret = vec![0u8; (padded.len() * 7).div_ceil(8)];
+    for (i, septet) in padded.iter().enumerate() {
+        let bit = i * 7;
+        let byte = bit / 8;
+        let shift = bit % 8;
+        ret[byte] |= septet << shift;
+        if shift > 1 {
+            ret[byte + 1] |= septet >> (8 - shift);
+        }
+    }
+    ret
+}
+
+/// Convert text into unpacked septets, using the extension table where
+/// needed.  Returns the first character that cannot be represented if
+/// there is one.
+pub fn encode_septets(text: &str) -> Result<Vec<u8>, char> {
+    let mut ret = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some(septet) = DEFAULT_ALPHABET
+            .iter()
+            .position(|d| *d == c && c != '\u{1b}')
+        {
+            ret.push(septet as u8);
+        } else if let Some((code, _)) =
+            EXTENSION_TABLE.iter().find(|(_, e)| *e == c)
+        {
+            ret.push(ESCAPE);
+            ret.push(*code);
+        } else {
+            return Err(c);
+        }
+    }
+    Ok(ret)
+}
+
+/// Convert unpacked septets into text, handling escapes into the
+/// extension table.  An escape followed by a septet not in the extension
+/// table is shown as the character from the default alphabet, and a
+/// trailing escape is ignored.
+pub fn decode_septets(septets: &[u8]) -> String {
+    let mut ret = String::with_capacity(septets.len());
+    let mut iter = septets.iter();
+    while let Some(&septet) = iter.next() {
+        if septet == ESCAPE {
+            if let Some(&next) = iter.next() {
+                ret.push(
+                    EXTENSION_TABLE
+                        .iter()
+                        .find(|(code, _)| *code == next)
+                        .map(|(_, c)| *c)
+                        .unwrap_or(DEFAULT_ALPHABET[next as usize]),
+                );
+            }
+        } else {
+            ret.push(DEFAULT_ALPHABET[septet as usize]);
+        }
+    }
+    ret
+}
+
+/// Check that unpacked septets are strictly valid: every escape must be
+/// followed by a septet from the extension table.  Returns the index of
+/// the first escape that is not.
+pub fn check_septets(septets: &[u8]) -> Result<(), usize> {
+    let mut i = 0;
+    while i < septets.len() {
+        if septets[i] == ESCAPE {
+            match septets.get(i + 1) {
+                Some(next)
+                    if EXTENSION_TABLE.iter().any(|(code, _)| code == next) => {
+                }
+                _ => return Err(i),
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_known_message() {
+        let packed = b"\xe8\x32\x9b\xfd\x46\x97\xd9\xec\x37";
+        assert_eq!(decode_septets(&unpack(packed)), "hellohello");
+    }
+
+    #[test]
+    fn unpack_drops_padding_when_septets_fill_octets() {
+        // 8 septets "abcdefgh" exactly fill 7 octets
+        let packed = b"\x61\xf1\x98\x5c\x36\x9f\xd1";
+        assert_eq!(decode_septets(&unpack(packed)), "abcdefgh");
+        // 7 septets "abcdefg" leave 7 bits, padded with a CR
+        let packed = b"\x61\xf1\x98\x5c\x36\x9f\x1b";
+        assert_eq!(decode_septets(&unpack(packed)), "abcdefg");
+    }
+
+    #[test]
+    fn pack_is_the_inverse_of_unpack() {
+        for text in ["", "a", "abcdefg", "abcdefg@", "abcdefgh", "hellohello"] {
+            let septets = encode_septets(text).unwrap();
+            assert_eq!(unpack(&pack(&septets)), septets, "{}", text);
+        }
+        assert_eq!(
+            pack(&encode_septets("hellohello").unwrap()),
+            b"\xe8\x32\x9b\xfd\x46\x97\xd9\xec\x37"
+        );
+    }
+
+    #[test]
+    fn decode_escaped_characters() {
+        assert_eq!(
+            decode_septets(&[0x1b, 0x65, 0x31, 0x1b, 0x28, 0x1b, 0x41, 0x1b]),
+            "€1{A"
+        );
+    }
+
+    #[test]
+    fn decode_non_ascii_characters() {
+        assert_eq!(decode_septets(&[0x00, 0x01, 0x10, 0x7f]), "@£Δà");
+    }
+
+    #[test]
+    fn check_septets_rejects_unknown_and_trailing_escapes() {
+        assert_eq!(check_septets(&[0x1b, 0x65, 0x31]), Ok(()));
+        assert_eq!(check_septets(&[0x31, 0x1b, 0x41]), Err(1));
+        assert_eq!(check_septets(&[0x31, 0x1b]), Err(1));
+    }
+}
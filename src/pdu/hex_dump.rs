@@ -0,0 +1,284 @@
+//! An annotated hex dump of a PDU's bytes, for debugging what went over
+//! the wire, e.g.
+//!
+//! ```text
+//! 00000000  00 00 00 14                                     command_length
+//! 00000004  80 00 00 02                                     command_id
+//! 00000008  00 00 00 00                                     command_status
+//! 0000000C  00 00 00 01                                     sequence_number
+//! 00000010  61 62 63 00                                     system_id
+//! ```
+//!
+//! Body fields are named for the PDU types whose layout we know, and any
+//! bytes left over, e.g. from a malformed PDU, are shown unnamed.
+
+use smpp_pdu::pdu::tlvs::KnownTlvTag;
+use std::fmt::Write;
+
+use crate::pdu::command_id::*;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Octets in the header: command_length, command_id, command_status and
+/// sequence_number
+const HEADER_FIELDS: [&str; 4] = [
+    "command_length",
+    "command_id",
+    "command_status",
+    "sequence_number",
+];
+
+#[derive(Clone, Copy)]
+enum Field {
+    COctetString(&'static str),
+    Integer1(&'static str),
+    /// sm_length, followed by that many octets of short_message
+    ShortMessage,
+    /// Every remaining octet, as TLVs
+    Tlvs,
+}
+
+use Field::*;
+
+const BIND: &[Field] = &[
+    COctetString("system_id"),
+    COctetString("password"),
+    COctetString("system_type"),
+    Integer1("interface_version"),
+    Integer1("addr_ton"),
+    Integer1("addr_npi"),
+    COctetString("address_range"),
+];
+
+const BIND_RESP: &[Field] = &[COctetString("system_id"), Tlvs];
+
+const SM: &[Field] = &[
+    COctetString("service_type"),
+    Integer1("source_addr_ton"),
+    Integer1("source_addr_npi"),
+    COctetString("source_addr"),
+    Integer1("dest_addr_ton"),
+    Integer1("dest_addr_npi"),
+    COctetString("destination_addr"),
+    Integer1("esm_class"),
+    Integer1("protocol_id"),
+    Integer1("priority_flag"),
+    COctetString("schedule_delivery_time"),
+    COctetString("validity_period"),
+    Integer1("registered_delivery"),
+    Integer1("replace_if_present_flag"),
+    Integer1("data_coding"),
+    Integer1("sm_default_msg_id"),
+    ShortMessage,
+    Tlvs,
+];
+
+const MESSAGE_ID: &[Field] = &[COctetString("message_id")];
+
+const QUERY_SM_FIELDS: &[Field] = &[
+    COctetString("message_id"),
+    Integer1("source_addr_ton"),
+    Integer1("source_addr_npi"),
+    COctetString("source_addr"),
+];
+
+const QUERY_SM_RESP_FIELDS: &[Field] = &[
+    COctetString("message_id"),
+    COctetString("final_date"),
+    Integer1("message_state"),
+    Integer1("error_code"),
+];
+
+const CANCEL_SM_FIELDS: &[Field] = &[
+    COctetString("service_type"),
+    COctetString("message_id"),
+    Integer1("source_addr_ton"),
+    Integer1("source_addr_npi"),
+    COctetString("source_addr"),
+    Integer1("dest_addr_ton"),
+    Integer1("dest_addr_npi"),
+    COctetString("destination_addr"),
+];
+
+const OUTBIND_FIELDS: &[Field] =
+    &[COctetString("system_id"), COctetString("password")];
+
+const ALERT_NOTIFICATION_FIELDS: &[Field] = &[
+    Integer1("source_addr_ton"),
+    Integer1("source_addr_npi"),
+    COctetString("source_addr"),
+    Integer1("esme_addr_ton"),
+    Integer1("esme_addr_npi"),
+    COctetString("esme_addr"),
+    Tlvs,
+];
+
+fn body_fields(command_id: u32) -> &'static [Field] {
+    match command_id {
+        BIND_RECEIVER | BIND_TRANSMITTER | BIND_TRANSCEIVER => BIND,
+        BIND_RECEIVER_RESP | BIND_TRANSMITTER_RESP | BIND_TRANSCEIVER_RESP => {
+            BIND_RESP
+        }
+        SUBMIT_SM | DELIVER_SM => SM,
+        SUBMIT_SM_RESP | DELIVER_SM_RESP | SUBMIT_MULTI_RESP => MESSAGE_ID,
+        QUERY_SM => QUERY_SM_FIELDS,
+        QUERY_SM_RESP => QUERY_SM_RESP_FIELDS,
+        CANCEL_SM => CANCEL_SM_FIELDS,
+        OUTBIND => OUTBIND_FIELDS,
+        ALERT_NOTIFICATION => ALERT_NOTIFICATION_FIELDS,
+        ENQUIRE_LINK_RESP => &[Tlvs],
+        _ => &[],
+    }
+}
+
+/// An annotated hex dump of a whole PDU, one field per line (or more, for
+/// fields longer than 16 octets).
+pub fn hex_dump(pdu: &[u8]) -> String {
+    let mut dump = Dump {
+        pdu,
+        offset: 0,
+        out: String::new(),
+    };
+    for name in HEADER_FIELDS {
+        dump.field(4, name);
+    }
+    if pdu.len() >= 8 {
+        let command_id = u32::from_be_bytes([pdu[4], pdu[5], pdu[6], pdu[7]]);
+        for field in body_fields(command_id) {
+            if dump.remaining().is_empty() {
+                break;
+            }
+            dump.body_field(*field);
+        }
+    }
+    let rest = dump.remaining().len();
+    dump.field(rest, "");
+    dump.out
+}
+
+struct Dump<'a> {
+    pdu: &'a [u8],
+    offset: usize,
+    out: String,
+}
+
+impl<'a> Dump<'a> {
+    fn remaining(&self) -> &'a [u8] {
+        &self.pdu[self.offset..]
+    }
+
+    fn body_field(&mut self, field: Field) {
+        match field {
+            COctetString(name) => {
+                let len = self
+                    .remaining()
+                    .iter()
+                    .position(|b| *b == 0)
+                    .map_or(self.remaining().len(), |nul| nul + 1);
+                self.field(len, name);
+            }
+            Integer1(name) => self.field(1, name),
+            ShortMessage => {
+                let sm_length = usize::from(self.remaining()[0]);
+                self.field(1, "sm_length");
+                self.field(sm_length, "short_message");
+            }
+            Tlvs => {
+                while self.remaining().len() >= 4 {
+                    let tlv = self.remaining();
+                    let tag = u16::from_be_bytes([tlv[0], tlv[1]]);
+                    let length =
+                        usize::from(u16::from_be_bytes([tlv[2], tlv[3]]));
+                    let name = match KnownTlvTag::new(tag) {
+                        Some(known) => format!("TLV {:?}", known),
+                        None => format!("TLV {:#06X}", tag),
+                    };
+                    self.field(4 + length, &name);
+                }
+            }
+        }
+    }
+
+    /// Write the next len octets (or as many as are left) under name
+    fn field(&mut self, len: usize, name: &str) {
+        let end = (self.offset + len).min(self.pdu.len());
+        let bytes = &self.pdu[self.offset..end];
+        for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+            let hex: Vec<String> =
+                line.iter().map(|b| format!("{:02x}", b)).collect();
+            let name = if i == 0 { name } else { "" };
+            let line = format!(
+                "{:08X}  {:<width$} {}",
+                self.offset + i * BYTES_PER_LINE,
+                hex.join(" "),
+                name,
+                width = BYTES_PER_LINE * 3 - 1
+            );
+            writeln!(self.out, "{}", line.trim_end()).unwrap();
+        }
+        self.offset = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each line of dump, with runs of spaces collapsed into one
+    fn lines(dump: &str) -> Vec<String> {
+        dump.lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect()
+    }
+
+    #[test]
+    fn header_and_unknown_body_are_shown() {
+        let pdu = b"\x00\x00\x00\x16\x00\x00\x00\x15\x00\x00\x00\x00\
+            \x00\x00\x00\x01\x00\x00\x00\x02\xab\xcd";
+        assert_eq!(
+            lines(&hex_dump(pdu)),
+            [
+                "00000000 00 00 00 16 command_length",
+                "00000004 00 00 00 15 command_id",
+                "00000008 00 00 00 00 command_status",
+                "0000000C 00 00 00 01 sequence_number",
+                "00000010 00 00 00 02 ab cd",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_short_message_wraps_and_tlvs_are_named() {
+        let mut pdu = b"\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\
+            \x00\x00\x00\x01\
+            \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+            \x14"
+            .to_vec();
+        pdu.extend_from_slice(&[b'x'; 20]);
+        pdu.extend_from_slice(b"\x02\x04\x00\x02\x00\x07\x14\x00\x00\x01\x61");
+
+        let dump = hex_dump(&pdu);
+
+        assert!(dump.contains("  14 "), "{}", dump);
+        assert!(
+            dump.contains(
+                "00000021  78 78 78 78 78 78 78 78 78 78 78 78 78 78 78 78 \
+                short_message\n\
+                00000031  78 78 78 78\n"
+            ),
+            "{}",
+            dump
+        );
+        assert!(dump.contains("02 04 00 02 00 07"), "{}", dump);
+        assert!(dump.contains(" TLV user_message_reference\n"), "{}", dump);
+        assert!(dump.ends_with(" TLV 0x1400\n"), "{}", dump);
+    }
+
+    #[test]
+    fn truncated_pdu_shows_what_there_is() {
+        let dump = hex_dump(b"\x00\x00");
+        assert!(dump.starts_with("00000000  00 00 "), "{}", dump);
+        assert!(dump.ends_with(" command_length\n"), "{}", dump);
+        assert_eq!(dump.lines().count(), 1);
+    }
+}
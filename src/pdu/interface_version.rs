@@ -0,0 +1,110 @@
+use std::fmt::{Display, Formatter};
+
+/// The SMPP version an ESME or SMSC speaks, as sent in the
+/// interface_version of a bind.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.4
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InterfaceVersion {
+    V33,
+    V34,
+    V50,
+    /// Any other value
+    Unknown(u8),
+}
+
+impl InterfaceVersion {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0x33 => InterfaceVersion::V33,
+            0x34 => InterfaceVersion::V34,
+            0x50 => InterfaceVersion::V50,
+            _ => InterfaceVersion::Unknown(value),
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            InterfaceVersion::V33 => 0x33,
+            InterfaceVersion::V34 => 0x34,
+            InterfaceVersion::V50 => 0x50,
+            InterfaceVersion::Unknown(value) => *value,
+        }
+    }
+
+    /// The version we will speak with an ESME that sent this one.  We only
+    /// speak 3.4, so newer versions are negotiated down to it, and the spec
+    /// says any value below 0x34 means 3.3 or earlier.
+    pub fn negotiate(&self) -> Self {
+        if self.as_u8() < 0x34 {
+            InterfaceVersion::V33
+        } else {
+            InterfaceVersion::V34
+        }
+    }
+}
+
+impl From<u8> for InterfaceVersion {
+    fn from(value: u8) -> Self {
+        InterfaceVersion::from_u8(value)
+    }
+}
+
+impl From<InterfaceVersion> for u8 {
+    fn from(interface_version: InterfaceVersion) -> u8 {
+        interface_version.as_u8()
+    }
+}
+
+impl Display for InterfaceVersion {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            InterfaceVersion::V33 => formatter.write_str("3.3"),
+            InterfaceVersion::V34 => formatter.write_str("3.4"),
+            InterfaceVersion::V50 => formatter.write_str("5.0"),
+            InterfaceVersion::Unknown(value) => {
+                write!(formatter, "unknown ({:#04X})", value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_versions_round_trip_through_u8() {
+        for (value, version) in &[
+            (0x33, InterfaceVersion::V33),
+            (0x34, InterfaceVersion::V34),
+            (0x50, InterfaceVersion::V50),
+        ] {
+            assert_eq!(InterfaceVersion::from_u8(*value), *version);
+            assert_eq!(version.as_u8(), *value);
+        }
+    }
+
+    #[test]
+    fn other_values_are_unknown() {
+        assert_eq!(
+            InterfaceVersion::from_u8(0x12),
+            InterfaceVersion::Unknown(0x12)
+        );
+        assert_eq!(InterfaceVersion::Unknown(0x12).as_u8(), 0x12);
+    }
+
+    #[test]
+    fn we_negotiate_down_to_v34() {
+        assert_eq!(InterfaceVersion::V50.negotiate(), InterfaceVersion::V34);
+        assert_eq!(InterfaceVersion::V34.negotiate(), InterfaceVersion::V34);
+        assert_eq!(InterfaceVersion::V33.negotiate(), InterfaceVersion::V33);
+        assert_eq!(
+            InterfaceVersion::Unknown(0x00).negotiate(),
+            InterfaceVersion::V33
+        );
+        assert_eq!(
+            InterfaceVersion::Unknown(0x40).negotiate(),
+            InterfaceVersion::V34
+        );
+    }
+}
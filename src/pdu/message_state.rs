@@ -0,0 +1,123 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+/// The state of a message, as reported in query_sm_resp, in the
+/// message_state TLV and in the stat field of a delivery receipt.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.28
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageState {
+    Enroute = 1,
+    Delivered = 2,
+    Expired = 3,
+    Deleted = 4,
+    Undeliverable = 5,
+    Accepted = 6,
+    Unknown = 7,
+    Rejected = 8,
+}
+
+const ALL: [MessageState; 8] = [
+    MessageState::Enroute,
+    MessageState::Delivered,
+    MessageState::Expired,
+    MessageState::Deleted,
+    MessageState::Undeliverable,
+    MessageState::Accepted,
+    MessageState::Unknown,
+    MessageState::Rejected,
+];
+
+impl MessageState {
+    /// None if the value is not a known message_state
+    pub fn from_u8(value: u8) -> Option<Self> {
+        ALL.iter().copied().find(|state| state.as_u8() == value)
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The state a delivery receipt's stat string (e.g. "DELIVRD") stands
+    /// for, ignoring case
+    pub fn from_stat(stat: &str) -> Option<Self> {
+        ALL.iter()
+            .copied()
+            .find(|state| state.stat().eq_ignore_ascii_case(stat))
+    }
+
+    /// The string a delivery receipt uses for this state in its stat field
+    pub fn stat(&self) -> &'static str {
+        match self {
+            MessageState::Enroute => "ENROUTE",
+            MessageState::Delivered => "DELIVRD",
+            MessageState::Expired => "EXPIRED",
+            MessageState::Deleted => "DELETED",
+            MessageState::Undeliverable => "UNDELIV",
+            MessageState::Accepted => "ACCEPTD",
+            MessageState::Unknown => "UNKNOWN",
+            MessageState::Rejected => "REJECTD",
+        }
+    }
+}
+
+impl TryFrom<u8> for MessageState {
+    type Error = u8;
+
+    /// Fails with the supplied value if it is not a known message_state
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::from_u8(value).ok_or(value)
+    }
+}
+
+/// Formats as the stat string of a delivery receipt
+impl Display for MessageState {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str(self.stat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_state_round_trips_through_u8() {
+        for value in 1..=8 {
+            let state = MessageState::try_from(value).unwrap();
+            assert_eq!(state as u8, value);
+            assert_eq!(MessageState::from_u8(value).unwrap().as_u8(), value);
+        }
+        assert_eq!(MessageState::try_from(0), Err(0));
+        assert_eq!(MessageState::try_from(9), Err(9));
+        assert_eq!(MessageState::from_u8(0xff), None);
+    }
+
+    #[test]
+    fn each_state_displays_as_its_stat_string() {
+        let displayed: Vec<String> =
+            ALL.iter().map(|state| state.to_string()).collect();
+        assert_eq!(
+            displayed,
+            [
+                "ENROUTE", "DELIVRD", "EXPIRED", "DELETED", "UNDELIV",
+                "ACCEPTD", "UNKNOWN", "REJECTD"
+            ]
+        );
+        for state in ALL {
+            assert_eq!(
+                MessageState::from_stat(&state.to_string()),
+                Some(state)
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_stat_strings_are_not_states() {
+        assert_eq!(
+            MessageState::from_stat("delivrd"),
+            Some(MessageState::Delivered)
+        );
+        assert_eq!(MessageState::from_stat("DELIVERED"), None);
+        assert_eq!(MessageState::from_stat(""), None);
+    }
+}
@@ -0,0 +1,65 @@
+//! Additions to the PDU types provided by the smpp-pdu crate.
+
+mod bind_data;
+mod c_octet_string;
+pub mod command_id;
+pub mod command_status;
+mod data_coding;
+mod delivery_receipt;
+mod encoding;
+mod esm_class;
+mod extra_pdu;
+mod gsm7;
+mod hex_dump;
+mod interface_version;
+mod message_state;
+mod octet_string;
+mod one_octet_tlvs;
+mod operations;
+mod pdu_ext;
+#[cfg(feature = "serde")]
+pub mod pdu_serde;
+mod reassembler;
+mod registered_delivery;
+mod sm_builder;
+mod sm_fields;
+mod tlv_count;
+mod ton_npi;
+mod udh;
+
+pub use bind_data::{write_bind_pdu, BindDataExt, INTERFACE_VERSION_34};
+pub use c_octet_string::{COctetStringDecimal, COctetStringHex};
+pub use data_coding::{DataCoding, DecodeError, DecodedText};
+pub use delivery_receipt::{
+    DeliveryReceipt, DeliveryReceiptExt, FailureKind, ReceiptParseError,
+};
+pub use encoding::{encode_gsm7, EncodeError, WithText};
+pub use esm_class::EsmClass;
+pub use extra_pdu::{AnyPdu, ExtraPdu, ExtraPduBody, BANNER_TLV_TAG};
+pub use hex_dump::hex_dump;
+pub use interface_version::InterfaceVersion;
+pub use message_state::MessageState;
+pub use octet_string::OctetStringExt;
+pub use one_octet_tlvs::{
+    MessageWaitingType, MsMsgWaitFacilities, OneOctetTlvs, UssdServiceOp,
+};
+pub use operations::alert_notification::AlertNotificationPdu;
+pub use operations::cancel_sm::CancelSmPdu;
+pub use operations::data_sm::{DataSmPdu, DataSmRespPdu};
+pub use operations::deliver_sm_resp::DeliverSmRespPdu;
+pub use operations::outbind::OutbindPdu;
+pub use operations::query_sm::QuerySmPdu;
+pub use operations::query_sm_resp::QuerySmRespPdu;
+pub use operations::submit_multi::{
+    DestAddress, SubmitMultiPdu, SubmitMultiRespPdu, UnsuccessSme,
+};
+pub use pdu_ext::{write_pdu, PduExt};
+pub use reassembler::{ReassembleError, Reassembler};
+pub use registered_delivery::RegisteredDelivery;
+pub use sm_builder::{
+    DeliverSmPduBuilder, SmPdu, SmPduBuilder, SubmitSmPduBuilder,
+};
+pub use sm_fields::SmFields;
+pub use tlv_count::{count_tlvs, sm_length_overrun, unknown_tlvs};
+pub use ton_npi::{Npi, Ton, TonNpi};
+pub use udh::{ConcatInfo, InformationElement, UserData, UserDataHeader, UDHI};
@@ -0,0 +1,64 @@
+use smpp_pdu::pdu::formats::OctetString;
+
+/// Accessors for an OctetString (e.g. the short_message of a submit_sm),
+/// so callers don't need to reach into its `value` field.  `len()` is
+/// already provided by OctetString itself.
+pub trait OctetStringExt {
+    fn is_empty(&self) -> bool;
+    fn as_bytes(&self) -> &[u8];
+
+    /// A copy of this string containing at most its first `n` octets, e.g.
+    /// for building one segment of a longer message.
+    fn truncated_to(&self, n: usize) -> OctetString;
+}
+
+impl OctetStringExt for OctetString {
+    fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.value.as_slice()
+    }
+
+    fn truncated_to(&self, n: usize) -> OctetString {
+        OctetString {
+            value: self.value[..n.min(self.len())].to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn octet_string(value: &[u8]) -> OctetString {
+        OctetString::from_bytes(value, 255).unwrap()
+    }
+
+    #[test]
+    fn len_and_is_empty_reflect_contents() {
+        let empty = octet_string(b"");
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+
+        let s = octet_string(b"hello");
+        assert_eq!(s.len(), 5);
+        assert!(!s.is_empty());
+        assert_eq!(s.as_bytes(), b"hello");
+    }
+
+    #[test]
+    fn truncated_to_keeps_the_first_n_octets() {
+        let s = octet_string(b"\x00hello\xff");
+        assert_eq!(s.truncated_to(3).as_bytes(), b"\x00he");
+        assert_eq!(s.truncated_to(0).as_bytes(), b"");
+    }
+
+    #[test]
+    fn truncated_to_longer_than_string_returns_all_of_it() {
+        let s = octet_string(b"hello");
+        assert_eq!(s.truncated_to(5), s);
+        assert_eq!(s.truncated_to(100), s);
+    }
+}
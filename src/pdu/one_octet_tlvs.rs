@@ -0,0 +1,266 @@
+//! Typed access to the ms_msg_wait_facilities and ussd_service_op TLVs,
+//! whose values are a single octet.  See
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf sections 5.3.2.13 and 5.3.2.44
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+
+/// The kind of message waiting that ms_msg_wait_facilities refers to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageWaitingType {
+    Voicemail = 0,
+    Fax = 1,
+    ElectronicMail = 2,
+    Other = 3,
+}
+
+/// The value of the ms_msg_wait_facilities TLV: whether the handset should
+/// show or clear its indicator for a type of waiting message
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MsMsgWaitFacilities {
+    pub active: bool,
+    pub message_type: MessageWaitingType,
+}
+
+const INDICATOR_ACTIVE: u8 = 0b10000000;
+
+impl MsMsgWaitFacilities {
+    pub fn new(active: bool, message_type: MessageWaitingType) -> Self {
+        Self {
+            active,
+            message_type,
+        }
+    }
+
+    pub fn tlv(&self) -> Tlv {
+        Tlv::new(KnownTlvTag::ms_msg_wait_facilities, &[u8::from(*self)])
+    }
+}
+
+impl From<u8> for MsMsgWaitFacilities {
+    /// Bits 6 to 2 are reserved, so are ignored
+    fn from(value: u8) -> Self {
+        let message_type = match value & 0b11 {
+            0 => MessageWaitingType::Voicemail,
+            1 => MessageWaitingType::Fax,
+            2 => MessageWaitingType::ElectronicMail,
+            _ => MessageWaitingType::Other,
+        };
+        Self::new(value & INDICATOR_ACTIVE != 0, message_type)
+    }
+}
+
+impl From<MsMsgWaitFacilities> for u8 {
+    fn from(value: MsMsgWaitFacilities) -> Self {
+        let active = if value.active { INDICATOR_ACTIVE } else { 0 };
+        active | value.message_type as u8
+    }
+}
+
+/// The value of the ussd_service_op TLV: which USSD operation a message is
+/// part of
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UssdServiceOp {
+    PssdIndication,
+    PssrIndication,
+    UssrRequest,
+    UssnRequest,
+    PssdResponse,
+    PssrResponse,
+    UssrConfirm,
+    UssnConfirm,
+    /// A reserved (4-15, 20-31) or vendor-specific (32-255) value
+    Other(u8),
+}
+
+impl UssdServiceOp {
+    pub fn tlv(&self) -> Tlv {
+        Tlv::new(KnownTlvTag::Genericussd_service_op, &[u8::from(*self)])
+    }
+}
+
+impl From<u8> for UssdServiceOp {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => UssdServiceOp::PssdIndication,
+            1 => UssdServiceOp::PssrIndication,
+            2 => UssdServiceOp::UssrRequest,
+            3 => UssdServiceOp::UssnRequest,
+            16 => UssdServiceOp::PssdResponse,
+            17 => UssdServiceOp::PssrResponse,
+            18 => UssdServiceOp::UssrConfirm,
+            19 => UssdServiceOp::UssnConfirm,
+            _ => UssdServiceOp::Other(value),
+        }
+    }
+}
+
+impl From<UssdServiceOp> for u8 {
+    fn from(value: UssdServiceOp) -> Self {
+        match value {
+            UssdServiceOp::PssdIndication => 0,
+            UssdServiceOp::PssrIndication => 1,
+            UssdServiceOp::UssrRequest => 2,
+            UssdServiceOp::UssnRequest => 3,
+            UssdServiceOp::PssdResponse => 16,
+            UssdServiceOp::PssrResponse => 17,
+            UssdServiceOp::UssrConfirm => 18,
+            UssdServiceOp::UssnConfirm => 19,
+            UssdServiceOp::Other(value) => value,
+        }
+    }
+}
+
+/// Implemented by PDUs that may carry ms_msg_wait_facilities or
+/// ussd_service_op.  Each returns None if the TLV is absent, or if its
+/// value is not exactly one octet.
+pub trait OneOctetTlvs {
+    fn ms_msg_wait_facilities(&self) -> Option<MsMsgWaitFacilities>;
+    fn ussd_service_op(&self) -> Option<UssdServiceOp>;
+}
+
+fn one_octet(sm_data: &SmData, tag: KnownTlvTag) -> Option<u8> {
+    match sm_data.tlvs.get(tag)?.value[..] {
+        [value] => Some(value),
+        _ => None,
+    }
+}
+
+impl OneOctetTlvs for SubmitSmPdu {
+    fn ms_msg_wait_facilities(&self) -> Option<MsMsgWaitFacilities> {
+        one_octet(&self.0, KnownTlvTag::ms_msg_wait_facilities)
+            .map(MsMsgWaitFacilities::from)
+    }
+
+    fn ussd_service_op(&self) -> Option<UssdServiceOp> {
+        one_octet(&self.0, KnownTlvTag::Genericussd_service_op)
+            .map(UssdServiceOp::from)
+    }
+}
+
+impl OneOctetTlvs for DeliverSmPdu {
+    fn ms_msg_wait_facilities(&self) -> Option<MsMsgWaitFacilities> {
+        one_octet(&self.0, KnownTlvTag::ms_msg_wait_facilities)
+            .map(MsMsgWaitFacilities::from)
+    }
+
+    fn ussd_service_op(&self) -> Option<UssdServiceOp> {
+        one_octet(&self.0, KnownTlvTag::Genericussd_service_op)
+            .map(UssdServiceOp::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::Tlvs;
+    use smpp_pdu::pdu::{Pdu, PduBody};
+    use std::io;
+
+    fn submit_sm(tlvs: &[Tlv]) -> SubmitSmPdu {
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "src",
+            0,
+            0,
+            "dest",
+            0,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            0x08,
+            0,
+            b"",
+            Tlvs::from(tlvs),
+        )
+        .unwrap()
+    }
+
+    fn deliver_sm(tlvs: &[Tlv]) -> DeliverSmPdu {
+        DeliverSmPdu::new(
+            "",
+            0,
+            0,
+            "src",
+            0,
+            0,
+            "dest",
+            0,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            0x08,
+            0,
+            b"",
+            Tlvs::from(tlvs),
+        )
+        .unwrap()
+    }
+
+    async fn round_trip(pdu: Pdu) -> Pdu {
+        let mut bytes: Vec<u8> = Vec::new();
+        pdu.write(&mut bytes).await.unwrap();
+        Pdu::parse(&mut io::BufReader::new(&bytes[..])).unwrap()
+    }
+
+    #[tokio::test]
+    async fn ms_msg_wait_facilities_round_trips_in_submit_sm() {
+        let value = MsMsgWaitFacilities::new(true, MessageWaitingType::Fax);
+        let pdu = Pdu::new(0, 1, submit_sm(&[value.tlv()]).into()).unwrap();
+
+        match round_trip(pdu).await.body() {
+            PduBody::SubmitSm(body) => {
+                assert_eq!(body.ms_msg_wait_facilities(), Some(value));
+                assert_eq!(body.ussd_service_op(), None);
+            }
+            body => panic!("Unexpected body {:?}", body),
+        }
+    }
+
+    #[tokio::test]
+    async fn ussd_service_op_round_trips_in_deliver_sm() {
+        let value = UssdServiceOp::UssrRequest;
+        let pdu = Pdu::new(0, 1, deliver_sm(&[value.tlv()]).into()).unwrap();
+
+        match round_trip(pdu).await.body() {
+            PduBody::DeliverSm(body) => {
+                assert_eq!(body.ussd_service_op(), Some(value));
+                assert_eq!(body.ms_msg_wait_facilities(), None);
+            }
+            body => panic!("Unexpected body {:?}", body),
+        }
+    }
+
+    #[test]
+    fn one_octet_values_round_trip_through_u8() {
+        for value in 0..=255 {
+            assert_eq!(u8::from(UssdServiceOp::from(value)), value);
+        }
+        for value in [0x00, 0x01, 0x02, 0x03, 0x80, 0x81, 0x82, 0x83] {
+            assert_eq!(u8::from(MsMsgWaitFacilities::from(value)), value);
+        }
+        assert_eq!(
+            MsMsgWaitFacilities::from(0x80),
+            MsMsgWaitFacilities::new(true, MessageWaitingType::Voicemail)
+        );
+    }
+
+    #[test]
+    fn values_of_the_wrong_length_are_ignored() {
+        let pdu = submit_sm(&[
+            Tlv::new(KnownTlvTag::ms_msg_wait_facilities, &[]),
+            Tlv::new(KnownTlvTag::Genericussd_service_op, &[0x02, 0x00]),
+        ]);
+        assert_eq!(pdu.ms_msg_wait_facilities(), None);
+        assert_eq!(pdu.ussd_service_op(), None);
+    }
+}
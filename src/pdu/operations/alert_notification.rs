@@ -0,0 +1,87 @@
+use smpp_pdu::pdu::formats::{COctetString, Integer1, WriteStream};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+
+use crate::pdu::operations::fld;
+
+const MAX_LENGTH_ADDR: usize = 65;
+
+/// Tells an ESME that a mobile it asked about has become available.  There
+/// is no response PDU.  See https://smpp.org/SMPP_v3_4_Issue1_2.pdf section
+/// 4.12.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertNotificationPdu {
+    /// The mobile that is now available
+    pub source_addr_ton: Integer1,
+    pub source_addr_npi: Integer1,
+    pub source_addr: COctetString,
+    /// The ESME that asked to be told
+    pub esme_addr_ton: Integer1,
+    pub esme_addr_npi: Integer1,
+    pub esme_addr: COctetString,
+    /// The value of the optional ms_availability_status TLV: 0=available,
+    /// 1=denied, 2=unavailable
+    pub ms_availability_status: Option<u8>,
+}
+
+impl AlertNotificationPdu {
+    pub fn new(
+        source_addr_ton: u8,
+        source_addr_npi: u8,
+        source_addr: &str,
+        esme_addr_ton: u8,
+        esme_addr_npi: u8,
+        esme_addr: &str,
+        ms_availability_status: Option<u8>,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            source_addr_ton: Integer1::new(source_addr_ton),
+            source_addr_npi: Integer1::new(source_addr_npi),
+            source_addr: COctetString::from_str(source_addr, MAX_LENGTH_ADDR)?,
+            esme_addr_ton: Integer1::new(esme_addr_ton),
+            esme_addr_npi: Integer1::new(esme_addr_npi),
+            esme_addr: COctetString::from_str(esme_addr, MAX_LENGTH_ADDR)?,
+            ms_availability_status,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.source_addr_ton.write(stream).await?;
+        self.source_addr_npi.write(stream).await?;
+        self.source_addr.write(stream).await?;
+        self.esme_addr_ton.write(stream).await?;
+        self.esme_addr_npi.write(stream).await?;
+        self.esme_addr.write(stream).await?;
+        if let Some(status) = self.ms_availability_status {
+            Tlv::new(KnownTlvTag::ms_availability_status, &[status])
+                .write(stream)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let source_addr_ton = fld("source_addr_ton", Integer1::read(bytes))?;
+        let source_addr_npi = fld("source_addr_npi", Integer1::read(bytes))?;
+        let source_addr =
+            fld("source_addr", COctetString::read(bytes, MAX_LENGTH_ADDR))?;
+        let esme_addr_ton = fld("esme_addr_ton", Integer1::read(bytes))?;
+        let esme_addr_npi = fld("esme_addr_npi", Integer1::read(bytes))?;
+        let esme_addr =
+            fld("esme_addr", COctetString::read(bytes, MAX_LENGTH_ADDR))?;
+        let tlvs = Tlvs::read(bytes)?;
+        let ms_availability_status = tlvs
+            .get(KnownTlvTag::ms_availability_status)
+            .and_then(|tlv| tlv.value.first().copied());
+        Ok(Self {
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            esme_addr_ton,
+            esme_addr_npi,
+            esme_addr,
+            ms_availability_status,
+        })
+    }
+}
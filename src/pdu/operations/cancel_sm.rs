@@ -0,0 +1,100 @@
+use smpp_pdu::pdu::formats::{COctetString, Integer1, WriteStream};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+
+use crate::pdu::operations::fld;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_ADDR: usize = 21;
+
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.9.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct CancelSmPdu {
+    pub service_type: COctetString,
+    /// The message to cancel.  If empty, cancel all messages matching
+    /// service_type, source_addr and destination_addr.
+    pub message_id: COctetString,
+    pub source_addr_ton: Integer1,
+    pub source_addr_npi: Integer1,
+    pub source_addr: COctetString,
+    pub dest_addr_ton: Integer1,
+    pub dest_addr_npi: Integer1,
+    pub destination_addr: COctetString,
+}
+
+impl CancelSmPdu {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        service_type: &str,
+        message_id: &str,
+        source_addr_ton: u8,
+        source_addr_npi: u8,
+        source_addr: &str,
+        dest_addr_ton: u8,
+        dest_addr_npi: u8,
+        destination_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            service_type: COctetString::from_str(
+                service_type,
+                MAX_LENGTH_SERVICE_TYPE,
+            )?,
+            message_id: COctetString::from_str(
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            source_addr_ton: Integer1::new(source_addr_ton),
+            source_addr_npi: Integer1::new(source_addr_npi),
+            source_addr: COctetString::from_str(source_addr, MAX_LENGTH_ADDR)?,
+            dest_addr_ton: Integer1::new(dest_addr_ton),
+            dest_addr_npi: Integer1::new(dest_addr_npi),
+            destination_addr: COctetString::from_str(
+                destination_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.service_type.write(stream).await?;
+        self.message_id.write(stream).await?;
+        self.source_addr_ton.write(stream).await?;
+        self.source_addr_npi.write(stream).await?;
+        self.source_addr.write(stream).await?;
+        self.dest_addr_ton.write(stream).await?;
+        self.dest_addr_npi.write(stream).await?;
+        self.destination_addr.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let service_type = fld(
+            "service_type",
+            COctetString::read(bytes, MAX_LENGTH_SERVICE_TYPE),
+        )?;
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        let source_addr_ton = fld("source_addr_ton", Integer1::read(bytes))?;
+        let source_addr_npi = fld("source_addr_npi", Integer1::read(bytes))?;
+        let source_addr =
+            fld("source_addr", COctetString::read(bytes, MAX_LENGTH_ADDR))?;
+        let dest_addr_ton = fld("dest_addr_ton", Integer1::read(bytes))?;
+        let dest_addr_npi = fld("dest_addr_npi", Integer1::read(bytes))?;
+        let destination_addr = fld(
+            "destination_addr",
+            COctetString::read(bytes, MAX_LENGTH_ADDR),
+        )?;
+        Ok(Self {
+            service_type,
+            message_id,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+        })
+    }
+}
@@ -0,0 +1,265 @@
+use smpp_pdu::pdu::formats::{COctetString, Integer1, WriteStream};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlvs};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+use std::str;
+
+use crate::pdu::operations::fld;
+use crate::pdu::pdu_ext::clone_tlvs;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 65;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+
+/// Carries a message, or a delivery receipt, in either direction.  Unlike
+/// submit_sm and deliver_sm, the message itself goes in the message_payload
+/// TLV.  See https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.7.1
+#[derive(Debug, PartialEq)]
+pub struct DataSmPdu {
+    pub service_type: COctetString,
+    pub source_addr_ton: Integer1,
+    pub source_addr_npi: Integer1,
+    pub source_addr: COctetString,
+    pub dest_addr_ton: Integer1,
+    pub dest_addr_npi: Integer1,
+    pub destination_addr: COctetString,
+    pub esm_class: Integer1,
+    pub registered_delivery: Integer1,
+    pub data_coding: Integer1,
+    pub tlvs: Tlvs,
+}
+
+// Not derived because Tlvs is not Clone
+impl Clone for DataSmPdu {
+    fn clone(&self) -> Self {
+        Self {
+            service_type: self.service_type.clone(),
+            source_addr_ton: self.source_addr_ton.clone(),
+            source_addr_npi: self.source_addr_npi.clone(),
+            source_addr: self.source_addr.clone(),
+            dest_addr_ton: self.dest_addr_ton.clone(),
+            dest_addr_npi: self.dest_addr_npi.clone(),
+            destination_addr: self.destination_addr.clone(),
+            esm_class: self.esm_class.clone(),
+            registered_delivery: self.registered_delivery.clone(),
+            data_coding: self.data_coding.clone(),
+            tlvs: clone_tlvs(&self.tlvs),
+        }
+    }
+}
+
+impl DataSmPdu {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        service_type: &str,
+        source_addr_ton: u8,
+        source_addr_npi: u8,
+        source_addr: &str,
+        dest_addr_ton: u8,
+        dest_addr_npi: u8,
+        destination_addr: &str,
+        esm_class: u8,
+        registered_delivery: u8,
+        data_coding: u8,
+        tlvs: Tlvs,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            service_type: fld(
+                "service_type",
+                COctetString::from_str(service_type, MAX_LENGTH_SERVICE_TYPE),
+            )?,
+            source_addr_ton: Integer1::new(source_addr_ton),
+            source_addr_npi: Integer1::new(source_addr_npi),
+            source_addr: fld(
+                "source_addr",
+                COctetString::from_str(source_addr, MAX_LENGTH_ADDR),
+            )?,
+            dest_addr_ton: Integer1::new(dest_addr_ton),
+            dest_addr_npi: Integer1::new(dest_addr_npi),
+            destination_addr: fld(
+                "destination_addr",
+                COctetString::from_str(destination_addr, MAX_LENGTH_ADDR),
+            )?,
+            esm_class: Integer1::new(esm_class),
+            registered_delivery: Integer1::new(registered_delivery),
+            data_coding: Integer1::new(data_coding),
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.service_type.write(stream).await?;
+        self.source_addr_ton.write(stream).await?;
+        self.source_addr_npi.write(stream).await?;
+        self.source_addr.write(stream).await?;
+        self.dest_addr_ton.write(stream).await?;
+        self.dest_addr_npi.write(stream).await?;
+        self.destination_addr.write(stream).await?;
+        self.esm_class.write(stream).await?;
+        self.registered_delivery.write(stream).await?;
+        self.data_coding.write(stream).await?;
+        self.tlvs.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let service_type = fld(
+            "service_type",
+            COctetString::read(bytes, MAX_LENGTH_SERVICE_TYPE),
+        )?;
+        let source_addr_ton = fld("source_addr_ton", Integer1::read(bytes))?;
+        let source_addr_npi = fld("source_addr_npi", Integer1::read(bytes))?;
+        let source_addr =
+            fld("source_addr", COctetString::read(bytes, MAX_LENGTH_ADDR))?;
+        let dest_addr_ton = fld("dest_addr_ton", Integer1::read(bytes))?;
+        let dest_addr_npi = fld("dest_addr_npi", Integer1::read(bytes))?;
+        let destination_addr = fld(
+            "destination_addr",
+            COctetString::read(bytes, MAX_LENGTH_ADDR),
+        )?;
+        let esm_class = fld("esm_class", Integer1::read(bytes))?;
+        let registered_delivery =
+            fld("registered_delivery", Integer1::read(bytes))?;
+        let data_coding = fld("data_coding", Integer1::read(bytes))?;
+        let tlvs = Tlvs::read(bytes)?;
+        Ok(Self {
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+            esm_class,
+            registered_delivery,
+            data_coding,
+            tlvs,
+        })
+    }
+
+    /// If this is a delivery receipt, the message_id of the message it is
+    /// about: from the receipted_message_id TLV if there is one, or else
+    /// from the "id:" field of the receipt text in message_payload.  The
+    /// same rules as DeliverSmPdu::extract_receipted_message_id.
+    pub fn extract_receipted_message_id(&self) -> Option<String> {
+        if let Some(tlv) = self.tlvs.get(KnownTlvTag::receipted_message_id) {
+            return String::from_utf8(tlv.value).ok().map(|mut s| {
+                if s.ends_with('\0') {
+                    s.truncate(s.len() - 1)
+                }
+                s
+            });
+        }
+
+        let payload = self.tlvs.get(KnownTlvTag::message_payload)?;
+        str::from_utf8(&payload.value).ok().and_then(|text| {
+            text.split_whitespace().find_map(|word| {
+                match (word.get(..3), word.get(3..)) {
+                    (Some(key), Some(id))
+                        if key.eq_ignore_ascii_case("id:") =>
+                    {
+                        Some(String::from(id))
+                    }
+                    _ => None,
+                }
+            })
+        })
+    }
+
+    pub fn source_addr(&self) -> String {
+        self.source_addr.value.to_string()
+    }
+}
+
+/// The response to a data_sm.  See https://smpp.org/SMPP_v3_4_Issue1_2.pdf
+/// section 4.7.2
+#[derive(Debug, PartialEq)]
+pub struct DataSmRespPdu {
+    /// The SMSC's id for the message, or empty in the response to a
+    /// delivery receipt
+    pub message_id: COctetString,
+    /// e.g. delivery_failure_reason
+    pub tlvs: Tlvs,
+}
+
+// Not derived because Tlvs is not Clone
+impl Clone for DataSmRespPdu {
+    fn clone(&self) -> Self {
+        Self {
+            message_id: self.message_id.clone(),
+            tlvs: clone_tlvs(&self.tlvs),
+        }
+    }
+}
+
+impl DataSmRespPdu {
+    pub fn new(message_id: &str, tlvs: Tlvs) -> Result<Self, PduParseError> {
+        Ok(Self {
+            message_id: fld(
+                "message_id",
+                COctetString::from_str(message_id, MAX_LENGTH_MESSAGE_ID),
+            )?,
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.message_id.write(stream).await?;
+        self.tlvs.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        let tlvs = Tlvs::read(bytes)?;
+        Ok(Self { message_id, tlvs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::Tlv;
+
+    fn data_sm(tlvs: &[Tlv]) -> DataSmPdu {
+        DataSmPdu::new(
+            "",
+            1,
+            1,
+            "447000123123",
+            0,
+            0,
+            "MyCompany",
+            0x04,
+            0,
+            0,
+            Tlvs::from(tlvs),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn receipted_message_id_tlv_is_preferred() {
+        let pdu = data_sm(&[
+            Tlv::new(KnownTlvTag::receipted_message_id, b"ab87J\0"),
+            Tlv::new(KnownTlvTag::message_payload, b"id:other stat:DELIVRD"),
+        ]);
+        assert_eq!(pdu.extract_receipted_message_id().unwrap(), "ab87J");
+    }
+
+    #[test]
+    fn without_the_tlv_the_id_comes_from_the_receipt_text() {
+        let pdu = data_sm(&[Tlv::new(
+            KnownTlvTag::message_payload,
+            b"ID:ab87J sub:001 dlvrd:001 stat:DELIVRD",
+        )]);
+        assert_eq!(pdu.extract_receipted_message_id().unwrap(), "ab87J");
+    }
+
+    #[test]
+    fn without_either_there_is_no_id() {
+        let pdu = data_sm(&[Tlv::new(KnownTlvTag::message_payload, b"hi")]);
+        assert_eq!(pdu.extract_receipted_message_id(), None);
+    }
+}
@@ -0,0 +1,38 @@
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+
+use crate::pdu::operations::fld;
+
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+
+/// Sent by an ESME to acknowledge a deliver_sm.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.6.2
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliverSmRespPdu {
+    /// Unused: the spec says this should be empty
+    pub message_id: COctetString,
+}
+
+impl DeliverSmRespPdu {
+    pub fn new(message_id: &str) -> Result<Self, PduParseError> {
+        Ok(Self {
+            message_id: fld(
+                "message_id",
+                COctetString::from_str(message_id, MAX_LENGTH_MESSAGE_ID),
+            )?,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.message_id.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        Ok(Self { message_id })
+    }
+}
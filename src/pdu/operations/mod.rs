@@ -0,0 +1,22 @@
+use smpp_pdu::pdu::PduParseError;
+
+pub mod alert_notification;
+pub mod cancel_sm;
+pub mod data_sm;
+pub mod deliver_sm_resp;
+pub mod outbind;
+pub mod query_sm;
+pub mod query_sm_resp;
+pub mod submit_multi;
+
+/// If the supplied result is an error, enrich it with the supplied field
+/// name.  Mirrors the helper of the same name inside smpp-pdu.
+pub(crate) fn fld<T, E>(
+    field_name: &str,
+    res: Result<T, E>,
+) -> Result<T, PduParseError>
+where
+    E: Into<PduParseError>,
+{
+    res.map_err(|e| e.into().into_with_field_name(field_name))
+}
@@ -0,0 +1,48 @@
+use smpp_pdu::pdu::formats::{COctetString, WriteStream};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+
+use crate::pdu::operations::fld;
+
+const MAX_LENGTH_SYSTEM_ID: usize = 16;
+const MAX_LENGTH_PASSWORD: usize = 9;
+
+/// Sent by an SMSC that has connected to an ESME, asking it to bind as a
+/// receiver.  See https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.1.7
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutbindPdu {
+    /// Identifies the SMSC to the ESME
+    pub system_id: COctetString,
+    pub password: COctetString,
+}
+
+impl OutbindPdu {
+    pub fn new(system_id: &str, password: &str) -> Result<Self, PduParseError> {
+        Ok(Self {
+            system_id: fld(
+                "system_id",
+                COctetString::from_str(system_id, MAX_LENGTH_SYSTEM_ID),
+            )?,
+            password: fld(
+                "password",
+                COctetString::from_str(password, MAX_LENGTH_PASSWORD),
+            )?,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.system_id.write(stream).await?;
+        self.password.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let system_id =
+            fld("system_id", COctetString::read(bytes, MAX_LENGTH_SYSTEM_ID))?;
+        let password =
+            fld("password", COctetString::read(bytes, MAX_LENGTH_PASSWORD))?;
+        Ok(Self {
+            system_id,
+            password,
+        })
+    }
+}
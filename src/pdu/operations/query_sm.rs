@@ -0,0 +1,67 @@
+use smpp_pdu::pdu::formats::{COctetString, Integer1, WriteStream};
+use smpp_pdu::pdu::PduParseError;
+use std::io;
+
+use crate::pdu::operations::fld;
+
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_SOURCE_ADDR: usize = 21;
+
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.8.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuerySmPdu {
+    /// The message_id we returned in the submit_sm_resp
+    pub message_id: COctetString,
+    /// Must match the source address of the original message
+    pub source_addr_ton: Integer1,
+    pub source_addr_npi: Integer1,
+    pub source_addr: COctetString,
+}
+
+impl QuerySmPdu {
+    pub fn new(
+        message_id: &str,
+        source_addr_ton: u8,
+        source_addr_npi: u8,
+        source_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            message_id: COctetString::from_str(
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            source_addr_ton: Integer1::new(source_addr_ton),
+            source_addr_npi: Integer1::new(source_addr_npi),
+            source_addr: COctetString::from_str(
+                source_addr,
+                MAX_LENGTH_SOURCE_ADDR,
+            )?,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.message_id.write(stream).await?;
+        self.source_addr_ton.write(stream).await?;
+        self.source_addr_npi.write(stream).await?;
+        self.source_addr.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        let source_addr_ton = fld("source_addr_ton", Integer1::read(bytes))?;
+        let source_addr_npi = fld("source_addr_npi", Integer1::read(bytes))?;
+        let source_addr = fld(
+            "source_addr",
+            COctetString::read(bytes, MAX_LENGTH_SOURCE_ADDR),
+        )?;
+        Ok(Self {
+            message_id,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+        })
+    }
+}
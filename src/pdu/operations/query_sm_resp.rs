@@ -0,0 +1,81 @@
+use smpp_pdu::pdu::formats::{COctetString, Integer1, WriteStream};
+use smpp_pdu::pdu::PduParseError;
+use std::convert::TryFrom;
+use std::io;
+
+use crate::pdu::operations::fld;
+use crate::pdu::MessageState;
+
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_LENGTH_FINAL_DATE: usize = 17;
+
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.8.2
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuerySmRespPdu {
+    pub message_id: COctetString,
+    /// Empty if the message has not reached a final state
+    pub final_date: COctetString,
+    pub message_state: MessageState,
+    /// Network-specific error code, or 0
+    pub error_code: Integer1,
+}
+
+impl QuerySmRespPdu {
+    pub fn new(
+        message_id: &str,
+        final_date: &str,
+        message_state: MessageState,
+        error_code: u8,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            message_id: COctetString::from_str(
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            final_date: COctetString::from_str(
+                final_date,
+                MAX_LENGTH_FINAL_DATE,
+            )?,
+            message_state,
+            error_code: Integer1::new(error_code),
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.message_id.write(stream).await?;
+        self.final_date.write(stream).await?;
+        Integer1::new(self.message_state.as_u8())
+            .write(stream)
+            .await?;
+        self.error_code.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        let final_date = fld(
+            "final_date",
+            COctetString::read(bytes, MAX_LENGTH_FINAL_DATE),
+        )?;
+        let message_state = fld(
+            "message_state",
+            Integer1::read(bytes).and_then(|state| {
+                MessageState::try_from(state.value).map_err(|value| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown message_state {:#04X}.", value),
+                    )
+                })
+            }),
+        )?;
+        let error_code = fld("error_code", Integer1::read(bytes))?;
+        Ok(Self {
+            message_id,
+            final_date,
+            message_state,
+            error_code,
+        })
+    }
+}
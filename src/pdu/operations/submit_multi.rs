@@ -0,0 +1,470 @@
+use smpp_pdu::pdu::formats::{
+    COctetString, Integer1, Integer4, OctetString, WriteStream,
+};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{PduParseError, PduParseErrorBody};
+use std::io;
+
+use crate::pdu::operations::fld;
+use crate::pdu::pdu_ext::clone_tlvs;
+
+const MAX_LENGTH_SERVICE_TYPE: usize = 6;
+const MAX_LENGTH_ADDR: usize = 21;
+const MAX_LENGTH_DL_NAME: usize = 21;
+const MAX_LENGTH_SCHEDULE_DELIVERY_TIME: usize = 17;
+const MAX_LENGTH_VALIDITY_PERIOD: usize = 17;
+const MAX_LENGTH_SHORT_MESSAGE: usize = 254;
+const MAX_LENGTH_MESSAGE_ID: usize = 65;
+const MAX_NUMBER_OF_DESTS: usize = 255;
+
+const DEST_FLAG_SME_ADDRESS: u8 = 0x01;
+const DEST_FLAG_DISTRIBUTION_LIST: u8 = 0x02;
+
+/// One of the destinations of a submit_multi.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.5.1.1
+#[derive(Clone, Debug, PartialEq)]
+pub enum DestAddress {
+    SmeAddress {
+        dest_addr_ton: Integer1,
+        dest_addr_npi: Integer1,
+        destination_addr: COctetString,
+    },
+    DistributionList {
+        dl_name: COctetString,
+    },
+}
+
+impl DestAddress {
+    pub fn sme_address(
+        dest_addr_ton: u8,
+        dest_addr_npi: u8,
+        destination_addr: &str,
+    ) -> Result<Self, PduParseError> {
+        Ok(DestAddress::SmeAddress {
+            dest_addr_ton: Integer1::new(dest_addr_ton),
+            dest_addr_npi: Integer1::new(dest_addr_npi),
+            destination_addr: COctetString::from_str(
+                destination_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+        })
+    }
+
+    pub fn distribution_list(dl_name: &str) -> Result<Self, PduParseError> {
+        Ok(DestAddress::DistributionList {
+            dl_name: COctetString::from_str(dl_name, MAX_LENGTH_DL_NAME)?,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        match self {
+            DestAddress::SmeAddress {
+                dest_addr_ton,
+                dest_addr_npi,
+                destination_addr,
+            } => {
+                Integer1::new(DEST_FLAG_SME_ADDRESS).write(stream).await?;
+                dest_addr_ton.write(stream).await?;
+                dest_addr_npi.write(stream).await?;
+                destination_addr.write(stream).await
+            }
+            DestAddress::DistributionList { dl_name } => {
+                Integer1::new(DEST_FLAG_DISTRIBUTION_LIST)
+                    .write(stream)
+                    .await?;
+                dl_name.write(stream).await
+            }
+        }
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let dest_flag = fld("dest_flag", Integer1::read(bytes))?;
+        match dest_flag.value {
+            DEST_FLAG_SME_ADDRESS => {
+                let dest_addr_ton =
+                    fld("dest_addr_ton", Integer1::read(bytes))?;
+                let dest_addr_npi =
+                    fld("dest_addr_npi", Integer1::read(bytes))?;
+                let destination_addr = fld(
+                    "destination_addr",
+                    COctetString::read(bytes, MAX_LENGTH_ADDR),
+                )?;
+                Ok(DestAddress::SmeAddress {
+                    dest_addr_ton,
+                    dest_addr_npi,
+                    destination_addr,
+                })
+            }
+            DEST_FLAG_DISTRIBUTION_LIST => Ok(DestAddress::DistributionList {
+                dl_name: fld(
+                    "dl_name",
+                    COctetString::read(bytes, MAX_LENGTH_DL_NAME),
+                )?,
+            }),
+            value => Err(invalid_data(
+                "dest_flag",
+                format!("Unknown dest_flag {:#04X}.", value),
+            )),
+        }
+    }
+}
+
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.5.1
+#[derive(Debug, PartialEq)]
+pub struct SubmitMultiPdu {
+    pub service_type: COctetString,
+    pub source_addr_ton: Integer1,
+    pub source_addr_npi: Integer1,
+    pub source_addr: COctetString,
+    pub dest_addresses: Vec<DestAddress>,
+    pub esm_class: Integer1,
+    pub protocol_id: Integer1,
+    pub priority_flag: Integer1,
+    pub schedule_delivery_time: COctetString,
+    pub validity_period: COctetString,
+    pub registered_delivery: Integer1,
+    pub replace_if_present_flag: Integer1,
+    pub data_coding: Integer1,
+    pub sm_default_msg_id: Integer1,
+    pub short_message: OctetString,
+    pub tlvs: Tlvs,
+}
+
+// Not derived because Tlvs is not Clone
+impl Clone for SubmitMultiPdu {
+    fn clone(&self) -> Self {
+        Self {
+            service_type: self.service_type.clone(),
+            source_addr_ton: self.source_addr_ton.clone(),
+            source_addr_npi: self.source_addr_npi.clone(),
+            source_addr: self.source_addr.clone(),
+            dest_addresses: self.dest_addresses.clone(),
+            esm_class: self.esm_class.clone(),
+            protocol_id: self.protocol_id.clone(),
+            priority_flag: self.priority_flag.clone(),
+            schedule_delivery_time: self.schedule_delivery_time.clone(),
+            validity_period: self.validity_period.clone(),
+            registered_delivery: self.registered_delivery.clone(),
+            replace_if_present_flag: self.replace_if_present_flag.clone(),
+            data_coding: self.data_coding.clone(),
+            sm_default_msg_id: self.sm_default_msg_id.clone(),
+            short_message: self.short_message.clone(),
+            tlvs: clone_tlvs(&self.tlvs),
+        }
+    }
+}
+
+impl SubmitMultiPdu {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        service_type: &str,
+        source_addr_ton: u8,
+        source_addr_npi: u8,
+        source_addr: &str,
+        dest_addresses: Vec<DestAddress>,
+        esm_class: u8,
+        protocol_id: u8,
+        priority_flag: u8,
+        schedule_delivery_time: &str,
+        validity_period: &str,
+        registered_delivery: u8,
+        replace_if_present_flag: u8,
+        data_coding: u8,
+        sm_default_msg_id: u8,
+        short_message: &[u8],
+        tlvs: Tlvs,
+    ) -> Result<Self, PduParseError> {
+        if dest_addresses.len() > MAX_NUMBER_OF_DESTS {
+            return Err(invalid_data(
+                "number_of_dests",
+                format!(
+                    "submit_multi may have at most {} destinations, not {}.",
+                    MAX_NUMBER_OF_DESTS,
+                    dest_addresses.len()
+                ),
+            ));
+        }
+
+        Ok(Self {
+            service_type: COctetString::from_str(
+                service_type,
+                MAX_LENGTH_SERVICE_TYPE,
+            )?,
+            source_addr_ton: Integer1::new(source_addr_ton),
+            source_addr_npi: Integer1::new(source_addr_npi),
+            source_addr: COctetString::from_str(source_addr, MAX_LENGTH_ADDR)?,
+            dest_addresses,
+            esm_class: Integer1::new(esm_class),
+            protocol_id: Integer1::new(protocol_id),
+            priority_flag: Integer1::new(priority_flag),
+            schedule_delivery_time: COctetString::from_str(
+                schedule_delivery_time,
+                MAX_LENGTH_SCHEDULE_DELIVERY_TIME,
+            )?,
+            validity_period: COctetString::from_str(
+                validity_period,
+                MAX_LENGTH_VALIDITY_PERIOD,
+            )?,
+            registered_delivery: Integer1::new(registered_delivery),
+            replace_if_present_flag: Integer1::new(replace_if_present_flag),
+            data_coding: Integer1::new(data_coding),
+            sm_default_msg_id: Integer1::new(sm_default_msg_id),
+            short_message: fld(
+                "short_message",
+                OctetString::from_bytes(
+                    short_message,
+                    MAX_LENGTH_SHORT_MESSAGE,
+                ),
+            )?,
+            tlvs,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.service_type.write(stream).await?;
+        self.source_addr_ton.write(stream).await?;
+        self.source_addr_npi.write(stream).await?;
+        self.source_addr.write(stream).await?;
+        Integer1::new(self.dest_addresses.len() as u8)
+            .write(stream)
+            .await?;
+        for dest_address in &self.dest_addresses {
+            dest_address.write(stream).await?;
+        }
+        self.esm_class.write(stream).await?;
+        self.protocol_id.write(stream).await?;
+        self.priority_flag.write(stream).await?;
+        self.schedule_delivery_time.write(stream).await?;
+        self.validity_period.write(stream).await?;
+        self.registered_delivery.write(stream).await?;
+        self.replace_if_present_flag.write(stream).await?;
+        self.data_coding.write(stream).await?;
+        self.sm_default_msg_id.write(stream).await?;
+        Integer1::new(self.short_message.len() as u8)
+            .write(stream)
+            .await?;
+        self.short_message.write(stream).await?;
+        self.tlvs.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let service_type = fld(
+            "service_type",
+            COctetString::read(bytes, MAX_LENGTH_SERVICE_TYPE),
+        )?;
+        let source_addr_ton = fld("source_addr_ton", Integer1::read(bytes))?;
+        let source_addr_npi = fld("source_addr_npi", Integer1::read(bytes))?;
+        let source_addr =
+            fld("source_addr", COctetString::read(bytes, MAX_LENGTH_ADDR))?;
+        let number_of_dests = fld("number_of_dests", Integer1::read(bytes))?;
+        let dest_addresses = (0..number_of_dests.value)
+            .map(|_| DestAddress::parse(bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let esm_class = fld("esm_class", Integer1::read(bytes))?;
+        let protocol_id = fld("protocol_id", Integer1::read(bytes))?;
+        let priority_flag = fld("priority_flag", Integer1::read(bytes))?;
+        let schedule_delivery_time = fld(
+            "schedule_delivery_time",
+            COctetString::read(bytes, MAX_LENGTH_SCHEDULE_DELIVERY_TIME),
+        )?;
+        let validity_period = fld(
+            "validity_period",
+            COctetString::read(bytes, MAX_LENGTH_VALIDITY_PERIOD),
+        )?;
+        let registered_delivery =
+            fld("registered_delivery", Integer1::read(bytes))?;
+        let replace_if_present_flag =
+            fld("replace_if_present_flag", Integer1::read(bytes))?;
+        let data_coding = fld("data_coding", Integer1::read(bytes))?;
+        let sm_default_msg_id =
+            fld("sm_default_msg_id", Integer1::read(bytes))?;
+        let sm_length = fld("sm_length", Integer1::read(bytes))?;
+        let short_message = fld(
+            "short_message",
+            OctetString::read(
+                bytes,
+                sm_length.value as usize,
+                MAX_LENGTH_SHORT_MESSAGE,
+            ),
+        )?;
+        let tlvs = Tlvs::read(bytes)?;
+
+        Ok(Self {
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addresses,
+            esm_class,
+            protocol_id,
+            priority_flag,
+            schedule_delivery_time,
+            validity_period,
+            registered_delivery,
+            replace_if_present_flag,
+            data_coding,
+            sm_default_msg_id,
+            short_message,
+            tlvs,
+        })
+    }
+}
+
+/// A destination that a submit_multi could not be sent to.  See
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.5.2.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsuccessSme {
+    pub dest_addr_ton: Integer1,
+    pub dest_addr_npi: Integer1,
+    pub destination_addr: COctetString,
+    /// The command_status we would have returned for a submit_sm to this
+    /// destination
+    pub error_status_code: Integer4,
+}
+
+impl UnsuccessSme {
+    pub fn new(
+        dest_addr_ton: u8,
+        dest_addr_npi: u8,
+        destination_addr: &str,
+        error_status_code: u32,
+    ) -> Result<Self, PduParseError> {
+        Ok(Self {
+            dest_addr_ton: Integer1::new(dest_addr_ton),
+            dest_addr_npi: Integer1::new(dest_addr_npi),
+            destination_addr: COctetString::from_str(
+                destination_addr,
+                MAX_LENGTH_ADDR,
+            )?,
+            error_status_code: Integer4::new(error_status_code),
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.dest_addr_ton.write(stream).await?;
+        self.dest_addr_npi.write(stream).await?;
+        self.destination_addr.write(stream).await?;
+        self.error_status_code.write(stream).await
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let dest_addr_ton = fld("dest_addr_ton", Integer1::read(bytes))?;
+        let dest_addr_npi = fld("dest_addr_npi", Integer1::read(bytes))?;
+        let destination_addr = fld(
+            "destination_addr",
+            COctetString::read(bytes, MAX_LENGTH_ADDR),
+        )?;
+        let error_status_code =
+            fld("error_status_code", Integer4::read(bytes))?;
+        Ok(Self {
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+            error_status_code,
+        })
+    }
+}
+
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 4.5.2
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubmitMultiRespPdu {
+    pub message_id: COctetString,
+    pub unsuccess_smes: Vec<UnsuccessSme>,
+}
+
+impl SubmitMultiRespPdu {
+    pub fn new(
+        message_id: &str,
+        unsuccess_smes: Vec<UnsuccessSme>,
+    ) -> Result<Self, PduParseError> {
+        if unsuccess_smes.len() > MAX_NUMBER_OF_DESTS {
+            return Err(invalid_data(
+                "no_unsuccess",
+                format!(
+                    "submit_multi_resp may have at most {} unsuccess_smes, \
+                    not {}.",
+                    MAX_NUMBER_OF_DESTS,
+                    unsuccess_smes.len()
+                ),
+            ));
+        }
+        Ok(Self {
+            message_id: COctetString::from_str(
+                message_id,
+                MAX_LENGTH_MESSAGE_ID,
+            )?,
+            unsuccess_smes,
+        })
+    }
+
+    pub async fn write(&self, stream: &mut WriteStream) -> io::Result<()> {
+        self.message_id.write(stream).await?;
+        Integer1::new(self.unsuccess_smes.len() as u8)
+            .write(stream)
+            .await?;
+        for unsuccess_sme in &self.unsuccess_smes {
+            unsuccess_sme.write(stream).await?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(bytes: &mut dyn io::BufRead) -> Result<Self, PduParseError> {
+        let message_id = fld(
+            "message_id",
+            COctetString::read(bytes, MAX_LENGTH_MESSAGE_ID),
+        )?;
+        let no_unsuccess = fld("no_unsuccess", Integer1::read(bytes))?;
+        let unsuccess_smes = (0..no_unsuccess.value)
+            .map(|_| UnsuccessSme::parse(bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            message_id,
+            unsuccess_smes,
+        })
+    }
+}
+
+fn invalid_data(field_name: &str, message: String) -> PduParseError {
+    PduParseError::new(PduParseErrorBody::OtherIoError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        message,
+    )))
+    .into_with_field_name(field_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_one_sme_and_one_distribution_list() {
+        let mut bytes = io::BufReader::new(
+            &b"\x01\x01\x01447111222222\x00\x02friends\x00"[..],
+        );
+
+        let sme = DestAddress::parse(&mut bytes).unwrap();
+        let dl = DestAddress::parse(&mut bytes).unwrap();
+
+        match sme {
+            DestAddress::SmeAddress {
+                dest_addr_ton,
+                dest_addr_npi,
+                destination_addr,
+            } => {
+                assert_eq!(dest_addr_ton.value, 1);
+                assert_eq!(dest_addr_npi.value, 1);
+                assert_eq!(destination_addr.value, "447111222222");
+            }
+            _ => panic!("Expected an SME address, got {:?}", sme),
+        }
+        assert_eq!(dl, DestAddress::distribution_list("friends").unwrap());
+    }
+
+    #[test]
+    fn distribution_list_name_that_is_too_long_fails_to_parse() {
+        let mut bytes =
+            io::BufReader::new(&b"\x02abcdefghijklmnopqrstuvwxyz\x00"[..]);
+        let err = DestAddress::parse(&mut bytes).unwrap_err();
+        assert!(err.to_string().contains("dl_name"), "{}", err);
+    }
+}
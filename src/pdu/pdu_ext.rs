@@ -0,0 +1,292 @@
+use smpp_pdu::pdu::formats::Integer4;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{GenericNackPdu, Pdu, PduBody, PduParseError};
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::pdu::encoding::MAX_LENGTH_SHORT_MESSAGE;
+use crate::pdu::hex_dump::hex_dump;
+
+pub trait PduExt {
+    /// A generic_nack with the supplied command_status, which must not be
+    /// zero.  The sequence_number is used as-is, even if it is not valid
+    /// (e.g. zero), so that the client can match the nack to the PDU it
+    /// sent.
+    fn generic_nack(command_status: u32, sequence_number: u32) -> Pdu;
+
+    /// A copy of this PDU, e.g. to send the same DR to several clients.
+    /// smpp-pdu's types are not Clone, so we write the PDU out and parse
+    /// it back, which fails only if the PDU could not have been parsed in
+    /// the first place.
+    fn try_clone(&self) -> Result<Pdu, PduParseError>;
+
+    /// The bytes write_pdu would send, as an annotated hex dump: see
+    /// hex_dump.
+    fn to_hex_dump(&self) -> String;
+}
+
+impl PduExt for Pdu {
+    fn generic_nack(command_status: u32, sequence_number: u32) -> Pdu {
+        let mut pdu =
+            Pdu::new(command_status, 1, GenericNackPdu::new_error().into())
+                .expect("generic_nack requires a non-zero command_status");
+        pdu.sequence_number = Integer4::new(sequence_number);
+        pdu
+    }
+
+    fn try_clone(&self) -> Result<Pdu, PduParseError> {
+        let mut buf = Vec::new();
+        // Writing to a Vec completes without waiting
+        futures::executor::block_on(self.write(&mut buf))?;
+        Pdu::parse(&mut io::Cursor::new(buf))
+    }
+
+    fn to_hex_dump(&self) -> String {
+        let mut buf = Vec::new();
+        match futures::executor::block_on(write_pdu(self, &mut buf)) {
+            Ok(()) => hex_dump(&buf),
+            Err(e) => format!("Unable to write PDU: {}", e),
+        }
+    }
+}
+
+/// A copy of some TLVs.  Like Pdu, Tlvs is not Clone, and does not let us
+/// see its TLVs, so we copy it by writing it out and reading it back.
+pub(crate) fn clone_tlvs(tlvs: &Tlvs) -> Tlvs {
+    let mut buf = Vec::new();
+    futures::executor::block_on(tlvs.write(&mut buf))
+        .expect("Writing to a Vec cannot fail");
+    Tlvs::read(&mut io::Cursor::new(buf))
+        .expect("TLVs we just wrote must be readable")
+}
+
+/// Write the whole of a PDU to stream.  Pdu::write writes the body with a
+/// single write(), which may write only part of it, leaving the stream
+/// corrupt.  We build the PDU in memory and use write_all() instead.
+/// Pdu::write also panics if a short_message was made too long for
+/// sm_length after the PDU was created, so we check that first.  Unlike
+/// Pdu::write, this takes any AsyncWrite, not just a WriteStream.
+pub async fn write_pdu<W: AsyncWrite + Unpin + ?Sized>(
+    pdu: &Pdu,
+    stream: &mut W,
+) -> io::Result<()> {
+    check_short_message_length(pdu)?;
+    let mut buf = Vec::new();
+    pdu.write(&mut buf).await?;
+    stream.write_all(&buf).await
+}
+
+/// sm_length is written from the length of short_message, so it must fit
+/// in one octet.  SubmitSmPdu::new and DeliverSmPdu::new check this, but
+/// short_message is a public field.
+fn check_short_message_length(pdu: &Pdu) -> io::Result<()> {
+    let short_message = match pdu.body() {
+        PduBody::SubmitSm(body) => &body.0.short_message,
+        PduBody::DeliverSm(body) => &body.0.short_message,
+        _ => return Ok(()),
+    };
+    if short_message.len() > MAX_LENGTH_SHORT_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "short_message is {} octets long, but sm_length allows at \
+                most {}.  Send longer messages in the message_payload TLV.",
+                short_message.len(),
+                MAX_LENGTH_SHORT_MESSAGE
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::SubmitSmPduBuilder;
+    use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+    use smpp_pdu::pdu::{
+        BindTransmitterRespPdu, EnquireLinkPdu, PduStatus, SubmitSmPdu,
+        SubmitSmRespPdu,
+    };
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::AsyncWrite;
+
+    /// A stream that accepts at most 3 bytes per write
+    #[derive(Default)]
+    struct ShortWrites {
+        written: Vec<u8>,
+    }
+
+    impl AsyncWrite for ShortWrites {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let n = buf.len().min(3);
+            self.written.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn write_pdu_writes_everything_to_a_stream_that_short_writes() {
+        let pdu =
+            Pdu::new(0, 0x12, SubmitSmRespPdu::new("abcdefgh").unwrap().into())
+                .unwrap();
+
+        let mut stream = ShortWrites::default();
+        write_pdu(&pdu, &mut stream).await.unwrap();
+
+        assert_eq!(
+            stream.written,
+            b"\x00\x00\x00\x19\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x12\
+            abcdefgh\x00"
+        );
+    }
+
+    #[tokio::test]
+    async fn write_pdu_writes_header_only_pdus() {
+        let pdu = Pdu::new(0, 0x12, EnquireLinkPdu::new().into()).unwrap();
+
+        let mut stream = ShortWrites::default();
+        write_pdu(&pdu, &mut stream).await.unwrap();
+
+        assert_eq!(
+            stream.written,
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12"
+        );
+    }
+
+    #[tokio::test]
+    async fn generic_nack_has_the_supplied_status_and_sequence_number() {
+        let pdu = Pdu::generic_nack(PduStatus::ESME_RINVCMDID as u32, 0x22);
+        assert_eq!(pdu.command_id().value, 0x80000000);
+        assert_eq!(pdu.command_status.value, 0x00000003);
+        assert_eq!(pdu.sequence_number.value, 0x22);
+
+        let mut buf: Vec<u8> = Vec::new();
+        pdu.write(&mut buf).await.unwrap();
+        assert_eq!(
+            buf,
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x22"
+        );
+    }
+
+    #[test]
+    fn generic_nack_echoes_invalid_sequence_number() {
+        let pdu = Pdu::generic_nack(PduStatus::ESME_RSYSERR as u32, 0);
+        assert_eq!(pdu.sequence_number.value, 0);
+    }
+
+    fn submit_sm(short_message: &[u8]) -> Result<Pdu, PduParseError> {
+        Pdu::new(0, 0x12, submit_sm_body(short_message)?.into())
+    }
+
+    fn submit_sm_body(
+        short_message: &[u8],
+    ) -> Result<SubmitSmPdu, PduParseError> {
+        SubmitSmPduBuilder::new()
+            .short_message(short_message)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn cloned_submit_sm_is_equal_and_writes_identical_bytes() {
+        let mut body = submit_sm_body(b"hello").unwrap();
+        body.0.tlvs = Tlvs::from(&[Tlv::new(
+            KnownTlvTag::user_message_reference,
+            &[0, 7],
+        )]);
+        let pdu = Pdu::new(0, 0x12, body.into()).unwrap();
+
+        let clone = pdu.try_clone().unwrap();
+        assert_eq!(clone, pdu);
+
+        let mut original_bytes = ShortWrites::default();
+        write_pdu(&pdu, &mut original_bytes).await.unwrap();
+        let mut clone_bytes = ShortWrites::default();
+        write_pdu(&clone, &mut clone_bytes).await.unwrap();
+        assert_eq!(clone_bytes.written, original_bytes.written);
+    }
+
+    #[test]
+    fn hex_dump_of_bind_transmitter_resp_names_its_fields() {
+        let pdu =
+            Pdu::new(0, 1, BindTransmitterRespPdu::new("abc").unwrap().into())
+                .unwrap();
+
+        let dump = pdu.to_hex_dump();
+
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 5, "{}", dump);
+        assert!(lines[0].starts_with("00000000  00 00 00 14 "), "{}", dump);
+        assert!(lines[0].ends_with(" command_length"), "{}", dump);
+        assert!(lines[1].starts_with("00000004  80 00 00 02 "), "{}", dump);
+        assert!(lines[1].ends_with(" command_id"), "{}", dump);
+        assert!(lines[4].starts_with("00000010  61 62 63 00 "), "{}", dump);
+        assert!(lines[4].ends_with(" system_id"), "{}", dump);
+    }
+
+    #[test]
+    fn cloned_tlvs_are_equal() {
+        let tlvs = Tlvs::from(&[
+            Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]),
+            Tlv::new_unknown(0x1400, b"x"),
+        ]);
+        assert_eq!(clone_tlvs(&tlvs), tlvs);
+    }
+
+    #[tokio::test]
+    async fn write_pdu_writes_the_longest_short_message_with_its_length() {
+        // SubmitSmPdu::new stops at 253 octets, but the builder allows 254
+        let pdu = submit_sm(&[b'x'; 254]).unwrap();
+
+        let mut stream = ShortWrites::default();
+        write_pdu(&pdu, &mut stream).await.unwrap();
+
+        // sm_length is just before short_message, which ends the PDU
+        let sm_length = stream.written.len() - 254 - 1;
+        assert_eq!(stream.written[sm_length], 254);
+        assert_eq!(&stream.written[sm_length + 1..], &[b'x'; 254][..]);
+    }
+
+    #[test]
+    fn short_message_longer_than_sm_length_allows_is_rejected() {
+        let e = submit_sm(&[b'x'; 255]).unwrap_err();
+        assert_eq!(e.status(), PduStatus::ESME_RINVMSGLEN as u32);
+    }
+
+    #[tokio::test]
+    async fn write_pdu_rejects_a_short_message_made_too_long_later() {
+        let mut body = submit_sm_body(b"hi").unwrap();
+        body.0.short_message.value = vec![b'x'; 300];
+        let pdu = Pdu::new(0, 0x12, body.into()).unwrap();
+
+        let mut stream = ShortWrites::default();
+        let e = write_pdu(&pdu, &mut stream).await.unwrap_err();
+
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            e.to_string(),
+            "short_message is 300 octets long, but sm_length allows at most \
+            254.  Send longer messages in the message_payload TLV."
+        );
+        assert!(stream.written.is_empty());
+    }
+}
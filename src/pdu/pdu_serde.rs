@@ -0,0 +1,271 @@
+//! Serialize and Deserialize for PDUs, with the "serde" feature, e.g. to
+//! log PDUs as JSON and replay them later.  smpp-pdu's types can't derive
+//! them, so a PDU is stored as its bytes in hex, which always round-trips,
+//! next to its header fields for people to read:
+//!
+//! {"command_id":"submit_sm","command_status":0,"sequence_number":18,
+//! "bytes":"0000003d00000004..."}
+//!
+//! Only bytes is read when deserialising.  Use AnyPdu or ExtraPdu directly,
+//! or `#[serde(with = "smpp::pdu::pdu_serde")]` on a field of type Pdu.
+
+use serde::de::{self, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+use smpp_pdu::pdu::Pdu;
+use std::convert::TryInto;
+
+use crate::pdu::command_id::command_name;
+use crate::pdu::{write_pdu, AnyPdu, ExtraPdu};
+use crate::pdu_reader::parse_framed;
+
+/// The smallest PDU is a header with no body
+const HEADER_LENGTH: usize = 16;
+
+#[derive(Serialize)]
+struct PduJson {
+    command_id: String,
+    command_status: u32,
+    sequence_number: u32,
+    bytes: String,
+}
+
+#[derive(Deserialize)]
+struct PduBytes {
+    bytes: String,
+}
+
+pub fn serialize<S: Serializer>(
+    pdu: &Pdu,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut bytes = Vec::new();
+    // Writing to a Vec completes without waiting
+    futures::executor::block_on(write_pdu(pdu, &mut bytes))
+        .map_err(ser::Error::custom)?;
+    pdu_json(&bytes).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Pdu, D::Error> {
+    match AnyPdu::deserialize(deserializer)? {
+        AnyPdu::Pdu(pdu) => Ok(pdu),
+        AnyPdu::Extra(pdu) => Err(de::Error::custom(format!(
+            "{} is not a PDU type smpp-pdu supports.",
+            name(pdu.command_id().value)
+        ))),
+    }
+}
+
+impl Serialize for AnyPdu {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match self {
+            AnyPdu::Pdu(pdu) => serialize(pdu, serializer),
+            AnyPdu::Extra(pdu) => pdu.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyPdu {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let bytes = from_hex(&PduBytes::deserialize(deserializer)?.bytes)
+            .map_err(de::Error::custom)?;
+        parse(&bytes).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ExtraPdu {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        futures::executor::block_on(self.write(&mut bytes))
+            .map_err(ser::Error::custom)?;
+        pdu_json(&bytes).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtraPdu {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        match AnyPdu::deserialize(deserializer)? {
+            AnyPdu::Extra(pdu) => Ok(pdu),
+            AnyPdu::Pdu(pdu) => Err(de::Error::custom(format!(
+                "{} is not an ExtraPdu.",
+                name(pdu.command_id().value)
+            ))),
+        }
+    }
+}
+
+/// The JSON for a whole PDU, which must be at least a header long
+fn pdu_json(bytes: &[u8]) -> PduJson {
+    PduJson {
+        command_id: name(integer4(&bytes[4..8])),
+        command_status: integer4(&bytes[8..12]),
+        sequence_number: integer4(&bytes[12..16]),
+        bytes: to_hex(bytes),
+    }
+}
+
+fn parse(bytes: &[u8]) -> Result<AnyPdu, String> {
+    if bytes.len() < HEADER_LENGTH {
+        return Err(format!(
+            "A PDU must be at least {} bytes long, but this is {}.",
+            HEADER_LENGTH,
+            bytes.len()
+        ));
+    }
+    let command_length = integer4(&bytes[0..4]) as usize;
+    if command_length != bytes.len() {
+        return Err(format!(
+            "command_length is {}, but the PDU is {} bytes long.",
+            command_length,
+            bytes.len()
+        ));
+    }
+    parse_framed(bytes).map_err(|e| e.to_string())
+}
+
+fn integer4(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes(bytes.try_into().expect("4 bytes"))
+}
+
+fn name(command_id: u32) -> String {
+    command_name(command_id)
+        .map(String::from)
+        .unwrap_or_else(|| format!("{:#010X}", command_id))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(String::from("PDU hex has an odd number of digits."));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| {
+                    format!("PDU hex has an invalid digit near offset {}.", i)
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+
+    use crate::pdu::{ExtraPduBody, SubmitSmPduBuilder};
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Logged {
+        #[serde(with = "crate::pdu::pdu_serde")]
+        pdu: Pdu,
+    }
+
+    fn submit_sm() -> Pdu {
+        let body = SubmitSmPduBuilder::new()
+            .source_addr("447000123123")
+            .destination_addr("447111222222")
+            .short_message(b"hello \x00\xff")
+            .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]))
+            .build()
+            .unwrap();
+        Pdu::new(0, 0x12, body.into()).unwrap()
+    }
+
+    #[test]
+    fn submit_sm_round_trips_through_json() {
+        let logged = Logged { pdu: submit_sm() };
+
+        let json = serde_json::to_string(&logged).unwrap();
+        assert!(
+            json.starts_with(
+                "{\"pdu\":{\"command_id\":\"submit_sm\",\"command_status\":0,\
+                \"sequence_number\":18,\"bytes\":\""
+            ),
+            "{}",
+            json
+        );
+
+        let read: Logged = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, logged);
+    }
+
+    #[test]
+    fn extra_pdu_round_trips_through_json_as_any_pdu() {
+        let pdu = ExtraPdu::new(0, 0x22, ExtraPduBody::Unbind);
+
+        let json = serde_json::to_string(&AnyPdu::Extra(pdu.clone())).unwrap();
+        assert_eq!(
+            json,
+            "{\"command_id\":\"unbind\",\"command_status\":0,\
+            \"sequence_number\":34,\
+            \"bytes\":\"00000010000000060000000000000022\"}"
+        );
+
+        match serde_json::from_str(&json).unwrap() {
+            AnyPdu::Extra(read) => assert_eq!(read, pdu),
+            AnyPdu::Pdu(read) => panic!("Expected unbind, got {:?}", read),
+        }
+    }
+
+    #[test]
+    fn only_bytes_is_read() {
+        let logged: Logged = serde_json::from_str(
+            "{\"pdu\":{\"bytes\":\"00000010000000150000000000000007\"}}",
+        )
+        .unwrap();
+        assert_eq!(logged.pdu.command_id().value, 0x15);
+        assert_eq!(logged.pdu.sequence_number.value, 7);
+    }
+
+    #[test]
+    fn bad_bytes_are_reported() {
+        for (bytes, error) in [
+            ("0000001", "PDU hex has an odd number of digits."),
+            ("000000zz", "PDU hex has an invalid digit near offset 6."),
+            (
+                "00000010",
+                "A PDU must be at least 16 bytes long, but this is 4.",
+            ),
+            (
+                "00000011000000150000000000000007",
+                "command_length is 17, but the PDU is 16 bytes long.",
+            ),
+        ] {
+            let json = format!("{{\"pdu\":{{\"bytes\":\"{}\"}}}}", bytes);
+            let e = serde_json::from_str::<Logged>(&json).unwrap_err();
+            assert!(e.to_string().starts_with(error), "{}", e);
+        }
+    }
+
+    #[test]
+    fn extra_pdu_is_not_a_pdu() {
+        let e = serde_json::from_str::<Logged>(
+            "{\"pdu\":{\"bytes\":\"00000010000000060000000000000022\"}}",
+        )
+        .unwrap_err();
+        assert!(
+            e.to_string()
+                .starts_with("unbind is not a PDU type smpp-pdu supports."),
+            "{}",
+            e
+        );
+    }
+}
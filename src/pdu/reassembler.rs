@@ -0,0 +1,274 @@
+use smpp_pdu::pdu::DeliverSmPdu;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{Display, Formatter};
+
+use crate::pdu::udh::UserData;
+use crate::pdu::{gsm7, DataCoding, DecodeError, DecodedText};
+
+/// Collects the parts of concatenated MOs (deliver_sm PDUs whose UDH
+/// contains concatenation information) until the whole message has
+/// arrived.  Parts of messages that never complete are kept until the
+/// Reassembler is dropped.
+#[derive(Default)]
+pub struct Reassembler {
+    messages: HashMap<(String, u16, u8), PartialMessage>,
+}
+
+struct PartialMessage {
+    data_coding: DataCoding,
+    /// The user data of each part (unpacked septets for GSM 7-bit),
+    /// excluding its UDH, keyed by sequence number
+    parts: BTreeMap<u8, Vec<u8>>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a deliver_sm to the messages being reassembled.  Returns the
+    /// full text of the message if this PDU completes it, or None if we
+    /// are still waiting for more parts.  A PDU that is not part of a
+    /// concatenated message is returned immediately.  A part with an
+    /// impossible sequence number, or a different data_coding from the
+    /// first part we received, is rejected and does not count towards
+    /// its message.
+    pub fn add(
+        &mut self,
+        pdu: &DeliverSmPdu,
+    ) -> Result<Option<String>, ReassembleError> {
+        let (udh_len, concat_info) = match pdu.udh() {
+            Some((udh, udh_len)) => match udh.concat_info() {
+                Some(concat_info) => (udh_len, concat_info),
+                None => return Ok(Some(without_udh(pdu, udh_len)?)),
+            },
+            None => return Ok(Some(pdu.decoded_text()?)),
+        };
+
+        if concat_info.sequence == 0 || concat_info.sequence > concat_info.total
+        {
+            return Err(ReassembleError::InvalidSequence {
+                sequence: concat_info.sequence,
+                total: concat_info.total,
+            });
+        }
+        let data_coding = DataCoding::try_from(pdu.0.data_coding.value)?;
+        let key = (pdu.source_addr(), concat_info.reference, concat_info.total);
+        let message = self.messages.entry(key.clone()).or_insert_with(|| {
+            PartialMessage {
+                data_coding,
+                parts: BTreeMap::new(),
+            }
+        });
+        if message.data_coding != data_coding {
+            return Err(ReassembleError::DataCodingMismatch {
+                expected: message.data_coding,
+                actual: data_coding,
+            });
+        }
+        message.parts.insert(
+            concat_info.sequence,
+            user_data(data_coding, &pdu.0.short_message.value, udh_len),
+        );
+
+        if !(1..=concat_info.total)
+            .all(|sequence| message.parts.contains_key(&sequence))
+        {
+            return Ok(None);
+        }
+
+        let message = self.messages.remove(&key).unwrap();
+        let all: Vec<u8> = message.parts.into_values().flatten().collect();
+        match message.data_coding {
+            DataCoding::Gsm7 => Ok(Some(gsm7::decode_septets(&all))),
+            data_coding => Ok(Some(data_coding.decode(&all)?)),
+        }
+    }
+}
+
+/// Why Reassembler::add() rejected a deliver_sm
+#[derive(Debug, PartialEq)]
+pub enum ReassembleError {
+    /// The UDH gave a sequence number of 0, or one beyond the total number
+    /// of parts
+    InvalidSequence {
+        sequence: u8,
+        total: u8,
+    },
+    /// The part's data_coding differs from that of the first part we
+    /// received
+    DataCodingMismatch {
+        expected: DataCoding,
+        actual: DataCoding,
+    },
+    Decode(DecodeError),
+}
+
+impl From<DecodeError> for ReassembleError {
+    fn from(decode_error: DecodeError) -> Self {
+        ReassembleError::Decode(decode_error)
+    }
+}
+
+impl Display for ReassembleError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        let s = match self {
+            ReassembleError::InvalidSequence { sequence, total } => format!(
+                "Concatenated message part has sequence number {}, which \
+                is not between 1 and its total of {}.",
+                sequence, total
+            ),
+            ReassembleError::DataCodingMismatch { expected, actual } => {
+                format!(
+                    "Concatenated message part has data_coding {:?}, but \
+                    the earlier parts had {:?}.",
+                    actual, expected
+                )
+            }
+            ReassembleError::Decode(e) => e.to_string(),
+        };
+        formatter.write_str(&s)
+    }
+}
+
+impl error::Error for ReassembleError {}
+
+/// The user data that follows a UDH.  For GSM 7-bit, the text starts on
+/// the next septet boundary after the UDH.
+fn user_data(
+    data_coding: DataCoding,
+    short_message: &[u8],
+    udh_len: usize,
+) -> Vec<u8> {
+    match data_coding {
+        DataCoding::Gsm7 => gsm7::unpack(short_message)
+            .into_iter()
+            .skip((udh_len * 8).div_ceil(7))
+            .collect(),
+        _ => short_message[udh_len..].to_vec(),
+    }
+}
+
+fn without_udh(
+    pdu: &DeliverSmPdu,
+    udh_len: usize,
+) -> Result<String, DecodeError> {
+    let data_coding = DataCoding::try_from(pdu.0.data_coding.value)?;
+    let data = user_data(data_coding, &pdu.0.short_message.value, udh_len);
+    match data_coding {
+        DataCoding::Gsm7 => Ok(gsm7::decode_septets(&data)),
+        data_coding => data_coding.decode(&data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    /// The body of a deliver_sm from 447777111111 with esm_class UDHI and
+    /// the supplied data_coding and short_message
+    fn mo(data_coding: u8, short_message: &[u8]) -> DeliverSmPdu {
+        let mut body: Vec<u8> = Vec::new();
+        body.extend(b"\x00\x05\x01447777111111\x00\x01\x01MyCompany\x00");
+        body.extend(&[0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        body.extend(&[data_coding, 0x00, short_message.len() as u8]);
+        body.extend(short_message);
+
+        DeliverSmPdu::parse(&mut io::BufReader::new(body.as_slice()), 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn two_ucs2_parts_are_reassembled() {
+        let part1 = mo(0x08, b"\x05\x00\x03\x2a\x02\x01\x00H\x00e\x00l");
+        let part2 = mo(0x08, b"\x05\x00\x03\x2a\x02\x02\x00l\x00o\x20\xac");
+        assert_eq!(part1.concat_info().unwrap().sequence, 1);
+
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.add(&part2).unwrap(), None);
+        assert_eq!(
+            reassembler.add(&part1).unwrap(),
+            Some(String::from("Hello€"))
+        );
+    }
+
+    #[test]
+    fn two_gsm7_parts_are_reassembled() {
+        // Each part is a 6-octet UDH, 1 fill bit, then "hello" or "world"
+        // packed starting at the 8th septet
+        let part1 = mo(0x00, b"\x05\x00\x03\x2a\x02\x01\xd0\x65\x36\xfb\x0d");
+        let part2 = mo(0x00, b"\x05\x00\x03\x2a\x02\x02\xee\x6f\x39\x9b\x0c");
+
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.add(&part1).unwrap(), None);
+        assert_eq!(
+            reassembler.add(&part2).unwrap(),
+            Some(String::from("helloworld"))
+        );
+    }
+
+    #[test]
+    fn parts_with_impossible_sequence_numbers_are_rejected() {
+        let part0 = mo(0x08, b"\x05\x00\x03\x2a\x02\x00\x00H\x00e\x00l");
+        let part3 = mo(0x08, b"\x05\x00\x03\x2a\x02\x03\x00l\x00o\x20\xac");
+        let part1 = mo(0x08, b"\x05\x00\x03\x2a\x02\x01\x00H\x00e\x00l");
+
+        let mut reassembler = Reassembler::new();
+        assert_eq!(
+            reassembler.add(&part0),
+            Err(ReassembleError::InvalidSequence {
+                sequence: 0,
+                total: 2
+            })
+        );
+        assert_eq!(
+            reassembler.add(&part3),
+            Err(ReassembleError::InvalidSequence {
+                sequence: 3,
+                total: 2
+            })
+        );
+
+        // Neither counts towards the message
+        assert_eq!(reassembler.add(&part1).unwrap(), None);
+    }
+
+    #[test]
+    fn parts_with_a_different_data_coding_are_rejected() {
+        let part1 = mo(0x08, b"\x05\x00\x03\x2a\x02\x01\x00H\x00e\x00l");
+        let latin1 = mo(0x03, b"\x05\x00\x03\x2a\x02\x02lo");
+        let part2 = mo(0x08, b"\x05\x00\x03\x2a\x02\x02\x00l\x00o\x20\xac");
+
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.add(&part1).unwrap(), None);
+        assert_eq!(
+            reassembler.add(&latin1),
+            Err(ReassembleError::DataCodingMismatch {
+                expected: DataCoding::Ucs2,
+                actual: DataCoding::Latin1
+            })
+        );
+
+        // The message still completes with the right part
+        assert_eq!(
+            reassembler.add(&part2).unwrap(),
+            Some(String::from("Hello€"))
+        );
+    }
+
+    #[test]
+    fn message_without_concat_info_is_returned_immediately() {
+        let pdu = mo(0x03, b"\x02\x24\x00hi");
+        assert_eq!(pdu.concat_info(), None);
+        assert_eq!(
+            Reassembler::new().add(&pdu).unwrap(),
+            Some(String::from("hi"))
+        );
+    }
+}
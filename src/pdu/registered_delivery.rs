@@ -0,0 +1,89 @@
+//! Reading which SMSC delivery receipts a submit_sm asks for, from bits
+//! 1-0 of its registered_delivery.  See
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 5.2.17
+
+use smpp_pdu::pdu::SubmitSmPdu;
+
+/// Bits 1-0 of registered_delivery: SMSC delivery receipt
+const SMSC_DELIVERY_RECEIPT: u8 = 0b11;
+
+/// A DR is wanted whether the message is delivered or fails
+const ON_SUCCESS_OR_FAILURE: u8 = 0b01;
+
+/// A DR is wanted only if the message fails
+const ON_FAILURE: u8 = 0b10;
+
+pub trait RegisteredDelivery {
+    /// Whether the client wants a DR in at least one outcome.  The reserved
+    /// value 0x03 is treated as not asking for one.
+    fn wants_delivery_receipt(&self) -> bool;
+
+    /// Whether the client wants a DR for a message that was delivered, or
+    /// for one that failed.
+    fn wants_delivery_receipt_for(&self, delivered: bool) -> bool;
+}
+
+impl RegisteredDelivery for SubmitSmPdu {
+    fn wants_delivery_receipt(&self) -> bool {
+        self.wants_delivery_receipt_for(true)
+            || self.wants_delivery_receipt_for(false)
+    }
+
+    fn wants_delivery_receipt_for(&self, delivered: bool) -> bool {
+        match self.0.registered_delivery.value & SMSC_DELIVERY_RECEIPT {
+            ON_SUCCESS_OR_FAILURE => true,
+            ON_FAILURE => !delivered,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::SubmitSmPduBuilder;
+
+    fn submit_sm(registered_delivery: u8) -> SubmitSmPdu {
+        SubmitSmPduBuilder::new()
+            .registered_delivery(registered_delivery)
+            .build()
+            .unwrap()
+    }
+
+    /// (wants any DR, wants one on success, wants one on failure)
+    fn wants(registered_delivery: u8) -> (bool, bool, bool) {
+        let pdu = submit_sm(registered_delivery);
+        (
+            pdu.wants_delivery_receipt(),
+            pdu.wants_delivery_receipt_for(true),
+            pdu.wants_delivery_receipt_for(false),
+        )
+    }
+
+    #[test]
+    fn no_receipt_requested() {
+        assert_eq!(wants(0x00), (false, false, false));
+    }
+
+    #[test]
+    fn receipt_on_success_or_failure() {
+        assert_eq!(wants(0x01), (true, true, true));
+    }
+
+    #[test]
+    fn receipt_on_failure_only() {
+        assert_eq!(wants(0x02), (true, false, true));
+    }
+
+    #[test]
+    fn reserved_value_requests_nothing() {
+        assert_eq!(wants(0x03), (false, false, false));
+    }
+
+    #[test]
+    fn other_bits_do_not_affect_receipts() {
+        // SME acknowledgement and intermediate notification bits
+        assert_eq!(wants(0x1d), (true, true, true));
+        assert_eq!(wants(0x10), (false, false, false));
+    }
+}
@@ -0,0 +1,445 @@
+//! Building a submit_sm or deliver_sm field by field, instead of passing
+//! all 18 arguments of SubmitSmPdu::new or DeliverSmPdu::new in order.
+//! Every field not set is zero or empty, which is the SMSC default where
+//! the spec defines one.
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::tlvs::{Tlv, Tlvs};
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, PduParseError, PduParseErrorBody,
+    SubmitSmPdu,
+};
+use std::marker::PhantomData;
+
+use crate::pdu::encoding::MAX_LENGTH_SHORT_MESSAGE;
+use crate::pdu::{DeliveryReceipt, MessageState};
+
+/// e.g. `SubmitSmPduBuilder::new().destination_addr("447777222222")
+/// .short_message(b"hello").build()`
+pub type SubmitSmPduBuilder = SmPduBuilder<SubmitSmPdu>;
+
+/// e.g. `DeliverSmPduBuilder::new().destination_addr("MyCompany")
+/// .delivery_receipt("ab87J", MessageState::Delivered).build()`
+pub type DeliverSmPduBuilder = SmPduBuilder<DeliverSmPdu>;
+
+/// The PDUs whose body is an SmData
+pub trait SmPdu {
+    fn from_sm_data(sm_data: SmData) -> Self;
+}
+
+impl SmPdu for SubmitSmPdu {
+    fn from_sm_data(sm_data: SmData) -> Self {
+        Self(sm_data)
+    }
+}
+
+impl SmPdu for DeliverSmPdu {
+    fn from_sm_data(sm_data: SmData) -> Self {
+        Self(sm_data)
+    }
+}
+
+#[derive(Debug)]
+pub struct SmPduBuilder<T> {
+    service_type: String,
+    source_addr_ton: u8,
+    source_addr_npi: u8,
+    source_addr: String,
+    dest_addr_ton: u8,
+    dest_addr_npi: u8,
+    destination_addr: String,
+    esm_class: u8,
+    protocol_id: u8,
+    priority_flag: u8,
+    schedule_delivery_time: String,
+    validity_period: String,
+    registered_delivery: u8,
+    replace_if_present_flag: u8,
+    data_coding: u8,
+    sm_default_msg_id: u8,
+    short_message: Vec<u8>,
+    tlvs: Vec<Tlv>,
+    pdu: PhantomData<T>,
+}
+
+// Not derived, which would require T: Default
+impl<T> Default for SmPduBuilder<T> {
+    fn default() -> Self {
+        Self {
+            service_type: String::new(),
+            source_addr_ton: 0,
+            source_addr_npi: 0,
+            source_addr: String::new(),
+            dest_addr_ton: 0,
+            dest_addr_npi: 0,
+            destination_addr: String::new(),
+            esm_class: 0,
+            protocol_id: 0,
+            priority_flag: 0,
+            schedule_delivery_time: String::new(),
+            validity_period: String::new(),
+            registered_delivery: 0,
+            replace_if_present_flag: 0,
+            data_coding: 0,
+            sm_default_msg_id: 0,
+            short_message: Vec::new(),
+            tlvs: Vec::new(),
+            pdu: PhantomData,
+        }
+    }
+}
+
+impl<T: SmPdu> SmPduBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn service_type(mut self, service_type: &str) -> Self {
+        self.service_type = String::from(service_type);
+        self
+    }
+
+    /// Takes a `Ton` or a raw u8
+    pub fn source_addr_ton(mut self, ton: impl Into<u8>) -> Self {
+        self.source_addr_ton = ton.into();
+        self
+    }
+
+    /// Takes an `Npi` or a raw u8
+    pub fn source_addr_npi(mut self, npi: impl Into<u8>) -> Self {
+        self.source_addr_npi = npi.into();
+        self
+    }
+
+    pub fn source_addr(mut self, source_addr: &str) -> Self {
+        self.source_addr = String::from(source_addr);
+        self
+    }
+
+    /// Takes a `Ton` or a raw u8
+    pub fn dest_addr_ton(mut self, ton: impl Into<u8>) -> Self {
+        self.dest_addr_ton = ton.into();
+        self
+    }
+
+    /// Takes an `Npi` or a raw u8
+    pub fn dest_addr_npi(mut self, npi: impl Into<u8>) -> Self {
+        self.dest_addr_npi = npi.into();
+        self
+    }
+
+    pub fn destination_addr(mut self, destination_addr: &str) -> Self {
+        self.destination_addr = String::from(destination_addr);
+        self
+    }
+
+    pub fn esm_class(mut self, esm_class: u8) -> Self {
+        self.esm_class = esm_class;
+        self
+    }
+
+    pub fn protocol_id(mut self, protocol_id: u8) -> Self {
+        self.protocol_id = protocol_id;
+        self
+    }
+
+    pub fn priority_flag(mut self, priority_flag: u8) -> Self {
+        self.priority_flag = priority_flag;
+        self
+    }
+
+    pub fn schedule_delivery_time(mut self, time: &str) -> Self {
+        self.schedule_delivery_time = String::from(time);
+        self
+    }
+
+    pub fn validity_period(mut self, validity_period: &str) -> Self {
+        self.validity_period = String::from(validity_period);
+        self
+    }
+
+    pub fn registered_delivery(mut self, registered_delivery: u8) -> Self {
+        self.registered_delivery = registered_delivery;
+        self
+    }
+
+    pub fn replace_if_present_flag(mut self, flag: u8) -> Self {
+        self.replace_if_present_flag = flag;
+        self
+    }
+
+    pub fn data_coding(mut self, data_coding: u8) -> Self {
+        self.data_coding = data_coding;
+        self
+    }
+
+    pub fn sm_default_msg_id(mut self, sm_default_msg_id: u8) -> Self {
+        self.sm_default_msg_id = sm_default_msg_id;
+        self
+    }
+
+    pub fn short_message(mut self, short_message: &[u8]) -> Self {
+        self.short_message = short_message.to_vec();
+        self
+    }
+
+    /// Add a TLV, after any added already
+    pub fn tlv(mut self, tlv: Tlv) -> Self {
+        self.tlvs.push(tlv);
+        self
+    }
+
+    /// Fails for the same reasons as SubmitSmPdu::new and
+    /// DeliverSmPdu::new, except that a short_message of 254 octets is
+    /// allowed, as sm_length allows it.
+    pub fn build(self) -> Result<T, PduParseError> {
+        check_short_message_length(&self.short_message)?;
+        let mut sm_data = SmData::new(
+            &self.service_type,
+            self.source_addr_ton,
+            self.source_addr_npi,
+            &self.source_addr,
+            self.dest_addr_ton,
+            self.dest_addr_npi,
+            &self.destination_addr,
+            self.esm_class,
+            self.protocol_id,
+            self.priority_flag,
+            &self.schedule_delivery_time,
+            &self.validity_period,
+            self.registered_delivery,
+            self.replace_if_present_flag,
+            self.data_coding,
+            self.sm_default_msg_id,
+            b"",
+            Tlvs::from(&self.tlvs),
+        )?;
+        sm_data.short_message.value = self.short_message;
+        Ok(T::from_sm_data(sm_data))
+    }
+}
+
+impl DeliverSmPduBuilder {
+    /// Make this an SMSC delivery receipt for the message with this id,
+    /// with the Appendix B text, e.g. "id:ab87J sub:001 dlvrd:001 submit
+    /// date: done date: stat:DELIVRD err:000 text:".  Use receipt() to fill
+    /// in the other fields.
+    pub fn delivery_receipt(
+        self,
+        message_id: &str,
+        state: MessageState,
+    ) -> Self {
+        let delivered = state == MessageState::Delivered;
+        self.receipt(&DeliveryReceipt {
+            id: String::from(message_id),
+            sub: 1,
+            dlvrd: u32::from(delivered),
+            submit_date: String::new(),
+            done_date: String::new(),
+            stat: state,
+            err: 0,
+            text: String::new(),
+        })
+    }
+
+    /// Make this an SMSC delivery receipt carrying receipt as its text
+    pub fn receipt(self, receipt: &DeliveryReceipt) -> Self {
+        self.esm_class(DeliverEsmClass::SmscDeliveryReceipt as u8)
+            .short_message(receipt.to_string().as_bytes())
+    }
+}
+
+fn check_short_message_length(
+    short_message: &[u8],
+) -> Result<(), PduParseError> {
+    if short_message.len() > MAX_LENGTH_SHORT_MESSAGE {
+        return Err(PduParseError::new(PduParseErrorBody::IncorrectLength(
+            short_message.len() as u32,
+            format!(
+                "short_message may be at most {} octets.  Send longer \
+                messages in the message_payload TLV.",
+                MAX_LENGTH_SHORT_MESSAGE
+            ),
+        ))
+        .into_with_field_name("short_message"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::KnownTlvTag;
+    use smpp_pdu::pdu::PduStatus;
+
+    use crate::pdu::{DeliveryReceiptExt, Npi, Ton};
+
+    #[test]
+    fn builder_makes_the_same_pdu_as_new() {
+        let built = SubmitSmPduBuilder::new()
+            .service_type("CMT")
+            .source_addr_ton(Ton::Alphanumeric)
+            .source_addr("MyCompany")
+            .dest_addr_ton(Ton::International)
+            .dest_addr_npi(Npi::Isdn)
+            .destination_addr("447777222222")
+            .esm_class(0x40)
+            .protocol_id(0x34)
+            .validity_period("000001000000000R")
+            .registered_delivery(1)
+            .data_coding(8)
+            .short_message(b"\x00h\x00i")
+            .tlv(Tlv::new(KnownTlvTag::user_message_reference, &[0, 7]))
+            .build()
+            .unwrap();
+
+        let positional = SubmitSmPdu::new(
+            "CMT",
+            5,
+            0,
+            "MyCompany",
+            1,
+            1,
+            "447777222222",
+            0x40,
+            0x34,
+            0,
+            "",
+            "000001000000000R",
+            1,
+            0,
+            8,
+            0,
+            b"\x00h\x00i",
+            Tlvs::from(&[Tlv::new(
+                KnownTlvTag::user_message_reference,
+                &[0, 7],
+            )]),
+        )
+        .unwrap();
+
+        assert_eq!(built, positional);
+    }
+
+    #[test]
+    fn unset_fields_are_zero_or_empty() {
+        let built = SubmitSmPduBuilder::new().build().unwrap();
+        let positional = SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "",
+            0,
+            0,
+            "",
+            0,
+            0,
+            0,
+            "",
+            "",
+            0,
+            0,
+            0,
+            0,
+            b"",
+            Tlvs::new(),
+        )
+        .unwrap();
+        assert_eq!(built, positional);
+    }
+
+    #[test]
+    fn short_message_may_be_254_octets_but_no_more() {
+        let pdu = SubmitSmPduBuilder::new()
+            .short_message(&[b'x'; 254])
+            .build()
+            .unwrap();
+        assert_eq!(pdu.0.short_message.value.len(), 254);
+
+        let e = SubmitSmPduBuilder::new()
+            .short_message(&[b'x'; 255])
+            .build()
+            .unwrap_err();
+        assert_eq!(e.status(), PduStatus::ESME_RINVMSGLEN as u32);
+        assert!(e.to_string().contains("message_payload TLV"), "{}", e);
+    }
+
+    #[test]
+    fn deliver_sm_builder_makes_the_same_pdu_as_new() {
+        let built = DeliverSmPduBuilder::new()
+            .source_addr("447777222222")
+            .destination_addr("MyCompany")
+            .esm_class(DeliverEsmClass::SmscDeliveryReceipt as u8)
+            .data_coding(3)
+            .short_message(b"id:7 stat:DELIVRD")
+            .build()
+            .unwrap();
+
+        let positional = DeliverSmPdu::new(
+            "",
+            0,
+            0,
+            "447777222222",
+            0,
+            0,
+            "MyCompany",
+            DeliverEsmClass::SmscDeliveryReceipt as u8,
+            0,
+            0,
+            "",
+            "",
+            0,
+            0,
+            3,
+            0,
+            b"id:7 stat:DELIVRD",
+            Tlvs::new(),
+        )
+        .unwrap();
+
+        assert_eq!(built, positional);
+    }
+
+    #[test]
+    fn delivery_receipt_carries_the_message_id() {
+        let pdu = DeliverSmPduBuilder::new()
+            .source_addr("447777222222")
+            .destination_addr("MyCompany")
+            .delivery_receipt("ab87J", MessageState::Delivered)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            pdu.0.esm_class.value,
+            DeliverEsmClass::SmscDeliveryReceipt as u8
+        );
+        assert_eq!(
+            pdu.0.short_message.value,
+            b"id:ab87J sub:001 dlvrd:001 submit date: done date: \
+            stat:DELIVRD err:000 text:"
+        );
+        assert_eq!(pdu.extract_receipted_message_id().unwrap(), "ab87J");
+    }
+
+    #[test]
+    fn failed_delivery_receipt_reads_back_its_state() {
+        let pdu = DeliverSmPduBuilder::new()
+            .delivery_receipt("12", MessageState::Undeliverable)
+            .build()
+            .unwrap();
+
+        let receipt = pdu.delivery_receipt().unwrap();
+        assert_eq!(receipt.id, "12");
+        assert_eq!(receipt.stat, MessageState::Undeliverable);
+        assert_eq!(receipt.dlvrd, 0);
+    }
+
+    #[test]
+    fn invalid_fields_are_reported_by_build() {
+        let e = SubmitSmPduBuilder::new()
+            .validity_period("tomorrow")
+            .build()
+            .unwrap_err();
+        assert!(e.to_string().contains("validity_period"), "{}", e);
+    }
+}
@@ -0,0 +1,72 @@
+//! Accessors for the fields of a submit_sm or deliver_sm, so SmscLogic
+//! implementations don't need to reach into its SmData.  source_addr() and
+//! destination_addr() are already provided by smpp-pdu.
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+
+pub trait SmFields {
+    fn sm_data(&self) -> &SmData;
+
+    fn service_type(&self) -> &str {
+        self.sm_data().service_type.value.as_str()
+    }
+
+    fn esm_class(&self) -> u8 {
+        self.sm_data().esm_class.value
+    }
+
+    fn data_coding(&self) -> u8 {
+        self.sm_data().data_coding.value
+    }
+
+    fn registered_delivery(&self) -> u8 {
+        self.sm_data().registered_delivery.value
+    }
+
+    fn short_message(&self) -> &[u8] {
+        &self.sm_data().short_message.value
+    }
+
+    /// Empty if the SMSC's default validity period applies
+    fn validity_period(&self) -> &str {
+        self.sm_data().validity_period.value.as_str()
+    }
+}
+
+impl SmFields for SubmitSmPdu {
+    fn sm_data(&self) -> &SmData {
+        &self.0
+    }
+}
+
+impl SmFields for DeliverSmPdu {
+    fn sm_data(&self) -> &SmData {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::SubmitSmPduBuilder;
+
+    #[test]
+    fn submit_sm_fields_are_readable() {
+        let pdu = SubmitSmPduBuilder::new()
+            .service_type("CMT")
+            .esm_class(0x40)
+            .validity_period("000001000000000R")
+            .registered_delivery(1)
+            .data_coding(8)
+            .short_message(b"\x00h\x00i")
+            .build()
+            .unwrap();
+        assert_eq!(pdu.service_type(), "CMT");
+        assert_eq!(pdu.esm_class(), 0x40);
+        assert_eq!(pdu.data_coding(), 8);
+        assert_eq!(pdu.registered_delivery(), 1);
+        assert_eq!(pdu.short_message(), b"\x00h\x00i");
+        assert_eq!(pdu.validity_period(), "000001000000000R");
+    }
+}
@@ -0,0 +1,161 @@
+use smpp_pdu::pdu::tlvs::KnownTlvTag;
+use std::convert::TryInto;
+
+/// Count the TLVs in a complete submit_sm or deliver_sm PDU, without
+/// parsing or allocating anything, so we can reject PDUs containing huge
+/// numbers of TLVs before we spend time parsing them.  Returns None for
+/// other PDU types, or if the PDU is malformed (in which case parsing it
+/// will produce a more useful error).
+pub fn count_tlvs(pdu: &[u8]) -> Option<usize> {
+    let mut bytes = tlv_bytes(pdu)?;
+
+    let mut count = 0;
+    while !bytes.is_empty() {
+        skip_tlv(&mut bytes)?;
+        count += 1;
+    }
+    Some(count)
+}
+
+/// The tag and length of each TLV in a complete submit_sm or deliver_sm
+/// PDU whose tag we don't recognise.  Returns None in the same cases as
+/// count_tlvs.
+pub fn unknown_tlvs(pdu: &[u8]) -> Option<Vec<(u16, u16)>> {
+    let mut bytes = tlv_bytes(pdu)?;
+
+    let mut ret = Vec::new();
+    while !bytes.is_empty() {
+        let (tag, length) = skip_tlv(&mut bytes)?;
+        if KnownTlvTag::new(tag).is_none() {
+            ret.push((tag, length));
+        }
+    }
+    Some(ret)
+}
+
+/// If the sm_length of a complete submit_sm or deliver_sm PDU says
+/// short_message runs past the end of the PDU, the sm_length and how many
+/// bytes actually follow it.  Returns None if short_message fits, and in
+/// the same cases as count_tlvs.
+pub fn sm_length_overrun(pdu: &[u8]) -> Option<(u8, usize)> {
+    let (sm_length, rest) = sm_length_and_rest(pdu)?;
+    if usize::from(sm_length) > rest.len() {
+        Some((sm_length, rest.len()))
+    } else {
+        None
+    }
+}
+
+/// The part of a submit_sm or deliver_sm PDU after the mandatory fields
+fn tlv_bytes(pdu: &[u8]) -> Option<&[u8]> {
+    let (sm_length, mut bytes) = sm_length_and_rest(pdu)?;
+    skip(&mut bytes, usize::from(sm_length))?;
+    Some(bytes)
+}
+
+/// The sm_length of a submit_sm or deliver_sm PDU, and the bytes after it
+fn sm_length_and_rest(pdu: &[u8]) -> Option<(u8, &[u8])> {
+    let command_id = u32::from_be_bytes(pdu.get(4..8)?.try_into().ok()?);
+    if command_id != 0x00000004 && command_id != 0x00000005 {
+        return None;
+    }
+
+    let mut bytes = pdu.get(16..)?;
+
+    // Skip the mandatory fields, which are the same for both
+    skip_c_octet_string(&mut bytes)?; // service_type
+    skip(&mut bytes, 2)?; // source_addr_ton, source_addr_npi
+    skip_c_octet_string(&mut bytes)?; // source_addr
+    skip(&mut bytes, 2)?; // dest_addr_ton, dest_addr_npi
+    skip_c_octet_string(&mut bytes)?; // destination_addr
+    skip(&mut bytes, 3)?; // esm_class, protocol_id, priority_flag
+    skip_c_octet_string(&mut bytes)?; // schedule_delivery_time
+    skip_c_octet_string(&mut bytes)?; // validity_period
+    skip(&mut bytes, 4)?; // registered_delivery to sm_default_msg_id
+    let sm_length = *bytes.first()?;
+    Some((sm_length, &bytes[1..]))
+}
+
+/// Skip over the TLV at the start of bytes, returning its tag and length
+fn skip_tlv(bytes: &mut &[u8]) -> Option<(u16, u16)> {
+    let tag = u16::from_be_bytes(bytes.get(0..2)?.try_into().ok()?);
+    let length = u16::from_be_bytes(bytes.get(2..4)?.try_into().ok()?);
+    skip(bytes, 4 + usize::from(length))?;
+    Some((tag, length))
+}
+
+fn skip(bytes: &mut &[u8], n: usize) -> Option<()> {
+    *bytes = bytes.get(n..)?;
+    Some(())
+}
+
+fn skip_c_octet_string(bytes: &mut &[u8]) -> Option<()> {
+    let end = bytes.iter().position(|b| *b == 0x00)?;
+    skip(bytes, end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBMIT_SM: &[u8] =
+        b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+        \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+        \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+    fn with_tlvs(pdu: &[u8], tlvs: &[u8]) -> Vec<u8> {
+        let mut ret = pdu.to_vec();
+        ret.extend(tlvs);
+        ret
+    }
+
+    #[test]
+    fn count_tlvs_in_submit_sm() {
+        assert_eq!(count_tlvs(SUBMIT_SM), Some(0));
+        assert_eq!(
+            count_tlvs(&with_tlvs(
+                SUBMIT_SM,
+                b"\x02\x04\x00\x02\x00\x01\x04\x24\x00\x00\x14\x2e\x00\x01\x00"
+            )),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn truncated_tlv_is_not_counted() {
+        assert_eq!(
+            count_tlvs(&with_tlvs(SUBMIT_SM, b"\x02\x04\x00\x02\x00")),
+            None
+        );
+    }
+
+    #[test]
+    fn other_pdu_types_are_not_counted() {
+        assert_eq!(
+            count_tlvs(
+                b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn sm_length_past_the_end_is_an_overrun() {
+        assert_eq!(sm_length_overrun(SUBMIT_SM), None);
+        let mut pdu = SUBMIT_SM.to_vec();
+        pdu[0x38] = 0x08;
+        assert_eq!(sm_length_overrun(&pdu), Some((8, 4)));
+    }
+
+    #[test]
+    fn unknown_tlvs_lists_only_tags_we_do_not_recognise() {
+        assert_eq!(unknown_tlvs(SUBMIT_SM), Some(vec![]));
+        assert_eq!(
+            unknown_tlvs(&with_tlvs(
+                SUBMIT_SM,
+                b"\x02\x04\x00\x02\x00\x01\x14\x2e\x00\x01\x00\x04\x24\x00\x00"
+            )),
+            Some(vec![(0x142e, 1)])
+        );
+    }
+}
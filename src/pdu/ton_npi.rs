@@ -0,0 +1,228 @@
+//! Typed views of the addr_ton and addr_npi fields of an address.  See
+//! https://smpp.org/SMPP_v3_4_Issue1_2.pdf sections 5.2.5 and 5.2.6
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+
+/// Type of Number: what kind of number an address is
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ton {
+    Unknown,
+    International,
+    National,
+    NetworkSpecific,
+    SubscriberNumber,
+    Alphanumeric,
+    Abbreviated,
+    /// A value the spec does not define, kept so it can be sent on
+    Other(u8),
+}
+
+impl Ton {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Ton::Unknown,
+            1 => Ton::International,
+            2 => Ton::National,
+            3 => Ton::NetworkSpecific,
+            4 => Ton::SubscriberNumber,
+            5 => Ton::Alphanumeric,
+            6 => Ton::Abbreviated,
+            _ => Ton::Other(value),
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Ton::Unknown => 0,
+            Ton::International => 1,
+            Ton::National => 2,
+            Ton::NetworkSpecific => 3,
+            Ton::SubscriberNumber => 4,
+            Ton::Alphanumeric => 5,
+            Ton::Abbreviated => 6,
+            Ton::Other(value) => *value,
+        }
+    }
+}
+
+impl From<u8> for Ton {
+    fn from(value: u8) -> Self {
+        Ton::from_u8(value)
+    }
+}
+
+impl From<Ton> for u8 {
+    fn from(value: Ton) -> Self {
+        value.as_u8()
+    }
+}
+
+/// Numeric Plan Indicator: which numbering plan an address belongs to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Npi {
+    Unknown,
+    /// E.163/E.164, i.e. ordinary phone numbers
+    Isdn,
+    /// X.121
+    Data,
+    /// F.69
+    Telex,
+    /// E.212
+    LandMobile,
+    National,
+    Private,
+    Ermes,
+    /// IP addresses
+    Internet,
+    WapClientId,
+    /// A value the spec does not define, kept so it can be sent on
+    Other(u8),
+}
+
+impl Npi {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Npi::Unknown,
+            1 => Npi::Isdn,
+            3 => Npi::Data,
+            4 => Npi::Telex,
+            6 => Npi::LandMobile,
+            8 => Npi::National,
+            9 => Npi::Private,
+            10 => Npi::Ermes,
+            14 => Npi::Internet,
+            18 => Npi::WapClientId,
+            _ => Npi::Other(value),
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Npi::Unknown => 0,
+            Npi::Isdn => 1,
+            Npi::Data => 3,
+            Npi::Telex => 4,
+            Npi::LandMobile => 6,
+            Npi::National => 8,
+            Npi::Private => 9,
+            Npi::Ermes => 10,
+            Npi::Internet => 14,
+            Npi::WapClientId => 18,
+            Npi::Other(value) => *value,
+        }
+    }
+}
+
+impl From<u8> for Npi {
+    fn from(value: u8) -> Self {
+        Npi::from_u8(value)
+    }
+}
+
+impl From<Npi> for u8 {
+    fn from(value: Npi) -> Self {
+        value.as_u8()
+    }
+}
+
+/// Implemented by PDUs with a source and destination address.  The raw
+/// fields are unchanged, so values we don't know are still sent on as
+/// they were received.
+pub trait TonNpi {
+    fn source_ton(&self) -> Ton;
+    fn source_npi(&self) -> Npi;
+    fn dest_ton(&self) -> Ton;
+    fn dest_npi(&self) -> Npi;
+}
+
+fn source(sm_data: &SmData) -> (Ton, Npi) {
+    (
+        Ton::from_u8(sm_data.source_addr_ton.value),
+        Npi::from_u8(sm_data.source_addr_npi.value),
+    )
+}
+
+fn dest(sm_data: &SmData) -> (Ton, Npi) {
+    (
+        Ton::from_u8(sm_data.dest_addr_ton.value),
+        Npi::from_u8(sm_data.dest_addr_npi.value),
+    )
+}
+
+impl TonNpi for SubmitSmPdu {
+    fn source_ton(&self) -> Ton {
+        source(&self.0).0
+    }
+
+    fn source_npi(&self) -> Npi {
+        source(&self.0).1
+    }
+
+    fn dest_ton(&self) -> Ton {
+        dest(&self.0).0
+    }
+
+    fn dest_npi(&self) -> Npi {
+        dest(&self.0).1
+    }
+}
+
+impl TonNpi for DeliverSmPdu {
+    fn source_ton(&self) -> Ton {
+        source(&self.0).0
+    }
+
+    fn source_npi(&self) -> Npi {
+        source(&self.0).1
+    }
+
+    fn dest_ton(&self) -> Ton {
+        dest(&self.0).0
+    }
+
+    fn dest_npi(&self) -> Npi {
+        dest(&self.0).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdu::SubmitSmPduBuilder;
+
+    #[test]
+    fn common_values_are_named() {
+        assert_eq!(Ton::from_u8(1), Ton::International);
+        assert_eq!(Ton::from_u8(2), Ton::National);
+        assert_eq!(Ton::from_u8(5), Ton::Alphanumeric);
+        assert_eq!(Npi::from_u8(1), Npi::Isdn);
+        assert_eq!(Npi::from_u8(3), Npi::Data);
+        assert_eq!(Npi::from_u8(14), Npi::Internet);
+    }
+
+    #[test]
+    fn every_value_round_trips_through_u8() {
+        for value in 0..=255 {
+            assert_eq!(Ton::from_u8(value).as_u8(), value);
+            assert_eq!(u8::from(Npi::from(value)), value);
+        }
+        assert_eq!(Ton::from_u8(7), Ton::Other(7));
+        assert_eq!(Npi::from_u8(2), Npi::Other(2));
+    }
+
+    #[test]
+    fn submit_sm_addresses_have_typed_ton_and_npi() {
+        let pdu = SubmitSmPduBuilder::new()
+            .source_addr_ton(Ton::Alphanumeric)
+            .source_addr_npi(Npi::Unknown)
+            .dest_addr_ton(Ton::International)
+            .dest_addr_npi(Npi::Isdn)
+            .build()
+            .unwrap();
+        assert_eq!(pdu.source_ton(), Ton::Alphanumeric);
+        assert_eq!(pdu.source_npi(), Npi::Unknown);
+        assert_eq!(pdu.dest_ton(), Ton::International);
+        assert_eq!(pdu.dest_npi(), Npi::Isdn);
+    }
+}
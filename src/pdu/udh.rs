@@ -0,0 +1,218 @@
+//! The User Data Header (UDH) that may precede the text in short_message,
+//! as described in 3GPP TS 23.040 section 9.2.3.24.
+
+use smpp_pdu::pdu::data::sm_data::SmData;
+use smpp_pdu::pdu::{DeliverSmPdu, SubmitSmPdu};
+
+/// The bit of esm_class that says short_message starts with a UDH
+pub const UDHI: u8 = 0b01000000;
+
+const IEI_CONCAT_8_BIT_REF: u8 = 0x00;
+const IEI_CONCAT_16_BIT_REF: u8 = 0x08;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InformationElement {
+    pub identifier: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserDataHeader {
+    pub information_elements: Vec<InformationElement>,
+}
+
+/// Where a single part fits into a concatenated message
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConcatInfo {
+    pub reference: u16,
+    pub total: u8,
+    pub sequence: u8,
+}
+
+impl UserDataHeader {
+    /// Parse the UDH at the start of some user data, returning it along
+    /// with its length in octets (including the length octet itself).
+    /// Returns None if the header is truncated.
+    pub fn parse(user_data: &[u8]) -> Option<(Self, usize)> {
+        let udh_len = usize::from(*user_data.first()?) + 1;
+        let mut ies = user_data.get(1..udh_len)?;
+        let mut information_elements = Vec::new();
+        while !ies.is_empty() {
+            let identifier = ies[0];
+            let len = usize::from(*ies.get(1)?);
+            let data = ies.get(2..2 + len)?.to_vec();
+            information_elements.push(InformationElement { identifier, data });
+            ies = &ies[2 + len..];
+        }
+        Some((
+            Self {
+                information_elements,
+            },
+            udh_len,
+        ))
+    }
+
+    /// The concatenation information element, if there is one
+    pub fn concat_info(&self) -> Option<ConcatInfo> {
+        self.information_elements.iter().find_map(|ie| {
+            match (ie.identifier, ie.data.as_slice()) {
+                (IEI_CONCAT_8_BIT_REF, &[reference, total, sequence]) => {
+                    Some(ConcatInfo {
+                        reference: u16::from(reference),
+                        total,
+                        sequence,
+                    })
+                }
+                (IEI_CONCAT_16_BIT_REF, &[ref1, ref2, total, sequence]) => {
+                    Some(ConcatInfo {
+                        reference: u16::from_be_bytes([ref1, ref2]),
+                        total,
+                        sequence,
+                    })
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Access to the UDH of a PDU carrying a short_message
+pub trait UserData {
+    /// The UDH and its length in octets, if esm_class says there is one
+    fn udh(&self) -> Option<(UserDataHeader, usize)>;
+
+    /// Which part of a concatenated message this is, if it is one
+    fn concat_info(&self) -> Option<ConcatInfo> {
+        self.udh().and_then(|(udh, _)| udh.concat_info())
+    }
+}
+
+fn sm_data_udh(sm_data: &SmData) -> Option<(UserDataHeader, usize)> {
+    if sm_data.esm_class.value & UDHI == 0 {
+        return None;
+    }
+    UserDataHeader::parse(&sm_data.short_message.value)
+}
+
+impl UserData for SubmitSmPdu {
+    fn udh(&self) -> Option<(UserDataHeader, usize)> {
+        sm_data_udh(&self.0)
+    }
+}
+
+impl UserData for DeliverSmPdu {
+    fn udh(&self) -> Option<(UserDataHeader, usize)> {
+        sm_data_udh(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::Tlvs;
+    use smpp_pdu::pdu::SubmitEsmClass;
+
+    fn submit_sm(esm_class: u8, short_message: &[u8]) -> SubmitSmPdu {
+        SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "src",
+            0,
+            0,
+            "dest",
+            esm_class,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            0x08,
+            0,
+            short_message,
+            Tlvs::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn single_part_message_has_no_concat_info() {
+        let pdu = submit_sm(SubmitEsmClass::Default as u8, b"\x00h\x00i");
+        assert_eq!(pdu.concat_info(), None);
+    }
+
+    #[test]
+    fn without_udhi_short_message_is_not_parsed_as_udh() {
+        // Looks like a UDH, but esm_class says there isn't one
+        let pdu = submit_sm(
+            SubmitEsmClass::Default as u8,
+            b"\x05\x00\x03\x2a\x02\x01\x00h",
+        );
+        assert_eq!(pdu.concat_info(), None);
+    }
+
+    #[test]
+    fn multipart_message_with_8_bit_reference() {
+        let pdu = submit_sm(UDHI, b"\x05\x00\x03\x2a\x03\x02\x00h\x00i");
+        assert_eq!(
+            pdu.concat_info(),
+            Some(ConcatInfo {
+                reference: 0x2a,
+                total: 3,
+                sequence: 2
+            })
+        );
+        assert_eq!(pdu.udh().unwrap().1, 6);
+    }
+
+    #[test]
+    fn multipart_message_with_16_bit_reference() {
+        let pdu = submit_sm(UDHI, b"\x06\x08\x04\x12\x34\x02\x01\x00h\x00i");
+        assert_eq!(
+            pdu.concat_info(),
+            Some(ConcatInfo {
+                reference: 0x1234,
+                total: 2,
+                sequence: 1
+            })
+        );
+        assert_eq!(pdu.udh().unwrap().1, 7);
+    }
+
+    #[test]
+    fn parse_udh_with_several_information_elements() {
+        let (udh, len) =
+            UserDataHeader::parse(b"\x08\x00\x03\x2a\x02\x01\x24\x01\x01hello")
+                .unwrap();
+        assert_eq!(len, 9);
+        assert_eq!(
+            udh.information_elements,
+            vec![
+                InformationElement {
+                    identifier: 0x00,
+                    data: vec![0x2a, 0x02, 0x01],
+                },
+                InformationElement {
+                    identifier: 0x24,
+                    data: vec![0x01],
+                },
+            ]
+        );
+        assert_eq!(
+            udh.concat_info(),
+            Some(ConcatInfo {
+                reference: 0x2a,
+                total: 2,
+                sequence: 1
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_udh_is_not_parsed() {
+        assert_eq!(UserDataHeader::parse(b""), None);
+        assert_eq!(UserDataHeader::parse(b"\x05\x00\x03\x2a"), None);
+        assert_eq!(UserDataHeader::parse(b"\x03\x00\x03\x2a"), None);
+    }
+}
@@ -0,0 +1,642 @@
+use bytes::{Buf, BytesMut};
+use log::*;
+use smpp_pdu::pdu::{
+    CommandLengthError, Pdu, PduParseError, PduParseErrorBody, MAX_PDU_LENGTH,
+    MIN_PDU_LENGTH,
+};
+use std::error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::Cursor;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::pdu::{
+    count_tlvs, sm_length_overrun, unknown_tlvs, AnyPdu, ExtraPdu,
+};
+
+/// Why PduReader::read_pdu could not return a PDU
+#[derive(Debug)]
+pub enum ReadPduError {
+    /// The PDU's command_length was acceptable and all of it arrived, but
+    /// its body was invalid.  We have skipped over it, so the caller can
+    /// reject it and carry on reading.
+    BadBody(PduParseError),
+    /// We can't tell where the next PDU starts (e.g. its command_length
+    /// was out of range, or the stream failed or ended part way through
+    /// it), so nothing more can be read.
+    Framing(PduParseError),
+}
+
+impl ReadPduError {
+    pub fn parse_error(&self) -> &PduParseError {
+        match self {
+            ReadPduError::BadBody(e) | ReadPduError::Framing(e) => e,
+        }
+    }
+}
+
+impl From<io::Error> for ReadPduError {
+    fn from(e: io::Error) -> Self {
+        ReadPduError::Framing(e.into())
+    }
+}
+
+impl Display for ReadPduError {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        self.parse_error().fmt(formatter)
+    }
+}
+
+impl error::Error for ReadPduError {}
+
+/// Reads whole PDUs from a stream of bytes (e.g. a TCP socket), buffering
+/// until each one is complete.  Used by the SMSC for its connections, and
+/// usable by an ESME for its connection to an SMSC.
+pub struct PduReader<R: AsyncRead + Unpin> {
+    stream: R,
+    buffer: BytesMut,
+    max_tlvs_per_pdu: Option<usize>,
+    log_unknown_tlvs: bool,
+    min_pdu_length: usize,
+    max_pdu_length: usize,
+}
+
+impl<R: AsyncRead + Unpin> PduReader<R> {
+    pub fn new(stream: R) -> Self {
+        Self {
+            stream,
+            buffer: BytesMut::with_capacity(4096),
+            max_tlvs_per_pdu: None,
+            log_unknown_tlvs: false,
+            min_pdu_length: MIN_PDU_LENGTH,
+            max_pdu_length: MAX_PDU_LENGTH,
+        }
+    }
+
+    /// Reject PDUs containing more than this many TLVs without parsing
+    /// them.
+    pub fn with_max_tlvs_per_pdu(mut self, max_tlvs_per_pdu: usize) -> Self {
+        self.max_tlvs_per_pdu = Some(max_tlvs_per_pdu);
+        self
+    }
+
+    pub(crate) fn set_max_tlvs_per_pdu(&mut self, max_tlvs_per_pdu: usize) {
+        self.max_tlvs_per_pdu = Some(max_tlvs_per_pdu);
+    }
+
+    /// Log the tag and length of any TLVs we don't recognise in the PDUs
+    /// we read.
+    pub fn with_log_unknown_tlvs(mut self, log_unknown_tlvs: bool) -> Self {
+        self.log_unknown_tlvs = log_unknown_tlvs;
+        self
+    }
+
+    pub(crate) fn set_log_unknown_tlvs(&mut self, log_unknown_tlvs: bool) {
+        self.log_unknown_tlvs = log_unknown_tlvs;
+    }
+
+    /// Reject PDUs whose command_length is outside these limits.  They
+    /// default to MIN_PDU_LENGTH and MAX_PDU_LENGTH, and can only be
+    /// tightened, because the parser itself enforces those.
+    pub fn with_pdu_length_limits(
+        mut self,
+        min_pdu_length: usize,
+        max_pdu_length: usize,
+    ) -> Self {
+        self.set_pdu_length_limits(min_pdu_length, max_pdu_length);
+        self
+    }
+
+    pub(crate) fn set_pdu_length_limits(
+        &mut self,
+        min_pdu_length: usize,
+        max_pdu_length: usize,
+    ) {
+        self.min_pdu_length = min_pdu_length.max(MIN_PDU_LENGTH);
+        self.max_pdu_length = max_pdu_length.min(MAX_PDU_LENGTH);
+    }
+
+    /// Read the next PDU.  Returns None if the stream ends cleanly between
+    /// PDUs.  Cancel-safe: bytes already read are kept for the next call.
+    pub async fn read_pdu(&mut self) -> Result<Option<AnyPdu>, ReadPduError> {
+        loop {
+            if let Some(pdu) = self.parse_pdu()? {
+                return Ok(Some(pdu));
+            }
+
+            if 0 == self.read_own_buf().await? {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                } else {
+                    return Err(ReadPduError::Framing(PduParseError::new(
+                        PduParseErrorBody::NotEnoughBytes,
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Read whatever has arrived, but never let the buffer grow beyond
+    /// max_pdu_length.  check rejects a PDU as soon as its command_length
+    /// is too long, so a buffer this full always holds a whole PDU, and we
+    /// never need to read more until it is parsed.
+    async fn read_own_buf(&mut self) -> Result<usize, io::Error> {
+        let limit = self.max_pdu_length.saturating_sub(self.buffer.len());
+        (&mut self.stream)
+            .take(limit as u64)
+            .read_buf(&mut self.buffer)
+            .await
+    }
+
+    fn parse_pdu(&mut self) -> Result<Option<AnyPdu>, ReadPduError> {
+        match check(&self.buffer, self.min_pdu_length, self.max_pdu_length) {
+            Ok(Some(len)) => {
+                let result = self.parse_checked(len);
+                // We know where this PDU ends, so move on to the next one
+                // whether or not it parsed
+                self.buffer.advance(len);
+                result.map(Some).map_err(ReadPduError::BadBody)
+            }
+            // Try again when we have more
+            Ok(None) => Ok(None),
+            // Failed (e.g. too long).  If we have already received the
+            // PDU header, include it so we can respond with the right
+            // sequence_number and PDU type.
+            Err(e) => Err(ReadPduError::Framing(with_header_from(
+                &self.buffer,
+                e.into(),
+            ))),
+        }
+    }
+
+    /// Parse the first len bytes of the buffer, which check has found to
+    /// be one whole PDU.
+    fn parse_checked(&self, len: usize) -> Result<AnyPdu, PduParseError> {
+        let bytes = &self.buffer[..len];
+        if let Some(max_tlvs_per_pdu) = self.max_tlvs_per_pdu {
+            check_tlv_count(bytes, max_tlvs_per_pdu)?;
+        }
+        if self.log_unknown_tlvs {
+            log_unknown_tlvs(bytes);
+        }
+        parse_framed(bytes)
+    }
+}
+
+/// If the buffer starts with a whole PDU, its length.  Like Pdu::check,
+/// but with our own limits on command_length, and by looking at the
+/// buffer's length instead of reading the PDU into a throwaway Vec, so
+/// that we don't allocate each time a partial PDU arrives.
+fn check(
+    buffer: &[u8],
+    min_pdu_length: usize,
+    max_pdu_length: usize,
+) -> Result<Option<usize>, CommandLengthError> {
+    if buffer.len() < 4 {
+        return Ok(None);
+    }
+    let len = command_length(buffer);
+    if len > max_pdu_length {
+        Err(CommandLengthError::TooLong(len as u32))
+    } else if len < min_pdu_length {
+        Err(CommandLengthError::TooShort(len as u32))
+    } else if buffer.len() < len {
+        Ok(None)
+    } else {
+        Ok(Some(len))
+    }
+}
+
+/// Parse a PDU whose bytes check has already found, so we know they are
+/// exactly one PDU long.  The check guarantees we have at least a
+/// command_length and command_id, so we can see which parser to use
+/// without reading them from a stream.
+pub(crate) fn parse_framed(pdu: &[u8]) -> Result<AnyPdu, PduParseError> {
+    let command_id = u32::from_be_bytes([pdu[4], pdu[5], pdu[6], pdu[7]]);
+    check_sm_length(pdu)?;
+    let mut buf = Cursor::new(pdu);
+    let parsed = if ExtraPdu::handles(command_id) {
+        AnyPdu::Extra(ExtraPdu::parse(&mut buf)?)
+    } else {
+        AnyPdu::Pdu(Pdu::parse(&mut buf)?)
+    };
+    check_consumed(pdu, buf.position() as usize)?;
+    Ok(parsed)
+}
+
+/// The command_length at the start of the supplied bytes, which must be at
+/// least 4 long
+fn command_length(bytes: &[u8]) -> usize {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+}
+
+fn check_tlv_count(
+    pdu: &[u8],
+    max_tlvs_per_pdu: usize,
+) -> Result<(), PduParseError> {
+    match count_tlvs(pdu) {
+        Some(count) if count > max_tlvs_per_pdu => Err(with_header_from(
+            pdu,
+            PduParseError::new(PduParseErrorBody::OtherIoError(
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "PDU contains {} TLVs, but the maximum allowed is {}.",
+                        count, max_tlvs_per_pdu
+                    ),
+                ),
+            )),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// smpp-pdu reports an sm_length longer than the rest of the PDU as an IO
+/// error from filling a buffer, so catch it first and say what is wrong.
+/// Some ESMEs send an sm_length that disagrees with the short_message.
+fn check_sm_length(pdu: &[u8]) -> Result<(), PduParseError> {
+    match sm_length_overrun(pdu) {
+        Some((sm_length, remaining)) => Err(with_header_from(
+            pdu,
+            PduParseError::new(PduParseErrorBody::IncorrectLength(
+                u32::from(sm_length),
+                format!(
+                    "sm_length ({}) extends beyond the PDU body (only {} \
+                    bytes remain).",
+                    sm_length, remaining
+                ),
+            ))
+            .into_with_field_name("short_message"),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn log_unknown_tlvs(pdu: &[u8]) {
+    for (tag, length) in unknown_tlvs(pdu).unwrap_or_default() {
+        debug!(
+            "PDU with sequence_number={:#010X} contains unknown TLV \
+            tag={:#06X} length={}",
+            u32::from_be_bytes([pdu[12], pdu[13], pdu[14], pdu[15]]),
+            tag,
+            length
+        );
+    }
+}
+
+/// Parsing a PDU must consume exactly the command_length bytes that the
+/// check found.  If not, our framing is wrong, and the next PDU would be
+/// read from the wrong place.
+fn check_consumed(pdu: &[u8], consumed: usize) -> Result<(), PduParseError> {
+    if consumed == pdu.len() {
+        return Ok(());
+    }
+    Err(with_header_from(
+        pdu,
+        PduParseError::new(PduParseErrorBody::OtherIoError(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Parsing consumed {} bytes of a PDU whose command_length \
+                is {}.",
+                consumed,
+                pdu.len()
+            ),
+        ))),
+    ))
+}
+
+/// Add the command_id, command_status and sequence_number from the start of
+/// the supplied bytes to the error, if there are enough bytes to contain
+/// them.
+fn with_header_from(bytes: &[u8], error: PduParseError) -> PduParseError {
+    if bytes.len() < 16 {
+        return error;
+    }
+    let header = |i: usize| {
+        Some(u32::from_be_bytes([
+            bytes[i],
+            bytes[i + 1],
+            bytes[i + 2],
+            bytes[i + 3],
+        ]))
+    };
+    error.into_with_header(header(4), header(8), header(12))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unittest_utils::CapturingLogger;
+    use futures::FutureExt;
+    use smpp_pdu::pdu::tlvs::Tlv;
+    use smpp_pdu::pdu::{CheckOutcome, PduBody, PduStatus};
+    use std::time::Duration;
+    use tokio::io::{duplex, AsyncWriteExt};
+
+    const ENQUIRE_LINK: &[u8; 16] =
+        b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x07";
+
+    #[tokio::test]
+    async fn pdu_split_across_two_reads_is_returned_once_complete() {
+        let (mut client, server) = duplex(64);
+        let mut reader = PduReader::new(server);
+
+        client.write_all(&ENQUIRE_LINK[..10]).await.unwrap();
+        assert!(reader.read_pdu().now_or_never().is_none());
+
+        client.write_all(&ENQUIRE_LINK[10..]).await.unwrap();
+        match reader.read_pdu().await.unwrap() {
+            Some(AnyPdu::Pdu(pdu)) => {
+                assert!(matches!(pdu.body(), PduBody::EnquireLink(_)));
+                assert_eq!(pdu.sequence_number.value, 7);
+            }
+            _ => panic!("Expected an enquire_link"),
+        }
+    }
+
+    #[tokio::test]
+    async fn end_of_stream_between_pdus_returns_none() {
+        let (mut client, server) = duplex(64);
+        let mut reader = PduReader::new(server);
+
+        client.write_all(ENQUIRE_LINK).await.unwrap();
+        drop(client);
+
+        assert!(reader.read_pdu().await.unwrap().is_some());
+        assert!(reader.read_pdu().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn end_of_stream_inside_a_pdu_is_an_error() {
+        let (mut client, server) = duplex(64);
+        let mut reader = PduReader::new(server);
+
+        client.write_all(&ENQUIRE_LINK[..10]).await.unwrap();
+        drop(client);
+
+        assert!(reader.read_pdu().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn too_long_command_length_is_rejected_before_the_rest_arrives() {
+        let (mut client, server) = duplex(64);
+        let mut reader = PduReader::new(server);
+
+        // When a client declares a 70001-byte PDU, one byte at a time, and
+        // keeps the stream open
+        let reading = tokio::spawn(async move { reader.read_pdu().await });
+        for byte in b"\x00\x01\x11\x71" {
+            client.write_all(&[*byte]).await.unwrap();
+        }
+
+        // Then we reject it straight away, without waiting for the body
+        let e = tokio::time::timeout(Duration::from_secs(1), reading)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(e, ReadPduError::Framing(_)), "{:?}", e);
+        assert_eq!(e.parse_error().status(), PduStatus::ESME_RINVCMDLEN as u32);
+    }
+
+    #[test]
+    fn check_is_ready_only_once_a_whole_large_pdu_has_arrived() {
+        let mut bytes = vec![0; 60_000];
+        bytes[..4].copy_from_slice(&60_000u32.to_be_bytes());
+
+        assert_eq!(
+            check(&bytes[..3], MIN_PDU_LENGTH, MAX_PDU_LENGTH).unwrap(),
+            None
+        );
+        assert_eq!(
+            check(&bytes[..59_999], MIN_PDU_LENGTH, MAX_PDU_LENGTH).unwrap(),
+            None
+        );
+        assert_eq!(
+            check(&bytes, MIN_PDU_LENGTH, MAX_PDU_LENGTH).unwrap(),
+            Some(60_000)
+        );
+    }
+
+    #[test]
+    fn check_agrees_with_pdu_check() {
+        for bytes in [
+            &ENQUIRE_LINK[..],
+            &ENQUIRE_LINK[..10],
+            b"\x00\x00\x00\x04",
+            b"\x00\x01\x11\x71\x00\x00\x00\x15",
+        ] {
+            let ours = check(bytes, MIN_PDU_LENGTH, MAX_PDU_LENGTH)
+                .map(|len| match len {
+                    Some(_) => CheckOutcome::Ready,
+                    None => CheckOutcome::Incomplete,
+                })
+                .map_err(PduParseError::from);
+            let theirs = Pdu::check(&mut Cursor::new(bytes))
+                .map_err(PduParseError::from);
+            assert_eq!(format!("{:?}", ours), format!("{:?}", theirs));
+        }
+    }
+
+    #[test]
+    fn parsing_framed_submit_sm_matches_checking_then_parsing() {
+        const SUBMIT_SM: &[u8; 0x3d] =
+            b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x2f\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+        let mut buf = Cursor::new(&SUBMIT_SM[..]);
+        assert!(matches!(Pdu::check(&mut buf), Ok(CheckOutcome::Ready)));
+        buf.set_position(0);
+        let two_step = Pdu::parse(&mut buf).unwrap();
+
+        let len = check(SUBMIT_SM, MIN_PDU_LENGTH, MAX_PDU_LENGTH)
+            .unwrap()
+            .unwrap();
+        match parse_framed(&SUBMIT_SM[..len]).unwrap() {
+            AnyPdu::Pdu(pdu) => assert_eq!(pdu, two_step),
+            other => panic!("Expected a submit_sm, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_consumes_the_length_check_found() {
+        const SUBMIT_SM_RESP: &[u8; 0x13] =
+            b"\x00\x00\x00\x13\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x07\
+            ab\x00";
+        for bytes in [&ENQUIRE_LINK[..], &SUBMIT_SM_RESP[..]] {
+            let mut buf = Cursor::new(bytes);
+            assert!(matches!(Pdu::check(&mut buf), Ok(CheckOutcome::Ready)));
+            let checked = buf.position();
+
+            buf.set_position(0);
+            Pdu::parse(&mut buf).unwrap();
+            assert_eq!(buf.position(), checked);
+            assert!(check_consumed(bytes, checked as usize).is_ok());
+        }
+    }
+
+    #[test]
+    fn sm_length_beyond_the_pdu_is_an_invalid_message_length() {
+        const PDU: &[u8; 0x3d] = b"\
+            \x00\x00\x00\x3d\
+            \x00\x00\x00\x04\
+            \x00\x00\x00\x00\
+            \x00\x00\x00\x03\
+            \x00\
+            \x00\x00447000123123\x00\
+            \x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\
+            \x00\x08hihi";
+
+        let err = parse_framed(PDU).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error parsing PDU \
+            (command_id=0x00000004, command_status=0x00000000, \
+            sequence_number=0x00000003, field_name=short_message): \
+            Length 8 was incorrect: sm_length (8) extends beyond the PDU \
+            body (only 4 bytes remain)."
+        );
+        assert_eq!(err.status(), PduStatus::ESME_RINVMSGLEN as u32);
+    }
+
+    #[test]
+    fn consuming_a_different_length_is_an_error() {
+        let e = check_consumed(ENQUIRE_LINK, 12).unwrap_err();
+        assert_eq!(e.sequence_number, Some(7));
+        assert!(e.to_string().contains("consumed 12 bytes"), "{}", e);
+    }
+
+    #[tokio::test]
+    async fn largest_message_payload_round_trips_unchanged() {
+        use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlvs};
+        use smpp_pdu::pdu::SubmitSmPdu;
+
+        let payload: Vec<u8> = (0..65_535).map(|i| i as u8).collect();
+        let body = SubmitSmPdu::new(
+            "",
+            0,
+            0,
+            "447000123123",
+            0,
+            0,
+            "447111222222",
+            0,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            0x08,
+            0,
+            b"",
+            Tlvs::from(&[Tlv::new(KnownTlvTag::message_payload, &payload)][..]),
+        )
+        .unwrap();
+        let mut bytes: Vec<u8> = Vec::new();
+        Pdu::new(0, 0x2f, body.into())
+            .unwrap()
+            .write(&mut bytes)
+            .await
+            .unwrap();
+
+        let mut reader = PduReader::new(&bytes[..]);
+        match reader.read_pdu().await.unwrap() {
+            Some(AnyPdu::Pdu(pdu)) => match pdu.body() {
+                PduBody::SubmitSm(body) => assert!(
+                    body.0
+                        .tlvs
+                        .get(KnownTlvTag::message_payload)
+                        .unwrap()
+                        .value
+                        == payload
+                ),
+                other => panic!("Expected a submit_sm, got {:?}", other),
+            },
+            other => panic!("Expected a submit_sm, got {:?}", other),
+        }
+        assert!(reader.read_pdu().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn after_a_pdu_with_a_bad_body_we_read_the_next_one() {
+        // An enquire_link with an unknown command_id, then a valid one
+        let mut bytes = ENQUIRE_LINK.to_vec();
+        bytes[4] = 0xff;
+        bytes.extend_from_slice(ENQUIRE_LINK);
+        let mut reader = PduReader::new(&bytes[..]);
+
+        let e = reader.read_pdu().await.unwrap_err();
+        assert!(matches!(e, ReadPduError::BadBody(_)), "{:?}", e);
+        assert_eq!(e.parse_error().status(), PduStatus::ESME_RINVCMDID as u32);
+
+        match reader.read_pdu().await.unwrap() {
+            Some(pdu) => assert_eq!(pdu.sequence_number(), 7),
+            None => panic!("Expected an enquire_link"),
+        }
+    }
+
+    #[tokio::test]
+    async fn pdus_up_to_a_custom_max_length_are_accepted() {
+        let mut reader =
+            PduReader::new(&ENQUIRE_LINK[..]).with_pdu_length_limits(8, 16);
+        assert!(reader.read_pdu().await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn pdus_over_a_custom_max_length_are_rejected() {
+        let mut reader =
+            PduReader::new(&ENQUIRE_LINK[..]).with_pdu_length_limits(8, 15);
+        let e = reader.read_pdu().await.unwrap_err();
+        assert_eq!(e.parse_error().status(), PduStatus::ESME_RINVCMDLEN as u32);
+    }
+
+    #[tokio::test]
+    async fn pdus_under_a_custom_min_length_are_rejected() {
+        let mut reader =
+            PduReader::new(&ENQUIRE_LINK[..]).with_pdu_length_limits(17, 100);
+        let e = reader.read_pdu().await.unwrap_err();
+        assert_eq!(e.parse_error().status(), PduStatus::ESME_RINVCMDLEN as u32);
+    }
+
+    #[tokio::test]
+    async fn unknown_tlvs_are_logged_and_kept() {
+        CapturingLogger::install();
+
+        let (mut client, server) = duplex(128);
+        let mut reader = PduReader::new(server).with_log_unknown_tlvs(true);
+
+        // A submit_sm with an unknown TLV 0x142E
+        client
+            .write_all(
+                b"\x00\x00\x00\x42\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x33\
+                \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+                \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi\
+                \x14\x2e\x00\x01\x07",
+            )
+            .await
+            .unwrap();
+
+        match reader.read_pdu().await.unwrap() {
+            Some(AnyPdu::Pdu(pdu)) => match pdu.body() {
+                PduBody::SubmitSm(body) => assert_eq!(
+                    body.0.tlvs.get_unknown(0x142e),
+                    Some(Tlv::new_unknown(0x142e, b"\x07"))
+                ),
+                _ => panic!("Expected a submit_sm"),
+            },
+            _ => panic!("Expected a submit_sm"),
+        }
+
+        let logged = CapturingLogger::lines();
+        assert!(
+            logged.iter().any(|line| line.contains(
+                "sequence_number=0x00000033 contains unknown TLV \
+                tag=0x142E length=1"
+            )),
+            "{:?}",
+            logged
+        );
+    }
+}
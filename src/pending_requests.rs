@@ -0,0 +1,136 @@
+use smpp_pdu::pdu::Pdu;
+use std::collections::HashMap;
+use tokio::sync::oneshot;
+
+/// Hands out the sequence_numbers for PDUs we originate on a connection.
+pub struct SequenceNumberAllocator {
+    last: std::sync::Mutex<u32>,
+}
+
+impl SequenceNumberAllocator {
+    pub fn new() -> Self {
+        Self {
+            last: std::sync::Mutex::new(0),
+        }
+    }
+
+    /// The next sequence_number, counting up from 1 and wrapping back to 1
+    /// after 0x7FFFFFFF.
+    pub fn next(&self) -> u32 {
+        let mut last = self.last.lock().unwrap();
+        // Valid sequence numbers are 0x00000001 to 0x7FFFFFFF
+        *last = if *last >= 0x7fffffff { 1 } else { *last + 1 };
+        *last
+    }
+}
+
+impl Default for SequenceNumberAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Requests we have sent and are waiting for responses to, by
+/// sequence_number.
+pub struct PendingRequests {
+    senders: std::sync::Mutex<HashMap<u32, oneshot::Sender<Pdu>>>,
+}
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self {
+            senders: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start waiting for the response with this sequence_number.  The
+    /// receiver completes when it arrives, or fails if we stop waiting
+    /// (e.g. because the connection closed).
+    pub fn register(&self, sequence_number: u32) -> oneshot::Receiver<Pdu> {
+        let (sender, receiver) = oneshot::channel();
+        self.senders.lock().unwrap().insert(sequence_number, sender);
+        receiver
+    }
+
+    /// Pass a response to whoever is waiting for it.  If it is not a
+    /// response, or nobody is waiting for it, returns it unchanged.
+    pub fn resolve(&self, pdu: Pdu) -> Option<Pdu> {
+        if pdu.command_id().value & 0x80000000 == 0 {
+            return Some(pdu);
+        }
+        let sender = self
+            .senders
+            .lock()
+            .unwrap()
+            .remove(&pdu.sequence_number.value);
+        match sender {
+            // If the receiver was dropped, nobody needs the response
+            Some(sender) => {
+                let _ = sender.send(pdu);
+                None
+            }
+            None => Some(pdu),
+        }
+    }
+
+    /// The number of requests still waiting for a response
+    pub fn len(&self) -> usize {
+        self.senders.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for PendingRequests {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::{EnquireLinkPdu, EnquireLinkRespPdu};
+
+    fn enquire_link_resp(sequence_number: u32) -> Pdu {
+        Pdu::new(0, sequence_number, EnquireLinkRespPdu::new().into()).unwrap()
+    }
+
+    #[test]
+    fn sequence_numbers_count_up_from_1_and_wrap_skipping_0() {
+        let allocator = SequenceNumberAllocator::new();
+        assert_eq!(allocator.next(), 1);
+        assert_eq!(allocator.next(), 2);
+
+        *allocator.last.lock().unwrap() = 0x7ffffffe;
+        assert_eq!(allocator.next(), 0x7fffffff);
+        assert_eq!(allocator.next(), 1);
+    }
+
+    #[tokio::test]
+    async fn responses_arriving_out_of_order_reach_their_own_requests() {
+        let pending = PendingRequests::new();
+        let first = pending.register(1);
+        let second = pending.register(2);
+
+        assert!(pending.resolve(enquire_link_resp(2)).is_none());
+        assert!(pending.resolve(enquire_link_resp(1)).is_none());
+
+        assert_eq!(first.await.unwrap().sequence_number.value, 1);
+        assert_eq!(second.await.unwrap().sequence_number.value, 2);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn unexpected_responses_and_requests_are_returned() {
+        let pending = PendingRequests::new();
+        let _receiver = pending.register(1);
+
+        assert!(pending.resolve(enquire_link_resp(3)).is_some());
+        let request = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+        assert!(pending.resolve(request).is_some());
+        assert_eq!(pending.len(), 1);
+    }
+}
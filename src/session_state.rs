@@ -0,0 +1,266 @@
+use smpp_pdu::pdu::{Pdu, PduBody, PduStatus};
+use std::fmt::{Display, Formatter};
+
+use crate::pdu::{ExtraPdu, ExtraPduBody};
+
+/// The state of an SMPP session, as described in
+/// https://smpp.org/SMPP_v3_4_Issue1_2.pdf section 2.2
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionState {
+    /// Connected, but not yet bound
+    Open,
+    /// Bound as a transmitter: the ESME may send messages to us
+    BoundTx,
+    /// Bound as a receiver: we may send messages to the ESME
+    BoundRx,
+    /// Bound as a transceiver: messages may go in both directions
+    BoundTrx,
+    /// The ESME has unbound, so no more messages may be exchanged
+    Unbound,
+}
+
+impl SessionState {
+    pub fn is_bound(&self) -> bool {
+        matches!(
+            self,
+            SessionState::BoundTx
+                | SessionState::BoundRx
+                | SessionState::BoundTrx
+        )
+    }
+
+    /// True if the ESME may send us submit_sm etc. in this state
+    pub fn can_transmit(&self) -> bool {
+        matches!(self, SessionState::BoundTx | SessionState::BoundTrx)
+    }
+
+    /// True if we may send deliver_sm etc. to the ESME in this state
+    pub fn can_receive(&self) -> bool {
+        matches!(self, SessionState::BoundRx | SessionState::BoundTrx)
+    }
+}
+
+impl Display for SessionState {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str(match self {
+            SessionState::Open => "open",
+            SessionState::BoundTx => "transmitter",
+            SessionState::BoundRx => "receiver",
+            SessionState::BoundTrx => "transceiver",
+            SessionState::Unbound => "unbound",
+        })
+    }
+}
+
+pub trait ValidateForSession {
+    /// Check whether this PDU may be sent (in whichever direction it
+    /// travels) over a session in the supplied state.  If not, returns
+    /// the command_status that should be used to reject it.
+    fn validate_for_session(
+        &self,
+        state: &SessionState,
+    ) -> Result<(), PduStatus>;
+}
+
+impl ValidateForSession for Pdu {
+    fn validate_for_session(
+        &self,
+        state: &SessionState,
+    ) -> Result<(), PduStatus> {
+        if *state == SessionState::Unbound {
+            return Err(PduStatus::ESME_RINVBNDSTS);
+        }
+
+        let allowed = match self.body() {
+            PduBody::BindReceiver(_)
+            | PduBody::BindTransceiver(_)
+            | PduBody::BindTransmitter(_) => {
+                if state.is_bound() {
+                    return Err(PduStatus::ESME_RALYBND);
+                }
+                true
+            }
+            // We send bind responses while the session is still open
+            PduBody::BindReceiverResp(_)
+            | PduBody::BindTransceiverResp(_)
+            | PduBody::BindTransmitterResp(_) => true,
+            PduBody::EnquireLink(_)
+            | PduBody::EnquireLinkResp(_)
+            | PduBody::GenericNack(_) => true,
+            // ESME -> SMSC
+            PduBody::SubmitSm(_) | PduBody::SubmitSmResp(_) => {
+                state.can_transmit()
+            }
+            // SMSC -> ESME
+            PduBody::DeliverSm(_) => state.can_receive(),
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(PduStatus::ESME_RINVBNDSTS)
+        }
+    }
+}
+
+impl ValidateForSession for ExtraPdu {
+    fn validate_for_session(
+        &self,
+        state: &SessionState,
+    ) -> Result<(), PduStatus> {
+        let allowed = match self.body() {
+            // ESME -> SMSC
+            ExtraPduBody::CancelSm(_)
+            | ExtraPduBody::QuerySm(_)
+            | ExtraPduBody::SubmitMulti(_) => state.can_transmit(),
+            // SMSC -> ESME
+            ExtraPduBody::CancelSmResp
+            | ExtraPduBody::QuerySmResp(_)
+            | ExtraPduBody::SubmitMultiResp(_) => state.can_transmit(),
+            // ESME -> SMSC, acknowledging our deliver_sm
+            ExtraPduBody::DeliverSmResp(_) => state.can_receive(),
+            // Either way: a message from the ESME, or a DR from us
+            ExtraPduBody::DataSm(_) | ExtraPduBody::DataSmResp(_) => {
+                state.is_bound()
+            }
+            // SMSC -> ESME, with no response
+            ExtraPduBody::AlertNotification(_) => state.can_receive(),
+            // SMSC -> ESME, asking it to bind
+            ExtraPduBody::Outbind(_) => *state == SessionState::Open,
+            ExtraPduBody::Unbind => state.is_bound(),
+            // We may have sent unbind as we stopped
+            ExtraPduBody::UnbindResp => true,
+            ExtraPduBody::EnquireLinkResp(_) => true,
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(PduStatus::ESME_RINVBNDSTS)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::tlvs::Tlvs;
+    use smpp_pdu::pdu::{
+        BindTransmitterPdu, DeliverEsmClass, DeliverSmPdu, EnquireLinkPdu,
+        SubmitEsmClass, SubmitSmPdu,
+    };
+
+    fn status(pdu: &Pdu, state: SessionState) -> Result<(), u32> {
+        pdu.validate_for_session(&state).map_err(|e| e as u32)
+    }
+
+    fn submit_sm() -> Pdu {
+        Pdu::new(
+            0,
+            1,
+            SubmitSmPdu::new(
+                "",
+                0,
+                0,
+                "src",
+                0,
+                0,
+                "dest",
+                SubmitEsmClass::Default as u8,
+                0x34,
+                1,
+                "",
+                "",
+                1,
+                0,
+                3,
+                0,
+                b"hi",
+                Tlvs::new(),
+            )
+            .unwrap()
+            .into(),
+        )
+        .unwrap()
+    }
+
+    fn deliver_sm() -> Pdu {
+        Pdu::new(
+            0,
+            1,
+            DeliverSmPdu::new(
+                "",
+                0,
+                0,
+                "src",
+                0,
+                0,
+                "dest",
+                DeliverEsmClass::Default as u8,
+                0x34,
+                1,
+                "",
+                "",
+                1,
+                0,
+                3,
+                0,
+                b"hi",
+                Tlvs::new(),
+            )
+            .unwrap()
+            .into(),
+        )
+        .unwrap()
+    }
+
+    fn bind_transmitter() -> Pdu {
+        Pdu::new(
+            0,
+            1,
+            BindTransmitterPdu::new("id", "pw", "", 0x34, 0, 0, "")
+                .unwrap()
+                .into(),
+        )
+        .unwrap()
+    }
+
+    const INVBNDSTS: u32 = PduStatus::ESME_RINVBNDSTS as u32;
+    const ALYBND: u32 = PduStatus::ESME_RALYBND as u32;
+
+    #[test]
+    fn submit_sm_is_only_allowed_when_bound_to_transmit() {
+        let pdu = submit_sm();
+        assert_eq!(status(&pdu, SessionState::BoundTx), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundTrx), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundRx), Err(INVBNDSTS));
+        assert_eq!(status(&pdu, SessionState::Open), Err(INVBNDSTS));
+        assert_eq!(status(&pdu, SessionState::Unbound), Err(INVBNDSTS));
+    }
+
+    #[test]
+    fn deliver_sm_is_only_allowed_when_bound_to_receive() {
+        let pdu = deliver_sm();
+        assert_eq!(status(&pdu, SessionState::BoundRx), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundTrx), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundTx), Err(INVBNDSTS));
+        assert_eq!(status(&pdu, SessionState::Open), Err(INVBNDSTS));
+    }
+
+    #[test]
+    fn bind_is_only_allowed_when_not_already_bound() {
+        let pdu = bind_transmitter();
+        assert_eq!(status(&pdu, SessionState::Open), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundTx), Err(ALYBND));
+        assert_eq!(status(&pdu, SessionState::BoundRx), Err(ALYBND));
+        assert_eq!(status(&pdu, SessionState::BoundTrx), Err(ALYBND));
+    }
+
+    #[test]
+    fn enquire_link_is_allowed_whether_bound_or_not() {
+        let pdu = Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap();
+        assert_eq!(status(&pdu, SessionState::Open), Ok(()));
+        assert_eq!(status(&pdu, SessionState::BoundTx), Ok(()));
+        assert_eq!(status(&pdu, SessionState::Unbound), Err(INVBNDSTS));
+    }
+}
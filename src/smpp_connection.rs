@@ -1,142 +1,618 @@
 use ascii::AsciiString;
-use bytes::{Buf, BytesMut};
 use log::*;
-use smpp_pdu::pdu::{CheckOutcome, Pdu, PduParseError, PduParseErrorBody};
+use smpp_pdu::pdu::{
+    Pdu, PduBody, PduParseError, PduParseErrorBody, PduStatus,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
 use std::io;
-use std::io::Cursor;
 use std::net::SocketAddr;
-use tokio::io::{split, AsyncReadExt, ReadHalf, WriteHalf};
-use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{
+    split, AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf,
+};
+use tokio::sync::{oneshot, Mutex, Notify};
+use tokio::time::Instant;
 
-#[derive(Clone, Eq, Hash, PartialEq)]
+use crate::async_result::AsyncResult;
+use crate::pdu::{write_pdu, AnyPdu, ExtraPdu, InterfaceVersion};
+use crate::pdu_reader::{parse_framed, PduReader, ReadPduError};
+use crate::pending_requests::{PendingRequests, SequenceNumberAllocator};
+use crate::session_state::SessionState;
+use crate::smsc::metrics::SmscMetrics;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct EsmeId {
     pub system_id: AsciiString,
     pub system_type: AsciiString,
 }
 
+/// A stream we can speak SMPP over, e.g. a TcpStream, or a TLS stream
+/// wrapping one.
+pub trait SmppStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> SmppStream for S {}
+
+pub(crate) type BoxedStream = Box<dyn SmppStream>;
+
+/// Where a connection comes from, for logging
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    /// The path of the Unix domain socket we accepted the connection on,
+    /// since Unix peers are usually unnamed
+    Unix(String),
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(socket_addr: SocketAddr) -> Self {
+        PeerAddr::Tcp(socket_addr)
+    }
+}
+
+impl Display for PeerAddr {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PeerAddr::Tcp(socket_addr) => write!(formatter, "{}", socket_addr),
+            PeerAddr::Unix(path) => write!(formatter, "unix:{}", path),
+        }
+    }
+}
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies a connection for its whole life.  Unlike its socket address,
+/// it is never reused by a later connection.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ConnectionId(pub u64);
+
+impl ConnectionId {
+    fn next() -> Self {
+        ConnectionId(NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Display for ConnectionId {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "conn#{}", self.0)
+    }
+}
+
 pub struct SmppConnection {
-    pub socket_addr: SocketAddr,
-    read: Mutex<Option<SmppRead>>,
+    pub id: ConnectionId,
+    pub socket_addr: PeerAddr,
+    read: Mutex<Option<PduReader<ReadHalf<BoxedStream>>>>,
     write: Mutex<Option<SmppWrite>>,
     bound_esme_id: std::sync::Mutex<Option<EsmeId>>,
+    session_state: std::sync::Mutex<SessionState>,
+    interface_version: std::sync::Mutex<Option<InterfaceVersion>>,
+    last_write: std::sync::Mutex<Instant>,
+    sequence_numbers: SequenceNumberAllocator,
+    pending_requests: PendingRequests,
+    outstanding_enquire_links: std::sync::Mutex<HashSet<u32>>,
+    /// sequence_numbers of the client's requests we are still handling in
+    /// the background, e.g. a deferred submit_sm
+    deferred_requests: std::sync::Mutex<HashSet<u32>>,
+    window_size: Option<usize>,
+    window: std::sync::Mutex<Window>,
+    /// Where to count the PDUs we write with write_windowed()
+    metrics: Option<Arc<SmscMetrics>>,
+    /// PDUs passed to enqueue_windowed(), waiting for write_enqueued()
+    enqueued: std::sync::Mutex<VecDeque<AnyPdu>>,
+    /// Held while taking a PDU from enqueued and writing it, so that they
+    /// are written in the order they were enqueued
+    writing_enqueued: Mutex<()>,
+    closing: Notify,
+}
+
+/// The requests (e.g. deliver_sm) we have sent and not had a response to,
+/// and the ones waiting for a space in the window.
+#[derive(Default)]
+struct Window {
+    outstanding: HashMap<u32, Outstanding>,
+    queued: VecDeque<AnyPdu>,
+}
+
+/// A request we have sent, kept so that we can send it again if no
+/// response arrives.
+struct Outstanding {
+    bytes: Vec<u8>,
+    sent_at: Instant,
+    retries: usize,
+}
+
+impl Outstanding {
+    async fn new(pdu: &AnyPdu) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        pdu.write(&mut bytes).await?;
+        Ok(Self {
+            bytes,
+            sent_at: Instant::now(),
+            retries: 0,
+        })
+    }
 }
 
 impl SmppConnection {
-    pub fn new(
-        tcp_stream: TcpStream,
-        socket_addr: SocketAddr,
+    pub fn new<S: SmppStream + 'static>(
+        stream: S,
+        socket_addr: impl Into<PeerAddr>,
     ) -> SmppConnection {
-        let (read_stream, write_stream) = split(tcp_stream);
-        let buffer = BytesMut::with_capacity(4096);
-        let read = SmppRead {
-            stream: read_stream,
-            buffer,
-        };
+        let stream: BoxedStream = Box::new(stream);
+        let (read_stream, write_stream) = split(stream);
+        let read = PduReader::new(read_stream);
         let write = SmppWrite {
             stream: write_stream,
         };
         SmppConnection {
+            id: ConnectionId::next(),
             read: Mutex::new(Some(read)),
             write: Mutex::new(Some(write)),
-            socket_addr,
+            socket_addr: socket_addr.into(),
             bound_esme_id: std::sync::Mutex::new(None),
+            session_state: std::sync::Mutex::new(SessionState::Open),
+            interface_version: std::sync::Mutex::new(None),
+            last_write: std::sync::Mutex::new(Instant::now()),
+            sequence_numbers: SequenceNumberAllocator::new(),
+            pending_requests: PendingRequests::new(),
+            outstanding_enquire_links: std::sync::Mutex::new(HashSet::new()),
+            deferred_requests: std::sync::Mutex::new(HashSet::new()),
+            window_size: None,
+            window: std::sync::Mutex::new(Window::default()),
+            metrics: None,
+            enqueued: std::sync::Mutex::new(VecDeque::new()),
+            writing_enqueued: Mutex::new(()),
+            closing: Notify::new(),
+        }
+    }
+
+    /// Reject PDUs containing more than this many TLVs without parsing
+    /// them.
+    pub fn with_max_tlvs_per_pdu(mut self, max_tlvs_per_pdu: usize) -> Self {
+        if let Some(read) = self.read.get_mut() {
+            read.set_max_tlvs_per_pdu(max_tlvs_per_pdu);
+        }
+        self
+    }
+
+    /// Log the tag and length of any TLVs we don't recognise in the PDUs
+    /// we read.
+    pub fn with_log_unknown_tlvs(mut self, log_unknown_tlvs: bool) -> Self {
+        if let Some(read) = self.read.get_mut() {
+            read.set_log_unknown_tlvs(log_unknown_tlvs);
         }
+        self
+    }
+
+    /// Reject PDUs whose command_length is outside these limits
+    pub fn with_pdu_length_limits(
+        mut self,
+        min_pdu_length: usize,
+        max_pdu_length: usize,
+    ) -> Self {
+        if let Some(read) = self.read.get_mut() {
+            read.set_pdu_length_limits(min_pdu_length, max_pdu_length);
+        }
+        self
+    }
+
+    /// Allow at most this many PDUs sent with write_windowed() to be
+    /// waiting for a response at once.  None means no limit.
+    pub fn with_window_size(mut self, window_size: Option<usize>) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    /// Count each PDU we write with write_windowed() (including those
+    /// queued, then written when space appears, and those sent again) in
+    /// these metrics' deliver_sm_sent.
+    pub(crate) fn with_metrics(mut self, metrics: Arc<SmscMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 
     pub fn bound_esme_id(&self) -> Option<EsmeId> {
         self.bound_esme_id.lock().unwrap().clone()
     }
 
-    pub async fn bind(&self, system_id: AsciiString, system_type: AsciiString) {
+    pub fn session_state(&self) -> SessionState {
+        *self.session_state.lock().unwrap()
+    }
+
+    /// The SMPP version we agreed to speak when the client bound
+    pub fn interface_version(&self) -> Option<InterfaceVersion> {
+        *self.interface_version.lock().unwrap()
+    }
+
+    pub async fn bind(
+        &self,
+        system_id: AsciiString,
+        system_type: AsciiString,
+        session_state: SessionState,
+        interface_version: InterfaceVersion,
+    ) {
         self.bound_esme_id.lock().unwrap().replace(EsmeId {
             system_id,
             system_type,
         });
+        *self.session_state.lock().unwrap() = session_state;
+        self.interface_version
+            .lock()
+            .unwrap()
+            .replace(interface_version);
     }
 
-    pub async fn read_pdu(&self) -> Result<Option<Pdu>, PduParseError> {
-        loop {
-            let mut read = self.read.lock().await;
-            if let Some(read) = &mut *read {
-                if let Some(pdu) = read.parse_pdu()? {
-                    return Ok(Some(pdu));
-                }
+    /// Record that the client has unbound, so may send us no more PDUs.
+    pub fn unbind(&self) {
+        *self.session_state.lock().unwrap() = SessionState::Unbound;
+    }
 
-                if 0 == read.read_own_buf().await? {
-                    if read.buffer.is_empty() {
-                        return Ok(None);
-                    } else {
-                        return Err(PduParseError::new(
-                            PduParseErrorBody::NotEnoughBytes,
-                        ));
-                    }
-                }
-            } else {
-                error!("Attempting to read from a closed connection!");
-                return Err(PduParseError::new(
-                    PduParseErrorBody::NotEnoughBytes.into(),
-                ));
-            }
+    /// Allocate a sequence_number for a PDU we are originating on this
+    /// connection (e.g. enquire_link).
+    pub fn next_sequence_number(&self) -> u32 {
+        self.sequence_numbers.next()
+    }
+
+    /// Send a request with a new sequence_number.  The returned receiver
+    /// completes when response_received() is given the matching response.
+    pub async fn write_request(
+        &self,
+        body: PduBody,
+    ) -> AsyncResult<oneshot::Receiver<Pdu>> {
+        let sequence_number = self.next_sequence_number();
+        let pdu = Pdu::new(PduStatus::ESME_ROK as u32, sequence_number, body)?;
+        let receiver = self.pending_requests.register(sequence_number);
+        self.write_pdu(&pdu).await?;
+        Ok(receiver)
+    }
+
+    /// Hand a PDU we received to the write_request() waiting for it.  If
+    /// nobody was waiting for it, returns it so it can be handled normally.
+    pub fn response_received(&self, pdu: Pdu) -> Option<Pdu> {
+        self.pending_requests.resolve(pdu)
+    }
+
+    /// How long it is since we last wrote a PDU to this connection.
+    pub fn idle_for(&self) -> Duration {
+        self.last_write.lock().unwrap().elapsed()
+    }
+
+    /// Record that we sent an enquire_link with this sequence_number, and
+    /// are waiting for the matching enquire_link_resp.
+    pub fn enquire_link_sent(&self, sequence_number: u32) {
+        self.outstanding_enquire_links
+            .lock()
+            .unwrap()
+            .insert(sequence_number);
+    }
+
+    /// Record that we received an enquire_link_resp.  Returns false if it
+    /// did not match any enquire_link we sent.
+    pub fn enquire_link_resp_received(&self, sequence_number: u32) -> bool {
+        let mut outstanding = self.outstanding_enquire_links.lock().unwrap();
+        if outstanding.contains(&sequence_number) {
+            // The client is alive, so none of the earlier ones count
+            // as unanswered any more.
+            outstanding.clear();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The number of enquire_links we have sent since we last received a
+    /// response to one.
+    pub fn unanswered_enquire_links(&self) -> usize {
+        self.outstanding_enquire_links.lock().unwrap().len()
+    }
+
+    /// Record that we will respond later to the client's request with this
+    /// sequence_number.
+    pub fn deferred_request_started(&self, sequence_number: u32) {
+        self.deferred_requests
+            .lock()
+            .unwrap()
+            .insert(sequence_number);
+    }
+
+    /// Record that we have responded to a request passed to
+    /// deferred_request_started().
+    pub fn deferred_request_finished(&self, sequence_number: u32) {
+        self.deferred_requests
+            .lock()
+            .unwrap()
+            .remove(&sequence_number);
+    }
+
+    /// Whether we have yet to respond to a request from the client with
+    /// this sequence_number.
+    pub fn deferred_request_in_flight(&self, sequence_number: u32) -> bool {
+        self.deferred_requests
+            .lock()
+            .unwrap()
+            .contains(&sequence_number)
+    }
+
+    pub async fn read_pdu(&self) -> Result<Option<AnyPdu>, ReadPduError> {
+        if let Some(read) = &mut *self.read.lock().await {
+            read.read_pdu().await
+        } else {
+            error!("Attempting to read from a closed connection!");
+            Err(ReadPduError::Framing(PduParseError::new(
+                PduParseErrorBody::NotEnoughBytes,
+            )))
         }
     }
 
     pub async fn write_pdu(&self, pdu: &Pdu) -> io::Result<()> {
-        info!("=> {} {:?}", self.socket_addr, pdu);
+        info!("=> {} {:?}", self, pdu);
         if let Some(write) = &mut *self.write.lock().await {
-            pdu.write(&mut write.stream).await
+            *self.last_write.lock().unwrap() = Instant::now();
+            write_pdu(pdu, &mut write.stream).await?;
+            write.flush().await
         } else {
             error!("Attempting to write to a closed connection!");
             Err(io::ErrorKind::BrokenPipe.into())
         }
     }
 
-    pub async fn disconnect(&self) {
-        self.read.lock().await.take();
-        self.write.lock().await.take();
+    pub async fn write_any_pdu(&self, pdu: &AnyPdu) -> io::Result<()> {
+        match pdu {
+            AnyPdu::Pdu(pdu) => self.write_pdu(pdu).await,
+            AnyPdu::Extra(pdu) => self.write_extra_pdu(pdu).await,
+        }
     }
-}
 
-struct SmppRead {
-    stream: ReadHalf<TcpStream>,
-    buffer: BytesMut,
-}
+    pub async fn write_extra_pdu(&self, pdu: &ExtraPdu) -> io::Result<()> {
+        info!("=> {} {:?}", self, pdu);
+        if let Some(write) = &mut *self.write.lock().await {
+            *self.last_write.lock().unwrap() = Instant::now();
+            pdu.write(&mut write.stream).await?;
+            write.flush().await
+        } else {
+            error!("Attempting to write to a closed connection!");
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    /// Write a request whose response we will report with
+    /// windowed_response_received().  If the window is already full of
+    /// requests waiting for responses, queue it to be sent when there is
+    /// space.
+    pub async fn write_windowed(&self, pdu: AnyPdu) -> io::Result<()> {
+        let outstanding = Outstanding::new(&pdu).await?;
+        let send_now = {
+            let mut window = self.window.lock().unwrap();
+            match self.window_size {
+                Some(size) if window.outstanding.len() >= size => {
+                    window.queued.push_back(pdu);
+                    None
+                }
+                _ => {
+                    window
+                        .outstanding
+                        .insert(pdu.sequence_number(), outstanding);
+                    Some(pdu)
+                }
+            }
+        };
+        match send_now {
+            Some(pdu) => self.write_windowed_pdu(&pdu).await,
+            None => {
+                info!(
+                    "{} window of {} is full: queued PDU",
+                    self,
+                    self.window_size.unwrap_or(0)
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Add a PDU to the end of this connection's queue, for a later call to
+    /// write_enqueued() to pass to write_windowed().  Call this, then
+    /// write_enqueued() from a new task, once for each PDU: the PDUs are
+    /// written in the order they were enqueued, whatever order the tasks
+    /// run in.
+    pub fn enqueue_windowed(&self, pdu: impl Into<AnyPdu>) {
+        self.enqueued.lock().unwrap().push_back(pdu.into());
+    }
+
+    /// Write the PDU at the front of the queue enqueue_windowed() adds to,
+    /// with write_windowed().
+    pub async fn write_enqueued(&self) -> io::Result<()> {
+        let _writing = self.writing_enqueued.lock().await;
+        let next = self.enqueued.lock().unwrap().pop_front();
+        match next {
+            Some(pdu) => self.write_windowed(pdu).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Record that a response to a PDU sent with write_windowed() arrived,
+    /// and send the next queued PDU in the space it leaves.  Returns false
+    /// if we were not waiting for a response with this sequence_number.
+    pub async fn windowed_response_received(
+        &self,
+        sequence_number: u32,
+    ) -> io::Result<bool> {
+        if self
+            .window
+            .lock()
+            .unwrap()
+            .outstanding
+            .remove(&sequence_number)
+            .is_none()
+        {
+            return Ok(false);
+        }
+        self.write_next_queued().await?;
+        Ok(true)
+    }
 
-impl SmppRead {
-    async fn read_own_buf(&mut self) -> Result<usize, io::Error> {
-        self.stream.read_buf(&mut self.buffer).await
+    /// Send again each PDU sent with write_windowed() that has waited
+    /// longer than timeout for a response.  Once one has been sent again
+    /// max_retries times, give up on it, freeing its space in the window.
+    /// Returns the sequence_numbers of the PDUs we gave up on.
+    pub async fn resend_overdue(
+        &self,
+        timeout: Duration,
+        max_retries: usize,
+    ) -> io::Result<Vec<u32>> {
+        let (resend, given_up) = {
+            let mut window = self.window.lock().unwrap();
+            let overdue: Vec<u32> = window
+                .outstanding
+                .iter()
+                .filter(|(_, outstanding)| {
+                    outstanding.sent_at.elapsed() >= timeout
+                })
+                .map(|(sequence_number, _)| *sequence_number)
+                .collect();
+            let mut resend = Vec::new();
+            let mut given_up = Vec::new();
+            for sequence_number in overdue {
+                let outstanding =
+                    window.outstanding.get_mut(&sequence_number).unwrap();
+                if outstanding.retries < max_retries {
+                    outstanding.retries += 1;
+                    outstanding.sent_at = Instant::now();
+                    resend.push((sequence_number, outstanding.bytes.clone()));
+                } else {
+                    window.outstanding.remove(&sequence_number);
+                    error!(
+                        "{} - giving up on PDU with sequence_number={:#010X} \
+                        after {} retries with no response",
+                        self, sequence_number, max_retries
+                    );
+                    given_up.push(sequence_number);
+                }
+            }
+            (resend, given_up)
+        };
+        for (sequence_number, bytes) in resend {
+            warn!(
+                "{} - no response to PDU with sequence_number={:#010X}: \
+                sending it again",
+                self, sequence_number
+            );
+            self.write_bytes(&bytes).await?;
+            self.windowed_pdu_written();
+        }
+        for _ in &given_up {
+            self.write_next_queued().await?;
+        }
+        Ok(given_up)
     }
 
-    fn parse_pdu(&mut self) -> Result<Option<Pdu>, PduParseError> {
-        let mut buf = Cursor::new(&self.buffer[..]);
-        match Pdu::check(&mut buf) {
-            Ok(CheckOutcome::Ready) => {
-                // Pdu::check moved us to the end, so position is length
-                let len = buf.position() as usize;
+    /// If any PDUs are waiting for space in the window, send the first.
+    async fn write_next_queued(&self) -> io::Result<()> {
+        let next = self.window.lock().unwrap().queued.pop_front();
+        if let Some(pdu) = next {
+            let outstanding = Outstanding::new(&pdu).await?;
+            self.window
+                .lock()
+                .unwrap()
+                .outstanding
+                .insert(pdu.sequence_number(), outstanding);
+            self.write_windowed_pdu(&pdu).await?;
+        }
+        Ok(())
+    }
+
+    /// Write a PDU that has a place in the window
+    async fn write_windowed_pdu(&self, pdu: &AnyPdu) -> io::Result<()> {
+        self.write_any_pdu(pdu).await?;
+        self.windowed_pdu_written();
+        Ok(())
+    }
 
-                // Rewind and parse
-                buf.set_position(0);
-                let pdu = Pdu::parse(&mut buf)?;
+    fn windowed_pdu_written(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.deliver_sm_sent();
+        }
+    }
+
+    /// Take the PDUs sent with write_windowed() that have had no response,
+    /// followed by those still waiting to be sent, so that they can be
+    /// sent again elsewhere.
+    pub fn take_unacknowledged(&self) -> Vec<AnyPdu> {
+        let mut window = self.window.lock().unwrap();
+        let mut outstanding: Vec<(u32, Outstanding)> =
+            window.outstanding.drain().collect();
+        outstanding.sort_by_key(|(sequence_number, _)| *sequence_number);
+        let mut pdus: Vec<AnyPdu> = outstanding
+            .into_iter()
+            .filter_map(|(sequence_number, outstanding)| {
+                match parse_framed(&outstanding.bytes) {
+                    Ok(pdu) => Some(pdu),
+                    Err(e) => {
+                        error!(
+                            "{} - unable to re-read PDU with \
+                            sequence_number={:#010X}: {}",
+                            self, sequence_number, e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        pdus.extend(window.queued.drain(..));
+        pdus.extend(self.enqueued.lock().unwrap().drain(..));
+        pdus
+    }
+
+    async fn write_bytes(&self, bytes: &[u8]) -> io::Result<()> {
+        if let Some(write) = &mut *self.write.lock().await {
+            *self.last_write.lock().unwrap() = Instant::now();
+            write.stream.write_all(bytes).await?;
+            write.flush().await
+        } else {
+            error!("Attempting to write to a closed connection!");
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
 
-                // Parsing succeeded, so consume bytes from buffer and return
-                self.buffer.advance(len);
-                Ok(Some(pdu))
+    /// Close our side of the connection, so the client reads EOF after
+    /// anything we have already written, and wake up whoever is waiting
+    /// in closed().
+    pub async fn close(&self) {
+        if let Some(mut write) = self.write.lock().await.take() {
+            if let Err(e) = write.stream.shutdown().await {
+                warn!("{} failed to shut down cleanly: {}", self, e);
             }
-            // Try again when we have more
-            Ok(CheckOutcome::Incomplete) => Ok(None),
-            // Failed (e.g. too long)
-            Err(e) => Err(e.into()),
-            // Issue#1: it would be good to respond with a specific error here,
-            // instead of generic_nack.  That should be possible in some cases
-            // if we can read the PDU header before we reject it.  It's not
-            // too bad to do this though, because the PDU is actually
-            // malformed, so not knowing what type it is is forgivable.
         }
+        self.closing.notify_one();
+    }
+
+    /// Completes when close() has been called.
+    pub async fn closed(&self) {
+        self.closing.notified().await
+    }
+
+    pub async fn disconnect(&self) {
+        self.read.lock().await.take();
+        self.write.lock().await.take();
+    }
+}
+
+/// How we refer to a connection in logs, e.g. "[conn#42] 127.0.0.1:5000"
+impl Display for SmppConnection {
+    fn fmt(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "[{}] {}", self.id, self.socket_addr)
     }
 }
 
 struct SmppWrite {
-    stream: WriteHalf<TcpStream>,
+    stream: WriteHalf<BoxedStream>,
 }
 
-impl SmppWrite {}
+impl SmppWrite {
+    /// Send on anything we have written.  Buffering streams, e.g. TLS, may
+    /// otherwise hold on to it until they have more to send.
+    async fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush().await
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+use crate::smpp_connection::{BoxedStream, PeerAddr};
+
+/// The prefix of a bind_address that names a Unix domain socket, e.g.
+/// "unix:/run/smsc.sock"
+const UNIX_PREFIX: &str = "unix:";
+
+/// Accepts connections on a TCP port, or on a Unix domain socket
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, String),
+}
+
+impl Listener {
+    /// Listen on bind_address, which is either "host:port" or "unix:path".
+    /// Binding to a Unix socket fails if its path already exists.
+    pub async fn bind(bind_address: &str) -> io::Result<Self> {
+        match bind_address.strip_prefix(UNIX_PREFIX) {
+            #[cfg(unix)]
+            Some(path) => Ok(Listener::Unix(
+                UnixListener::bind(path)?,
+                String::from(path),
+            )),
+            #[cfg(not(unix))]
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            )),
+            None => Ok(Listener::Tcp(TcpListener::bind(bind_address).await?)),
+        }
+    }
+
+    pub async fn accept(&self) -> io::Result<(BoxedStream, PeerAddr)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, socket_addr) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Tcp(socket_addr)))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Unix(path.clone())))
+            }
+        }
+    }
+}
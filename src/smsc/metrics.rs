@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters describing what the SMSC has done since it started, for
+/// operators to monitor.  Updated as things happen, and read with
+/// snapshot().
+#[derive(Default)]
+pub struct SmscMetrics {
+    open_connections: AtomicU64,
+    binds: AtomicU64,
+    submit_sm_received: AtomicU64,
+    submit_sm_accepted: AtomicU64,
+    submit_sm_rejected: AtomicU64,
+    deliver_sm_sent: AtomicU64,
+    deliver_sm_acknowledged: AtomicU64,
+    deliver_sm_failed: AtomicU64,
+}
+
+/// The values of the SmscMetrics counters at one moment
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Connections open now, bound or not
+    pub open_connections: u64,
+    /// Successful binds of any type
+    pub binds: u64,
+    pub submit_sm_received: u64,
+    /// submit_sm PDUs we responded to with ESME_ROK
+    pub submit_sm_accepted: u64,
+    /// submit_sm PDUs we responded to with an error, whether we or the
+    /// SmscLogic rejected them
+    pub submit_sm_rejected: u64,
+    /// deliver_sm PDUs written to clients, counting each time one is sent
+    /// again, but not those still waiting for a space in the window
+    pub deliver_sm_sent: u64,
+    /// deliver_sm PDUs the client responded to with ESME_ROK
+    pub deliver_sm_acknowledged: u64,
+    /// deliver_sm PDUs we could not write, that the client responded to
+    /// with an error, or that we gave up on (including held DRs whose client
+    /// did not bind again in time)
+    pub deliver_sm_failed: u64,
+}
+
+impl SmscMetrics {
+    pub fn connection_opened(&self) {
+        increment(&self.open_connections);
+    }
+
+    pub fn connection_closed(&self) {
+        self.open_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn bound(&self) {
+        increment(&self.binds);
+    }
+
+    pub fn submit_sm_received(&self) {
+        increment(&self.submit_sm_received);
+    }
+
+    pub fn submit_sm_responded(&self, accepted: bool) {
+        if accepted {
+            increment(&self.submit_sm_accepted);
+        } else {
+            increment(&self.submit_sm_rejected);
+        }
+    }
+
+    pub fn deliver_sm_sent(&self) {
+        increment(&self.deliver_sm_sent);
+    }
+
+    pub fn deliver_sm_acknowledged(&self) {
+        increment(&self.deliver_sm_acknowledged);
+    }
+
+    pub fn deliver_sm_failed(&self) {
+        increment(&self.deliver_sm_failed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            open_connections: get(&self.open_connections),
+            binds: get(&self.binds),
+            submit_sm_received: get(&self.submit_sm_received),
+            submit_sm_accepted: get(&self.submit_sm_accepted),
+            submit_sm_rejected: get(&self.submit_sm_rejected),
+            deliver_sm_sent: get(&self.deliver_sm_sent),
+            deliver_sm_acknowledged: get(&self.deliver_sm_acknowledged),
+            deliver_sm_failed: get(&self.deliver_sm_failed),
+        }
+    }
+}
+
+fn increment(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
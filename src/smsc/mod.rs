@@ -1,9 +1,21 @@
+mod listener;
+pub(crate) mod metrics;
+mod pdu_rate_limiter;
+#[allow(clippy::module_inception)]
 pub mod smsc;
 pub mod smsc_config;
 pub mod smsc_logic;
+mod submit_throttle;
+mod tls;
 
+pub use metrics::MetricsSnapshot;
 pub use smpp_pdu::pdu::data::bind_data::BindData;
 pub use smpp_pdu::pdu::data::bind_resp_data::BindRespData;
-pub use smsc::{run, Smsc};
-pub use smsc_config::SmscConfig;
-pub use smsc_logic::{BindError, SmscLogic, SubmitSmError};
+pub use smsc::{
+    run, run_until, DeliveryStatus, PendingMessage, ReceivePduError, Smsc,
+};
+pub use smsc_config::{SmscConfig, TlsConfig};
+pub use smsc_logic::{
+    BindContext, BindError, CancelSmError, QuerySmError, SmscLogic,
+    SubmitOutcome, SubmitSmError,
+};
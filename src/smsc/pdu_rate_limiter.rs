@@ -0,0 +1,47 @@
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Counts the PDUs a client sends us in each one-second window, so we can
+/// stop reading from it when it sends too many.
+pub struct PduRateLimiter {
+    max_per_second: u32,
+    window_start: Option<Instant>,
+    count: u32,
+}
+
+impl PduRateLimiter {
+    pub fn new(max_per_second: u32) -> Self {
+        Self {
+            max_per_second,
+            window_start: None,
+            count: 0,
+        }
+    }
+
+    /// Record that we have read a PDU.  Returns true if this PDU used up
+    /// the allowance for the current window.
+    pub fn record(&mut self) -> bool {
+        let now = Instant::now();
+        match self.window_start {
+            Some(start) if now < start + WINDOW => {}
+            _ => {
+                self.window_start = Some(now);
+                self.count = 0;
+            }
+        }
+        self.count += 1;
+        self.count == self.max_per_second
+    }
+
+    /// Wait until we may read another PDU.  Changes nothing, so it is safe
+    /// to cancel.
+    pub async fn wait(&self) {
+        if let Some(start) = self.window_start {
+            if self.count >= self.max_per_second {
+                sleep_until(start + WINDOW).await;
+            }
+        }
+    }
+}
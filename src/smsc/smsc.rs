@@ -1,45 +1,227 @@
+use ascii::AsciiString;
+use futures::FutureExt;
 use log::*;
+use smpp_pdu::pdu::formats::Integer4;
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
 use smpp_pdu::pdu::{
     BindReceiverRespPdu, BindTransceiverRespPdu, BindTransmitterRespPdu,
-    EnquireLinkRespPdu, GenericNackPdu, Pdu, PduBody, PduParseError, PduStatus,
-    SubmitSmPdu, SubmitSmRespPdu,
+    DeliverSmPdu, EnquireLinkPdu, EnquireLinkRespPdu, Pdu, PduBody,
+    PduParseError, PduStatus, SubmitSmPdu, SubmitSmRespPdu, MAX_PDU_LENGTH,
+    MIN_PDU_LENGTH,
 };
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error;
 use std::fmt::{Display, Formatter};
+use std::future::Future;
 use std::io;
-use std::net::SocketAddr;
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::net::TcpListener;
-use tokio::sync::{Mutex, Semaphore, TryAcquireError};
-use tokio::time::sleep;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpStream;
+use tokio::signal;
+use tokio::sync::{
+    watch, Mutex, OwnedSemaphorePermit, Semaphore, TryAcquireError,
+};
+use tokio::task::JoinHandle;
+use tokio::time::{
+    interval_at, sleep, timeout, timeout_at, Instant, Interval,
+    MissedTickBehavior,
+};
+use tokio_rustls::TlsAcceptor;
 
 use crate::async_result::AsyncResult;
 use crate::message_unique_key::MessageUniqueKey;
-use crate::smpp_connection::{EsmeId, SmppConnection};
-use crate::smsc::{SmscConfig, SmscLogic};
+use crate::pdu::command_status::ToCommandStatus;
+use crate::pdu::{
+    command_id, command_status, AlertNotificationPdu, AnyPdu, BindDataExt,
+    DataCoding, EsmClass, ExtraPdu, ExtraPduBody, InterfaceVersion, OutbindPdu,
+    PduExt, BANNER_TLV_TAG,
+};
+use crate::pdu_reader::ReadPduError;
+use crate::session_state::{SessionState, ValidateForSession};
+use crate::smpp_connection::{ConnectionId, EsmeId, SmppConnection};
+use crate::smsc::listener::Listener;
+use crate::smsc::metrics::{MetricsSnapshot, SmscMetrics};
+use crate::smsc::pdu_rate_limiter::PduRateLimiter;
+use crate::smsc::submit_throttle::SubmitThrottle;
+use crate::smsc::tls;
+use crate::smsc::{
+    BindContext, BindError, SmscConfig, SmscLogic, SubmitOutcome, SubmitSmError,
+};
 
 pub fn run<L: SmscLogic + Send + Sync + 'static>(
     config: SmscConfig,
     smsc_logic: L,
 ) -> AsyncResult<()> {
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(async move {
-        let smsc = Smsc::start(config, smsc_logic).await?;
-        loop {
-            if let Err(e) = smsc.lock().await.stopped().await {
-                return Err(e);
+    rt.block_on(run_until(config, smsc_logic, async {
+        if let Err(e) = signal::ctrl_c().await {
+            error!("Unable to listen for Ctrl-C: {}", e);
+            futures::future::pending::<()>().await;
+        }
+    }))
+}
+
+/// Run an SMSC until shutdown_signal completes, and then shut it down
+/// gracefully, or until it is stopped some other way.
+pub async fn run_until<L: SmscLogic + Send + Sync + 'static>(
+    config: SmscConfig,
+    smsc_logic: L,
+    shutdown_signal: impl Future<Output = ()>,
+) -> AsyncResult<()> {
+    let smsc = Smsc::start(config, smsc_logic).await?;
+    let mut stopped = smsc.lock().await.stopped_signal();
+    tokio::select! {
+        _ = shutdown_signal => Smsc::shutdown(&smsc).await,
+        // (We must not hold the borrow this returns across an await)
+        _ = async { stopped.wait_for(|stopped| *stopped).await.is_ok() } => {}
+    }
+    Ok(())
+}
+
+/// How far we have got delivering a deliver_sm to a client
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeliveryStatus {
+    /// Sent (or queued to send, or held until the client binds again), but
+    /// not yet acknowledged
+    Pending,
+    /// The client responded with deliver_sm_resp and ESME_ROK
+    Acknowledged,
+    /// The client responded with this non-zero command_status
+    Rejected(u32),
+    /// We gave up after sending it deliver_sm_max_retries more times
+    /// without a response, or its client did not bind again within
+    /// dr_delivery_deadline
+    Expired,
+}
+
+/// A message a client has submitted, remembered so that we can send its
+/// delivery receipt back to the client that submitted it
+#[derive(Clone, Debug)]
+pub struct PendingMessage {
+    pub key: MessageUniqueKey,
+    /// Who submitted it.  Its DR goes to whichever connection this ESME is
+    /// bound on when the DR arrives, which need not be the same one.
+    pub esme_id: EsmeId,
+    /// The connection it was submitted on
+    pub connection: ConnectionId,
+    /// The sequence_number of the submit_sm
+    pub sequence_number: u32,
+    pub source_addr: String,
+    pub submitted_at: SystemTime,
+}
+
+/// Why receive_pdu could not deliver a PDU to a client
+#[derive(Debug)]
+pub enum ReceivePduError {
+    /// We only deliver deliver_sm and data_sm PDUs
+    UnexpectedPduType(u32),
+    /// The DR does not say which message it is about
+    NoMessageId,
+    /// No client submitted the message the DR is about
+    UnknownMessageId(MessageUniqueKey),
+    /// The client that submitted the message is not connected
+    NoConnection(EsmeId),
+    /// The client's session may not receive DRs, e.g. because it is bound
+    /// as a transmitter
+    WrongBindType {
+        message_unique_key: MessageUniqueKey,
+        connection: ConnectionId,
+        session_state: SessionState,
+    },
+}
+
+impl ToCommandStatus for ReceivePduError {
+    /// The command_status to respond to whoever sent us the PDU with, if
+    /// they expect a deliver_sm_resp
+    fn command_status(&self) -> u32 {
+        let status = match self {
+            ReceivePduError::UnexpectedPduType(_) => PduStatus::ESME_RINVCMDID,
+            ReceivePduError::NoMessageId
+            | ReceivePduError::UnknownMessageId(_) => PduStatus::ESME_RINVMSGID,
+            ReceivePduError::NoConnection(_)
+            | ReceivePduError::WrongBindType { .. } => {
+                PduStatus::ESME_RDELIVERYFAILURE
             }
-            sleep(Duration::from_millis(100)).await;
-            // TODO: notify instead of poll?
+        };
+        status as u32
+    }
+}
+
+impl Display for ReceivePduError {
+    fn fmt(
+        &self,
+        formatter: &mut Formatter,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            ReceivePduError::UnexpectedPduType(command_id) => write!(
+                formatter,
+                "Unexpected PDU type ({}).  Currently we can only handle \
+                deliver_sm and data_sm PDUs.",
+                command_id::command_name(*command_id)
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("{:#010X}", command_id))
+            ),
+            ReceivePduError::NoMessageId => formatter
+                .write_str("Could not extract message ID from supplied PDU."),
+            ReceivePduError::UnknownMessageId(key) => write!(
+                formatter,
+                "No record found of message with namespaceId='{}', \
+                message_id='{}', destination_addr='{}'",
+                key.namespace_id, key.message_id, key.destination_addr
+            ),
+            ReceivePduError::NoConnection(esme_id) => write!(
+                formatter,
+                "No client connection found with system_id='{}' \
+                system_type='{}'.",
+                esme_id.system_id, esme_id.system_type
+            ),
+            ReceivePduError::WrongBindType {
+                message_unique_key,
+                connection,
+                session_state,
+            } => write!(
+                formatter,
+                "Unable to deliver message_id='{}' to connection {} \
+                because it is bound as {}.",
+                message_unique_key.message_id, connection, session_state
+            ),
         }
-    })
+    }
+}
+
+impl error::Error for ReceivePduError {}
+
+/// A DR for a client that was not connected when it arrived, kept until
+/// the client binds again or dr_delivery_deadline passes
+struct HeldDr {
+    pdu: AnyPdu,
+    message_unique_key: MessageUniqueKey,
+    deadline: Instant,
 }
 
 pub struct Smsc {
+    config: SmscConfig,
+    listener: Option<JoinHandle<()>>,
+    stopped: watch::Sender<bool>,
+    /// Every bound connection, so we can unbind them all when we stop
+    live_connections: HashMap<ConnectionId, Arc<SmppConnection>>,
+    /// The connection to send DRs on for each ESME
     connections: HashMap<EsmeId, Arc<SmppConnection>>,
-    messages: HashMap<MessageUniqueKey, EsmeId>,
+    messages: HashMap<MessageUniqueKey, PendingMessage>,
+    /// DRs waiting for each ESME to bind again, oldest first
+    held_drs: HashMap<EsmeId, Vec<HeldDr>>,
+    sequence_numbers: HashMap<AsciiString, u32>,
+    /// How many sessions each system_id has bound
+    binds: HashMap<AsciiString, usize>,
+    /// How many more submit_sm PDUs each system_id may send right now
+    submit_throttles: HashMap<AsciiString, SubmitThrottle>,
+    /// The message each deliver_sm we are waiting for a response to is
+    /// about, by connection and sequence_number
+    unacknowledged: HashMap<(ConnectionId, u32), MessageUniqueKey>,
+    deliveries: HashMap<MessageUniqueKey, DeliveryStatus>,
+    metrics: Arc<SmscMetrics>,
 }
 
 impl Smsc {
@@ -53,86 +235,529 @@ impl Smsc {
     ) -> AsyncResult<Arc<Mutex<Self>>> {
         info!("Starting SMSC");
 
+        if smsc_config.min_pdu_length < MIN_PDU_LENGTH
+            || smsc_config.max_pdu_length > MAX_PDU_LENGTH
+            || smsc_config.min_pdu_length > smsc_config.max_pdu_length
+        {
+            return Err(format!(
+                "PDU length limits {}-{} are not allowed: they must be \
+                within {}-{}.",
+                smsc_config.min_pdu_length,
+                smsc_config.max_pdu_length,
+                MIN_PDU_LENGTH,
+                MAX_PDU_LENGTH
+            )
+            .into());
+        }
+
+        if smsc_config.max_message_payload_len > smsc_config.max_pdu_length {
+            warn!(
+                "max_message_payload_len ({}) is larger than the maximum \
+                PDU length ({}), so PDUs will be rejected for being too \
+                long before we check the size of their message_payload.",
+                smsc_config.max_message_payload_len, smsc_config.max_pdu_length
+            );
+        }
+
         let smsc = Smsc {
+            config: smsc_config.clone(),
+            listener: None,
+            stopped: watch::channel(false).0,
+            live_connections: HashMap::new(),
             connections: HashMap::new(),
             messages: HashMap::new(),
+            held_drs: HashMap::new(),
+            sequence_numbers: HashMap::new(),
+            binds: HashMap::new(),
+            submit_throttles: HashMap::new(),
+            unacknowledged: HashMap::new(),
+            deliveries: HashMap::new(),
+            metrics: Arc::new(SmscMetrics::default()),
         };
         let smsc = Arc::new(Mutex::new(smsc));
 
-        let listener = TcpListener::bind(&smsc_config.bind_address).await?;
-        info!("Bound on {}", &smsc_config.bind_address);
+        let dr_delivery_deadline = smsc_config.dr_delivery_deadline;
+        if !dr_delivery_deadline.is_zero() {
+            tokio::spawn(discard_expired_drs_loop(
+                Arc::downgrade(&smsc),
+                dr_delivery_deadline.min(Duration::from_secs(1)),
+            ));
+        }
+
+        let tls_acceptor = match &smsc_config.tls {
+            Some(tls_config) => Some(tls::load_acceptor(tls_config)?),
+            None => None,
+        };
+
+        let listener = Listener::bind(&smsc_config.bind_address).await?;
+        info!(
+            "Bound on {}{}",
+            &smsc_config.bind_address,
+            if tls_acceptor.is_some() { " (TLS)" } else { "" }
+        );
 
         // Spawn off a task that deals with incoming connections
-        tokio::spawn(listen_loop(
+        let listener = tokio::spawn(listen_loop(
             listener,
+            tls_acceptor,
             Arc::clone(&smsc),
             smsc_config,
             smsc_logic,
         ));
+        smsc.lock().await.listener = Some(listener);
 
         Ok(smsc)
     }
 
-    async fn stopped(&self) -> AsyncResult<()> {
-        // TODO: check whether we are stopped and return an error if so
-        Ok(())
+    /// Stop accepting new connections, and send unbind to every bound
+    /// client before closing its connection.
+    pub async fn stop(&mut self) {
+        info!("Stopping SMSC");
+        if let Some(listener) = self.listener.take() {
+            listener.abort();
+        }
+        self.stopped.send_replace(true);
+
+        for connection in self.live_connections.values() {
+            // We do the IO in a separate task, because when a connection
+            // closes it needs to lock the Smsc, which our caller holds.
+            let connection = Arc::clone(connection);
+            tokio::spawn(async move {
+                let unbind = ExtraPdu::new(
+                    PduStatus::ESME_ROK as u32,
+                    connection.next_sequence_number(),
+                    ExtraPduBody::Unbind,
+                );
+                if let Err(e) = connection.write_extra_pdu(&unbind).await {
+                    warn!("Failed to send unbind to {}: {}", connection, e);
+                }
+                connection.close().await;
+            });
+        }
+    }
+
+    /// Close every connection bound with this system_id, without sending
+    /// unbind first.  Returns the number of connections closed.
+    pub fn disconnect_system_id(&self, system_id: &str) -> usize {
+        let connections: Vec<Arc<SmppConnection>> = self
+            .live_connections
+            .values()
+            .filter(|connection| {
+                connection
+                    .bound_esme_id()
+                    .is_some_and(|esme_id| esme_id.system_id == system_id)
+            })
+            .map(Arc::clone)
+            .collect();
+
+        for connection in &connections {
+            info!(
+                "Disconnecting {} with system_id='{}'",
+                connection, system_id
+            );
+            // As in stop(), closing needs to lock the Smsc, so we do it in
+            // a separate task.
+            let connection = Arc::clone(connection);
+            tokio::spawn(async move { connection.close().await });
+        }
+        connections.len()
+    }
+
+    /// Stop, as in stop(), then wait up to the configured
+    /// shutdown_grace_period for every bound client to disconnect.
+    pub async fn shutdown(smsc: &Arc<Mutex<Smsc>>) {
+        let grace_period = {
+            let mut smsc = smsc.lock().await;
+            smsc.stop().await;
+            smsc.config.shutdown_grace_period
+        };
+        let all_closed = timeout(grace_period, async {
+            while !smsc.lock().await.live_connections.is_empty() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        if all_closed.is_err() {
+            warn!(
+                "{} connections still open after waiting {:?} for them to \
+                close",
+                smsc.lock().await.live_connections.len(),
+                grace_period
+            );
+        }
+        info!("SMSC shut down");
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.stopped.borrow()
+    }
+
+    /// A receiver whose value becomes true when we stop.
+    pub fn stopped_signal(&self) -> watch::Receiver<bool> {
+        self.stopped.subscribe()
     }
 
+    /// Deliver a DR, as a deliver_sm or a data_sm, to the client that
+    /// submitted the message it is about.
     pub async fn receive_pdu(
         &mut self,
         namespace_id: &str,
-        pdu: Pdu,
-    ) -> AsyncResult<()> {
-        // Later: Issue#5: consider retrying after a delay if unable to match DR
+        pdu: impl Into<AnyPdu>,
+    ) -> Result<(), ReceivePduError> {
         // Later: Issue#12: handle MOs
+        let pdu = pdu.into();
         info!("<= receive_pdu() {:?}", pdu);
-        match pdu.body() {
-            PduBody::DeliverSm(body) => {
-                let k =
-                    MessageUniqueKey::from_dr(String::from(namespace_id), body);
-                match k {
-                    Some(message_unique_key) => {
-                        self.receive_pdu_for_message(pdu, message_unique_key)
-                            .await
-                    }
-                    None => {
-                        Err("Could not extract message ID from supplied PDU."
-                            .into())
-                    }
+        let namespace_id = String::from(namespace_id);
+        let k = match &pdu {
+            AnyPdu::Pdu(p) => match p.body() {
+                PduBody::DeliverSm(body) => {
+                    MessageUniqueKey::from_dr(namespace_id, body)
+                }
+                _ => {
+                    return Err(ReceivePduError::UnexpectedPduType(
+                        pdu.command_id(),
+                    ))
+                }
+            },
+            AnyPdu::Extra(p) => match p.body() {
+                ExtraPduBody::DataSm(body) => {
+                    MessageUniqueKey::from_data_sm(namespace_id, body)
+                }
+                _ => {
+                    return Err(ReceivePduError::UnexpectedPduType(
+                        pdu.command_id(),
+                    ))
                 }
+            },
+        };
+        match k {
+            Some(message_unique_key) => {
+                self.receive_pdu_for_message(pdu, message_unique_key).await
             }
-            _ => Err("Unexpected PDU type.  Currently we can only \
-                    handle deliver_sm PDUs."
-                .into()),
+            None => Err(ReceivePduError::NoMessageId),
         }
     }
 
     async fn receive_pdu_for_message(
         &mut self,
-        pdu: Pdu,
+        mut pdu: AnyPdu,
         message_unique_key: MessageUniqueKey,
-    ) -> AsyncResult<()> {
-        let conn = self.connection_for_message(message_unique_key).await?;
-        // Later: Issue#3: in order to support a window size to the client, we
-        // will need to put this PDU into a queue rather than writing
-        // it immediately here.
+    ) -> Result<(), ReceivePduError> {
+        let conn = match self.connection_for_message(&message_unique_key) {
+            Ok(conn) => conn,
+            Err(ReceivePduError::NoConnection(esme_id))
+                if !self.config.dr_delivery_deadline.is_zero() =>
+            {
+                info!(
+                    "Holding DR for message_id='{}' until system_id='{}' \
+                    binds again",
+                    message_unique_key.message_id, esme_id.system_id
+                );
+                self.deliveries.insert(
+                    message_unique_key.clone(),
+                    DeliveryStatus::Pending,
+                );
+                self.held_drs.entry(esme_id).or_default().push(HeldDr {
+                    pdu,
+                    message_unique_key,
+                    deadline: Instant::now() + self.config.dr_delivery_deadline,
+                });
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let session_state = conn.session_state();
+        if !session_state.can_receive() {
+            return Err(ReceivePduError::WrongBindType {
+                message_unique_key,
+                connection: conn.id,
+                session_state,
+            });
+        }
+        if self.config.preserve_sequence_numbers {
+            if let Some(esme_id) = conn.bound_esme_id() {
+                pdu.set_sequence_number(
+                    self.next_sequence_number(&esme_id.system_id),
+                );
+            }
+        }
+        self.unacknowledged.insert(
+            (conn.id, pdu.sequence_number()),
+            message_unique_key.clone(),
+        );
+        // Later: delete old entries in this map to keep size bounded
+        self.deliveries
+            .insert(message_unique_key, DeliveryStatus::Pending);
+        let metrics = self.metrics();
+        // We enqueue the PDU now, so that DRs to the same client keep their
+        // order, and schedule the write here, so we return immediately, and
+        // the IO is done later.  If the client's window is full, the
+        // connection queues it until a deliver_sm_resp frees a space.
+        conn.enqueue_windowed(pdu);
         tokio::spawn(async move {
-            // We schedule the write here, as a sort-of 1-message queue,
-            // so we return immediately, and the IO is done later.
-            conn.write_pdu(&pdu).await.map_err(
-                |e| error!("Failed to send PDU to client: {}", e), // TODO: give information about the client here
+            // The connection counts the deliver_sm as sent when it writes
+            // it, which may be later, when the window has space.
+            if let Err(e) = conn.write_enqueued().await {
+                metrics.deliver_sm_failed();
+                error!("Failed to send PDU to {}: {}", conn, e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Deliver the DRs we held for this connection's ESME while it was not
+    /// connected.  Call once the client has its bind_resp, so that the
+    /// DRs follow it.
+    async fn deliver_held_drs(&mut self, connection: &SmppConnection) {
+        if !connection.session_state().can_receive() {
+            return;
+        }
+        let held = match connection
+            .bound_esme_id()
+            .and_then(|esme_id| self.held_drs.remove(&esme_id))
+        {
+            Some(held) => held,
+            None => return,
+        };
+        for dr in held {
+            if dr.deadline <= Instant::now() {
+                warn_dr_expired(&dr, self.config.dr_delivery_deadline);
+                self.held_dr_expired(dr.message_unique_key);
+                continue;
+            }
+            let message_id = dr.message_unique_key.message_id.clone();
+            if let Err(e) = self
+                .receive_pdu_for_message(dr.pdu, dr.message_unique_key)
+                .await
+            {
+                warn!(
+                    "Failed to deliver held DR for message_id='{}' to {}: {}",
+                    message_id, connection, e
+                );
+            }
+        }
+    }
+
+    /// Drop the held DRs that have waited longer than dr_delivery_deadline
+    /// for their client to bind again
+    fn discard_expired_drs(&mut self) {
+        let now = Instant::now();
+        let deadline = self.config.dr_delivery_deadline;
+        let mut expired_keys = Vec::new();
+        self.held_drs.retain(|_, held| {
+            held.retain(|dr| {
+                let expired = dr.deadline <= now;
+                if expired {
+                    warn_dr_expired(dr, deadline);
+                    expired_keys.push(dr.message_unique_key.clone());
+                }
+                !expired
+            });
+            !held.is_empty()
+        });
+        for message_unique_key in expired_keys {
+            self.held_dr_expired(message_unique_key);
+        }
+    }
+
+    /// Record that we dropped a held DR because its client did not bind
+    /// again in time
+    fn held_dr_expired(&mut self, message_unique_key: MessageUniqueKey) {
+        self.metrics.deliver_sm_failed();
+        self.deliveries
+            .insert(message_unique_key, DeliveryStatus::Expired);
+    }
+
+    /// The current values of the counters describing what we have done
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    fn metrics(&self) -> Arc<SmscMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// How far we have got delivering the deliver_sm about this message,
+    /// or None if we have not tried to deliver one.
+    pub fn delivery_status(
+        &self,
+        message_unique_key: &MessageUniqueKey,
+    ) -> Option<DeliveryStatus> {
+        self.deliveries.get(message_unique_key).copied()
+    }
+
+    /// Record the client's response to a deliver_sm we sent it.  Returns
+    /// false if we were not waiting for this response.
+    fn deliver_sm_resp_received(
+        &mut self,
+        connection_id: ConnectionId,
+        sequence_number: u32,
+        command_status: u32,
+    ) -> bool {
+        match self
+            .unacknowledged
+            .remove(&(connection_id, sequence_number))
+        {
+            Some(message_unique_key) => {
+                let status = if command_status == PduStatus::ESME_ROK as u32 {
+                    self.metrics.deliver_sm_acknowledged();
+                    DeliveryStatus::Acknowledged
+                } else {
+                    self.metrics.deliver_sm_failed();
+                    DeliveryStatus::Rejected(command_status)
+                };
+                self.deliveries.insert(message_unique_key, status);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record that the connection gave up waiting for responses to the
+    /// deliver_sms with these sequence_numbers
+    fn deliver_sm_given_up(
+        &mut self,
+        connection_id: ConnectionId,
+        sequence_numbers: &[u32],
+    ) {
+        for sequence_number in sequence_numbers {
+            if let Some(message_unique_key) = self
+                .unacknowledged
+                .remove(&(connection_id, *sequence_number))
+            {
+                self.metrics.deliver_sm_failed();
+                self.deliveries
+                    .insert(message_unique_key, DeliveryStatus::Expired);
+            }
+        }
+    }
+
+    /// Connect to an ESME listening on addr and send it an outbind, asking
+    /// it to bind to us as a receiver.  Later: read the bind that follows
+    /// and handle the session like one the ESME opened.
+    pub async fn outbind(
+        &self,
+        addr: &str,
+        system_id: &str,
+        password: &str,
+    ) -> AsyncResult<SmppConnection> {
+        let tcp_stream = TcpStream::connect(addr).await?;
+        let socket_addr = tcp_stream.peer_addr()?;
+        let connection = SmppConnection::new(tcp_stream, socket_addr)
+            .with_max_tlvs_per_pdu(self.config.max_tlvs_per_pdu)
+            .with_pdu_length_limits(
+                self.config.min_pdu_length,
+                self.config.max_pdu_length,
             )
+            .with_log_unknown_tlvs(self.config.log_unknown_tlvs);
+        info!("Connection {} - opened for outbind", connection);
+        let pdu = ExtraPdu::new(
+            PduStatus::ESME_ROK as u32,
+            connection.next_sequence_number(),
+            ExtraPduBody::Outbind(OutbindPdu::new(system_id, password)?),
+        );
+        connection.write_extra_pdu(&pdu).await?;
+        Ok(connection)
+    }
+
+    /// Tell the ESME bound with this esme_id that a mobile has become
+    /// available.  It must be bound as a receiver or transceiver.
+    pub async fn send_alert_notification(
+        &mut self,
+        esme_id: &EsmeId,
+        alert_notification: AlertNotificationPdu,
+    ) -> AsyncResult<()> {
+        let conn = match self.connections.get(esme_id) {
+            Some(conn) => Arc::clone(conn),
+            None => {
+                return Err(format!(
+                    "No client connection found with system_id='{}' \
+                    system_type='{}'.",
+                    esme_id.system_id, esme_id.system_type
+                )
+                .into())
+            }
+        };
+        let session_state = conn.session_state();
+        if !session_state.can_receive() {
+            return Err(format!(
+                "Unable to send alert_notification to connection {} \
+                because it is bound as {}.",
+                conn, session_state
+            )
+            .into());
+        }
+        let sequence_number = if self.config.preserve_sequence_numbers {
+            self.next_sequence_number(&esme_id.system_id)
+        } else {
+            conn.next_sequence_number()
+        };
+        let pdu = ExtraPdu::new(
+            PduStatus::ESME_ROK as u32,
+            sequence_number,
+            ExtraPduBody::AlertNotification(alert_notification),
+        );
+        tokio::spawn(async move {
+            conn.write_extra_pdu(&pdu)
+                .await
+                .map_err(|e| error!("Failed to send PDU to {}: {}", conn, e))
         });
         Ok(())
     }
 
+    /// Whether this system_id may submit another message now, under
+    /// max_submits_per_second.  Shared by all its connections.
+    fn allow_submit(&mut self, system_id: &AsciiString) -> bool {
+        match self.config.max_submits_per_second {
+            Some(max_per_second) => self
+                .submit_throttles
+                .entry(system_id.clone())
+                .or_insert_with(|| SubmitThrottle::new(max_per_second))
+                .try_take(),
+            None => true,
+        }
+    }
+
+    /// The sequence_number to use for the next PDU we send to this
+    /// system_id.  We keep track of this here rather than in the
+    /// connection, so that numbering continues where it left off when a
+    /// client reconnects.
+    fn next_sequence_number(&mut self, system_id: &AsciiString) -> u32 {
+        let sequence_number =
+            self.sequence_numbers.entry(system_id.clone()).or_insert(0);
+        // Valid sequence numbers are 0x00000001 to 0x7FFFFFFF, so wrap
+        // around to 1 when we reach the end.
+        *sequence_number = if *sequence_number >= 0x7fffffff {
+            1
+        } else {
+            *sequence_number + 1
+        };
+        *sequence_number
+    }
+
     pub fn add_connection(&mut self, connection: Arc<SmppConnection>) {
         if let Some(esme_id) = connection.bound_esme_id() {
+            info!(
+                "Connection {} bound as {} with system_id='{}', speaking \
+                SMPP {}",
+                connection,
+                connection.session_state(),
+                esme_id.system_id,
+                connection
+                    .interface_version()
+                    .unwrap_or(InterfaceVersion::V34),
+            );
+            let previous = self
+                .live_connections
+                .insert(connection.id, Arc::clone(&connection));
+            if previous.is_none() {
+                *self.binds.entry(esme_id.system_id.clone()).or_insert(0) += 1;
+            }
             self.connections.insert(esme_id, connection);
         } else {
             error!(
                 "Failed to add connection {} because it is not bound!",
-                connection.socket_addr
+                connection
             );
         }
     }
@@ -142,95 +767,247 @@ impl Smsc {
         connection: &Arc<SmppConnection>,
     ) {
         connection.disconnect().await;
+        let was_live = self.live_connections.remove(&connection.id).is_some();
         if let Some(esme_id) = connection.bound_esme_id() {
-            self.connections.remove(&esme_id);
+            if was_live {
+                if let Some(binds) = self.binds.get_mut(&esme_id.system_id) {
+                    *binds -= 1;
+                    if *binds == 0 {
+                        self.binds.remove(&esme_id.system_id);
+                    }
+                }
+            }
+            // Deliver this ESME's DRs on another of its connections if it
+            // has one, but don't forget a newer connection it has bound.
+            if self
+                .connections
+                .get(&esme_id)
+                .is_some_and(|current| current.id == connection.id)
+            {
+                let other = self
+                    .live_connections
+                    .values()
+                    .find(|other| {
+                        other.bound_esme_id().as_ref() == Some(&esme_id)
+                    })
+                    .map(Arc::clone);
+                match other {
+                    Some(other) => self.connections.insert(esme_id, other),
+                    None => self.connections.remove(&esme_id),
+                };
+            }
+        }
+        if !self.config.dr_delivery_deadline.is_zero() {
+            self.redeliver_unacknowledged(connection).await;
         }
+        // No deliver_sm_resp can now arrive for the deliver_sm PDUs we sent
+        // on this connection
+        self.unacknowledged
+            .retain(|(connection_id, _), _| *connection_id != connection.id);
     }
 
-    fn add_message(
-        &mut self,
-        message_unique_key: MessageUniqueKey,
-        esme_id: EsmeId,
-    ) {
-        // Later: Issue#14: delete old entries in this map to keep size bounded
-        self.messages.insert(message_unique_key, esme_id);
+    /// Send the deliver_sm PDUs this closed connection had no response to,
+    /// or had not sent yet, on another connection of the same ESME, or
+    /// hold them until it binds again.
+    async fn redeliver_unacknowledged(&mut self, connection: &SmppConnection) {
+        for pdu in connection.take_unacknowledged() {
+            let message_unique_key = match self
+                .unacknowledged
+                .remove(&(connection.id, pdu.sequence_number()))
+            {
+                Some(message_unique_key) => message_unique_key,
+                None => continue,
+            };
+            let message_id = message_unique_key.message_id.clone();
+            if let Err(e) =
+                self.receive_pdu_for_message(pdu, message_unique_key).await
+            {
+                warn!(
+                    "Failed to deliver DR for message_id='{}' after {} \
+                    closed: {}",
+                    message_id, connection, e
+                );
+            }
+        }
     }
 
-    async fn connection_for_message(
-        &mut self,
-        message_unique_key: MessageUniqueKey,
-    ) -> AsyncResult<Arc<SmppConnection>> {
-        if let Some(esme_id) = self.messages.get(&message_unique_key) {
-            if let Some(connection) = self.connections.get(esme_id) {
-                Ok(Arc::clone(connection))
-            } else {
-                Err(format!(
-                    "No client connection found with \
-                system_id='{}' system_type='{}'.",
-                    esme_id.system_id, esme_id.system_type
-                )
-                .into())
+    /// Whether this system_id may bind another session, under
+    /// max_binds_per_system_id
+    fn may_bind(&self, system_id: &AsciiString) -> bool {
+        match self.config.max_binds_per_system_id {
+            Some(max_binds) => {
+                self.binds.get(system_id).copied().unwrap_or(0) < max_binds
             }
-        } else {
-            Err(format!(
-                "No record found of message with \
-                namespaceId='{}', message_id='{}', destination_addr='{}'",
-                message_unique_key.namespace_id,
-                message_unique_key.message_id,
-                message_unique_key.destination_addr
-            )
-            .into())
+            None => true,
+        }
+    }
+
+    fn add_message(&mut self, pending_message: PendingMessage) {
+        // Later: Issue#14: delete old entries in this map to keep size bounded
+        self.messages
+            .insert(pending_message.key.clone(), pending_message);
+    }
+
+    /// What we know about the submission of the message with this key, if
+    /// a client submitted it to us
+    pub fn pending_message(
+        &self,
+        message_unique_key: &MessageUniqueKey,
+    ) -> Option<&PendingMessage> {
+        self.messages.get(message_unique_key)
+    }
+
+    /// The submitted message that this delivery receipt is about, if we
+    /// can find it
+    pub fn pending_message_for_dr(
+        &self,
+        namespace_id: &str,
+        dr: &DeliverSmPdu,
+    ) -> Option<&PendingMessage> {
+        MessageUniqueKey::from_dr(String::from(namespace_id), dr)
+            .and_then(|key| self.pending_message(&key))
+    }
+
+    fn connection_for_message(
+        &self,
+        message_unique_key: &MessageUniqueKey,
+    ) -> Result<Arc<SmppConnection>, ReceivePduError> {
+        let pending_message =
+            self.messages.get(message_unique_key).ok_or_else(|| {
+                ReceivePduError::UnknownMessageId(message_unique_key.clone())
+            })?;
+        let esme_id = &pending_message.esme_id;
+        self.connections
+            .get(esme_id)
+            .map(Arc::clone)
+            .ok_or_else(|| ReceivePduError::NoConnection(esme_id.clone()))
+    }
+}
+
+fn warn_dr_expired(dr: &HeldDr, deadline: Duration) {
+    warn!(
+        "Dropping DR for message_id='{}' because its client did not bind \
+        again within {:?}",
+        dr.message_unique_key.message_id, deadline
+    );
+}
+
+/// Every period, drop the held DRs that are past their deadline, until the
+/// Smsc goes away.
+async fn discard_expired_drs_loop(smsc: Weak<Mutex<Smsc>>, period: Duration) {
+    let mut timer = interval_at(Instant::now() + period, period);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        timer.tick().await;
+        match smsc.upgrade() {
+            Some(smsc) => smsc.lock().await.discard_expired_drs(),
+            None => return,
         }
     }
 }
 
 /// Listen for clients connecting, and spawn a new task every time one does
 async fn listen_loop<L: SmscLogic + Send + Sync + 'static>(
-    listener: TcpListener,
+    listener: Listener,
+    tls_acceptor: Option<TlsAcceptor>,
     smsc: Arc<Mutex<Smsc>>,
     config: SmscConfig,
     logic: L,
 ) {
     let sem = Arc::new(Semaphore::new(config.max_open_sockets));
     let logic = Arc::new(Mutex::new(logic));
+    let metrics = smsc.lock().await.metrics();
     loop {
         match listener.accept().await {
             Err(e) => {
                 error!("Client connection failed: {}", e);
             }
-            Ok((tcp_stream, socket_addr)) => {
-                tokio::spawn(process_stream(
-                    Arc::clone(&sem),
-                    SmppConnection::new(tcp_stream, socket_addr),
-                    config.clone(),
-                    Arc::clone(&logic),
-                    Arc::clone(&smsc),
-                ));
+            Ok((stream, socket_addr)) => {
+                // Take the permit before the TLS handshake, so that clients
+                // which never finish it still count towards
+                // max_open_sockets.
+                let permit = Arc::clone(&sem).try_acquire_owned();
+                let tls_acceptor = tls_acceptor.clone();
+                let config = config.clone();
+                let logic = Arc::clone(&logic);
+                let smsc = Arc::clone(&smsc);
+                let metrics = Arc::clone(&metrics);
+                // The TLS handshake happens in the new task, so a slow
+                // client does not hold up the others connecting.
+                tokio::spawn(async move {
+                    let connection = match tls_acceptor {
+                        Some(tls_acceptor) => {
+                            match timeout(
+                                config.tls_handshake_timeout,
+                                tls_acceptor.accept(stream),
+                            )
+                            .await
+                            {
+                                Ok(Ok(tls_stream)) => {
+                                    SmppConnection::new(tls_stream, socket_addr)
+                                }
+                                Ok(Err(e)) => {
+                                    error!(
+                                        "TLS handshake with {} failed: {}",
+                                        socket_addr, e
+                                    );
+                                    return;
+                                }
+                                Err(_) => {
+                                    error!(
+                                        "TLS handshake with {} timed out",
+                                        socket_addr
+                                    );
+                                    return;
+                                }
+                            }
+                        }
+                        None => SmppConnection::new(stream, socket_addr),
+                    };
+                    process_stream(
+                        permit,
+                        connection
+                            .with_max_tlvs_per_pdu(config.max_tlvs_per_pdu)
+                            .with_pdu_length_limits(
+                                config.min_pdu_length,
+                                config.max_pdu_length,
+                            )
+                            .with_log_unknown_tlvs(config.log_unknown_tlvs)
+                            .with_window_size(config.window_size)
+                            .with_metrics(metrics),
+                        config,
+                        logic,
+                        smsc,
+                    )
+                    .await
+                });
             }
         }
     }
 }
 
 async fn process_stream<L: SmscLogic + Send + Sync + 'static>(
-    sem: Arc<Semaphore>,
+    permit: Result<OwnedSemaphorePermit, TryAcquireError>,
     connection: SmppConnection,
     config: SmscConfig,
     logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
 ) {
-    let socket_addr = connection.socket_addr.clone();
-    let aqu = sem.try_acquire();
-    match aqu {
-        Ok(_guard) => {
-            info!("Connection {} - opened", socket_addr);
+    let tag = connection.to_string();
+    match permit {
+        Ok(_permit) => {
+            info!("Connection {} - opened", tag);
+            let metrics = smsc.lock().await.metrics();
+            metrics.connection_opened();
             let result = process(connection, config, logic, smsc).await;
-            log_result(result, socket_addr);
+            metrics.connection_closed();
+            log_result(result, &tag);
         }
         Err(TryAcquireError::NoPermits) => {
-            error!(
-                "Refused connection {} - too many open sockets",
-                connection.socket_addr
-            );
+            error!("Refused connection {} - too many open sockets", tag);
+            if let Some(wait) = config.overload_bind_wait {
+                refuse_bind(&connection, wait).await;
+            }
         }
         Err(TryAcquireError::Closed) => {
             error!("Unexpected error: semaphore closed");
@@ -238,17 +1015,38 @@ async fn process_stream<L: SmscLogic + Send + Sync + 'static>(
     }
 }
 
-fn log_result(closed_by_us: Result<bool, ProcessError>, addr: SocketAddr) {
+/// Wait up to the supplied time for a client we have no room for to send
+/// its bind, and reject it with ESME_RBINDFAIL, so that it knows why we
+/// are closing the connection.
+async fn refuse_bind(connection: &SmppConnection, wait: Duration) {
+    if let Ok(Ok(Some(AnyPdu::Pdu(pdu)))) =
+        timeout(wait, connection.read_pdu()).await
+    {
+        if matches!(
+            pdu.body(),
+            PduBody::BindReceiver(_)
+                | PduBody::BindTransceiver(_)
+                | PduBody::BindTransmitter(_)
+        ) {
+            let resp = error_response(&pdu, PduStatus::ESME_RBINDFAIL);
+            if let Err(e) = connection.write_pdu(&resp).await {
+                warn!("Failed to refuse bind from {}: {}", connection, e);
+            }
+        }
+    }
+    connection.close().await;
+}
+
+fn log_result(closed_by_us: Result<bool, ProcessError>, tag: &str) {
     match closed_by_us {
         Ok(true) => {
-            info!("Connection {} - closed by us", addr)
+            info!("Connection {} - closed by us", tag)
+        }
+        Ok(false) => {
+            info!("Connection {} - closed since client closed the socket", tag)
         }
-        Ok(false) => info!(
-            "Connection {} - closed since client closed the socket",
-            addr
-        ),
         Err(e) => {
-            error!("Connection {} - closed due to error: {}", addr, e)
+            error!("Connection {} - closed due to error: {}", tag, e)
         }
     }
 }
@@ -259,13 +1057,41 @@ struct UnexpectedPduType {
     sequence_number: u32,
 }
 
+#[derive(Debug)]
+struct SessionStateViolation {
+    command_id: u32,
+    state: SessionState,
+    sequence_number: u32,
+}
+
+impl SessionStateViolation {
+    /// The command_status to reject the offending PDU with
+    fn command_status(&self) -> PduStatus {
+        let is_bind = matches!(
+            self.command_id,
+            command_id::BIND_RECEIVER
+                | command_id::BIND_TRANSMITTER
+                | command_id::BIND_TRANSCEIVER
+        );
+        if is_bind && self.state.is_bound() {
+            PduStatus::ESME_RALYBND
+        } else {
+            PduStatus::ESME_RINVBNDSTS
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ProcessError {
     PduParseError(PduParseError),
+    /// A PDU with a valid command_length, but a body we could not parse
+    BadPduBody(PduParseError),
     UnexpectedPduType(UnexpectedPduType),
+    SessionStateViolation(SessionStateViolation),
     ConnectionNotBoundAsTransmitter,
     IoError(io::Error),
     InternalError(String),
+    LogicPanicked,
 }
 
 impl ProcessError {
@@ -276,6 +1102,44 @@ impl ProcessError {
         })
     }
 
+    fn new_session_state_violation(
+        command_id: u32,
+        state: SessionState,
+        sequence_number: u32,
+    ) -> Self {
+        ProcessError::SessionStateViolation(SessionStateViolation {
+            command_id,
+            state,
+            sequence_number,
+        })
+    }
+
+    /// The command_status to reject a PDU with when handling it failed
+    /// with this error.  Parse errors carry their own status, so are
+    /// handled separately, in handle_pdu_parse_error.
+    fn command_status(&self) -> PduStatus {
+        match self {
+            ProcessError::SessionStateViolation(e) => e.command_status(),
+            ProcessError::UnexpectedPduType(_) => PduStatus::ESME_RINVCMDID,
+            _ => PduStatus::ESME_RSYSERR,
+        }
+    }
+
+    /// Whether we must drop the connection after this error.  Errors
+    /// reading or writing the stream are fatal, because after them we
+    /// can't be sure where the next PDU starts, and so is a panic in the
+    /// SmscLogic, because we can't trust the state it left behind.
+    /// Anything else, including a PDU whose body we could not parse, was a
+    /// problem with one PDU, which we reject before carrying on.
+    fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            ProcessError::PduParseError(_)
+                | ProcessError::IoError(_)
+                | ProcessError::LogicPanicked
+        )
+    }
+
     fn new_internal_error(message: &str) -> Self {
         ProcessError::InternalError(String::from(message))
     }
@@ -291,6 +1155,15 @@ impl From<PduParseError> for ProcessError {
     }
 }
 
+impl From<ReadPduError> for ProcessError {
+    fn from(read_pdu_error: ReadPduError) -> Self {
+        match read_pdu_error {
+            ReadPduError::BadBody(e) => ProcessError::BadPduBody(e),
+            ReadPduError::Framing(e) => ProcessError::PduParseError(e),
+        }
+    }
+}
+
 impl From<io::Error> for ProcessError {
     fn from(io_error: io::Error) -> Self {
         ProcessError::IoError(io_error)
@@ -303,12 +1176,24 @@ impl Display for ProcessError {
         formatter: &mut Formatter,
     ) -> std::result::Result<(), std::fmt::Error> {
         let s = match self {
-            ProcessError::PduParseError(e) => e.to_string(),
+            ProcessError::PduParseError(e) | ProcessError::BadPduBody(e) => {
+                e.to_string()
+            }
             ProcessError::UnexpectedPduType(e) => {
                 format!(
                     "Unexpected PDU type \
+                    (command_id={:#010X}, sequence_number={:#010X}), \
+                    rejected with {}",
+                    e.command_id,
+                    e.sequence_number,
+                    status_name(self.command_status() as u32)
+                )
+            }
+            ProcessError::SessionStateViolation(e) => {
+                format!(
+                    "PDU not allowed in session state {} \
                     (command_id={:#010X}, sequence_number={:#010X})",
-                    e.command_id, e.sequence_number
+                    e.state, e.command_id, e.sequence_number
                 )
             }
             ProcessError::ConnectionNotBoundAsTransmitter => String::from(
@@ -317,6 +1202,9 @@ impl Display for ProcessError {
             ),
             ProcessError::IoError(e) => e.to_string(),
             ProcessError::InternalError(s) => String::from(s),
+            ProcessError::LogicPanicked => {
+                String::from("Panicked while handling PDU")
+            }
         };
         formatter.write_str(&s)
     }
@@ -324,7 +1212,16 @@ impl Display for ProcessError {
 
 impl error::Error for ProcessError {}
 
-async fn process<L: SmscLogic>(
+/// The symbolic name of command_status for logging, e.g. "ESME_RINVCMDID",
+/// or its value in hex if the spec does not name it.
+fn status_name(command_status: u32) -> String {
+    match command_status::status_name(command_status) {
+        Some(name) => String::from(name),
+        None => format!("command_status={:#010X}", command_status),
+    }
+}
+
+async fn process<L: SmscLogic + Send>(
     connection: SmppConnection,
     config: SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
@@ -364,247 +1261,920 @@ async fn process<L: SmscLogic>(
     .await
 }
 
-async fn process_loop<L: SmscLogic>(
+async fn process_loop<L: SmscLogic + Send>(
     connection: Arc<SmppConnection>,
     config: SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
 ) -> Result<bool, ProcessError> {
+    let mut enquire_link_timer = config.enquire_link_interval.map(|period| {
+        let mut timer = interval_at(Instant::now() + period, period);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        timer
+    });
+    let mut resend_timer = config.deliver_sm_resp_timeout.map(|period| {
+        let mut timer = interval_at(Instant::now() + period, period);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        timer
+    });
+    let mut rate_limiter = config.max_pdus_per_second.map(PduRateLimiter::new);
+    let mut last_received = Instant::now();
+
     loop {
-        let pdu = connection.read_pdu().await;
-        match pdu {
-            Ok(pdu) => {
-                if let Some(pdu) = pdu {
-                    let sequence_number = pdu.sequence_number.value;
-                    match handle_pdu(
-                        pdu,
-                        Arc::clone(&connection),
-                        &config,
-                        Arc::clone(&smsc_logic),
-                        Arc::clone(&smsc),
+        let pdu = tokio::select! {
+            pdu = async {
+                if let Some(rate_limiter) = &rate_limiter {
+                    rate_limiter.wait().await;
+                }
+                match config.idle_timeout {
+                    Some(idle_timeout) => timeout_at(
+                        last_received + idle_timeout,
+                        connection.read_pdu(),
                     )
-                    .await
+                    .await,
+                    None => Ok(connection.read_pdu().await),
+                }
+            } => match pdu {
+                Ok(pdu) => pdu,
+                Err(_) => {
+                    warn!(
+                        "Connection {} - closing because we received \
+                        nothing for {:?}",
+                        connection,
+                        config.idle_timeout.unwrap_or_default()
+                    );
+                    return Ok(true);
+                }
+            },
+            _ = connection.closed() => return Ok(true),
+            _ = tick(&mut enquire_link_timer) => {
+                if !keep_alive(&connection, &config).await? {
+                    return Ok(true);
+                }
+                continue;
+            }
+            _ = tick(&mut resend_timer) => {
+                if let Some(timeout) = config.deliver_sm_resp_timeout {
+                    let given_up = connection
+                        .resend_overdue(timeout, config.deliver_sm_max_retries)
+                        .await?;
+                    if !given_up.is_empty() {
+                        smsc.lock()
+                            .await
+                            .deliver_sm_given_up(connection.id, &given_up);
+                    }
+                }
+                continue;
+            }
+        };
+        match pdu {
+            Ok(Some(pdu)) => {
+                last_received = Instant::now();
+                if let Some(rate_limiter) = &mut rate_limiter {
+                    if rate_limiter.record() {
+                        warn!(
+                            "Connection {} - sent {} PDUs within a \
+                            second.  Pausing reading.",
+                            connection,
+                            config.max_pdus_per_second.unwrap_or(0),
+                        );
+                    }
+                }
+                let pdu = match pdu {
+                    AnyPdu::Pdu(pdu) => match connection.response_received(pdu)
                     {
-                        Ok(response) => connection.write_pdu(&response).await?,
-                        Err(e) => {
-                            // Couldn't handle this PDU type.  Send a nack...
-                            connection
-                                .write_pdu(
-                                    &Pdu::new(
-                                        PduStatus::ESME_RINVCMDID as u32,
-                                        sequence_number,
-                                        GenericNackPdu::new_error().into(),
-                                    )
-                                    .unwrap(),
-                                )
-                                .await?;
-                            // ...and Drop the connection.
+                        Some(pdu) => AnyPdu::Pdu(pdu),
+                        // A write_request() was waiting for this response
+                        None => continue,
+                    },
+                    pdu => pdu,
+                };
+                let command_id = pdu.command_id();
+                let sequence_number = pdu.sequence_number();
+                // Catch panics in the SmscLogic, so the client gets a
+                // response before we drop the connection.
+                let response = AssertUnwindSafe(async {
+                    match pdu {
+                        AnyPdu::Pdu(pdu) => handle_pdu(
+                            pdu,
+                            Arc::clone(&connection),
+                            &config,
+                            Arc::clone(&smsc_logic),
+                            Arc::clone(&smsc),
+                        )
+                        .await
+                        .map(|response| response.map(AnyPdu::Pdu)),
+                        AnyPdu::Extra(pdu) => {
+                            handle_extra_pdu(
+                                pdu,
+                                Arc::clone(&connection),
+                                Arc::clone(&smsc_logic),
+                                Arc::clone(&smsc),
+                            )
+                            .await
+                        }
+                    }
+                })
+                .catch_unwind()
+                .await;
+                let response = match response {
+                    Ok(response) => response,
+                    Err(_) => {
+                        error!(
+                            "Connection {} - panicked while handling {} \
+                            with sequence_number={:#010X}",
+                            connection,
+                            command_id::command_name(command_id)
+                                .unwrap_or("PDU"),
+                            sequence_number
+                        );
+                        Err(ProcessError::LogicPanicked)
+                    }
+                };
+                match response {
+                    Ok(Some(AnyPdu::Pdu(response))) => {
+                        connection.write_pdu(&response).await?
+                    }
+                    Ok(Some(AnyPdu::Extra(response))) => {
+                        connection.write_extra_pdu(&response).await?
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        // Couldn't handle this PDU.  Reject it...
+                        connection
+                            .write_pdu(&error_response_for(
+                                Some(command_id),
+                                e.command_status() as u32,
+                                sequence_number,
+                            ))
+                            .await?;
+                        // ...and drop the connection only if we must.
+                        if e.is_fatal() {
                             return Err(e);
                         }
+                        warn!("Connection {} - {}", connection, e);
                     }
-                } else {
-                    // Client closed the connection
-                    return Ok(false);
+                }
+                if connection.session_state() == SessionState::Unbound {
+                    // The client unbound, and we have responded
+                    return Ok(true);
                 }
             }
-            Err(pdu_parse_error) => {
+            // Client closed the connection
+            Ok(None) => return Ok(false),
+            Err(read_pdu_error) => {
                 // Respond with an error
-                let response = handle_pdu_parse_error(&pdu_parse_error);
+                let response =
+                    handle_pdu_parse_error(read_pdu_error.parse_error());
                 connection.write_pdu(&response).await?;
 
-                // Then return the error, so we drop the connection
-                return Err(pdu_parse_error.into());
+                // Then drop the connection, unless the reader has skipped
+                // past the bad PDU and can read the next one
+                let e = ProcessError::from(read_pdu_error);
+                if e.is_fatal() {
+                    return Err(e);
+                }
+                warn!("Connection {} - {}", connection, e);
             }
         }
     }
 }
 
+/// Wait for the next tick of the timer, or forever if there is no timer.
+async fn tick(timer: &mut Option<Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => futures::future::pending().await,
+    }
+}
+
+/// Send an enquire_link if we have sent nothing to this client for a whole
+/// enquire_link_interval.  Returns false if too many of our enquire_links
+/// have gone unanswered, meaning we should close the connection.
+async fn keep_alive(
+    connection: &SmppConnection,
+    config: &SmscConfig,
+) -> Result<bool, ProcessError> {
+    let unanswered = connection.unanswered_enquire_links();
+    if unanswered >= config.enquire_link_max_unanswered {
+        warn!(
+            "Connection {} - closing because {} enquire_links went \
+            unanswered",
+            connection, unanswered
+        );
+        return Ok(false);
+    }
+
+    if let Some(interval) = config.enquire_link_interval {
+        if connection.idle_for() >= interval {
+            let sequence_number = connection.next_sequence_number();
+            connection.enquire_link_sent(sequence_number);
+            connection
+                .write_pdu(&Pdu::new(
+                    PduStatus::ESME_ROK as u32,
+                    sequence_number,
+                    EnquireLinkPdu::new().into(),
+                )?)
+                .await?;
+        }
+    }
+    Ok(true)
+}
+
 fn handle_pdu_parse_error(error: &PduParseError) -> Pdu {
-    let sequence_number = error.sequence_number.unwrap_or(1);
-    match error.command_id {
-        Some(0x00000002) => Pdu::new(
-            error.status(),
-            sequence_number,
-            BindTransmitterRespPdu::new_error().into(),
-        )
-        .unwrap(),
+    error_response_for(
+        error.command_id,
+        error.command_status(),
+        error.sequence_number.unwrap_or(1),
+    )
+}
+
+/// Build the response rejecting a PDU that we only know the header of:
+/// the matching *_resp if we know it, or generic_nack.
+fn error_response_for(
+    command_id: Option<u32>,
+    command_status: u32,
+    sequence_number: u32,
+) -> Pdu {
+    let body: PduBody = match command_id {
+        Some(command_id::BIND_RECEIVER) => {
+            BindReceiverRespPdu::new_error().into()
+        }
+        Some(command_id::BIND_TRANSMITTER) => {
+            BindTransmitterRespPdu::new_error().into()
+        }
+        Some(command_id::SUBMIT_SM) => SubmitSmRespPdu::new_error().into(),
+        Some(command_id::BIND_TRANSCEIVER) => {
+            BindTransceiverRespPdu::new_error().into()
+        }
         // For any PDU type we're not set up for, send generic_nack
-        Some(_) => Pdu::new(
-            error.status(),
-            sequence_number,
-            GenericNackPdu::new_error().into(),
-        )
-        .unwrap(),
+        Some(_) => return Pdu::generic_nack(command_status, sequence_number),
         // If we don't even know the PDU type, send generic_nack
-        None => Pdu::new(
-            error.status(),
-            sequence_number,
-            GenericNackPdu::new_error().into(),
+        None => return Pdu::generic_nack(command_status, sequence_number),
+    };
+    let mut pdu = Pdu::new(command_status, 1, body).unwrap();
+    // Echo the sequence_number we were sent, even if it is not valid
+    pdu.sequence_number = Integer4::new(sequence_number);
+    pdu
+}
+
+/// Check that the client may send us this PDU in its current session state
+fn check_session_state(
+    pdu: &Pdu,
+    state: SessionState,
+) -> Result<(), ProcessError> {
+    pdu.validate_for_session(&state).map_err(|_| {
+        ProcessError::new_session_state_violation(
+            pdu.command_id().value,
+            state,
+            pdu.sequence_number.value,
         )
-        .unwrap(),
-    }
+    })
 }
 
-async fn handle_bind_pdu<L: SmscLogic>(
+/// Build the response to a request that we are rejecting with the supplied
+/// command_status: the matching *_resp if we know it, or generic_nack.
+fn error_response(pdu: &Pdu, command_status: PduStatus) -> Pdu {
+    let body: PduBody = match pdu.body() {
+        PduBody::BindReceiver(_) => BindReceiverRespPdu::new_error().into(),
+        PduBody::BindTransceiver(_) => {
+            BindTransceiverRespPdu::new_error().into()
+        }
+        PduBody::BindTransmitter(_) => {
+            BindTransmitterRespPdu::new_error().into()
+        }
+        PduBody::SubmitSm(_) => SubmitSmRespPdu::new_error().into(),
+        _ => {
+            return Pdu::generic_nack(
+                command_status as u32,
+                pdu.sequence_number.value,
+            )
+        }
+    };
+    Pdu::new(command_status as u32, pdu.sequence_number.value, body).unwrap()
+}
+
+async fn handle_bind_pdu<L: SmscLogic + Send>(
     pdu: Pdu,
     connection: Arc<SmppConnection>,
     config: &SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
-) -> Result<Pdu, ProcessError> {
-    let mut command_status = PduStatus::ESME_ROK;
-
-    let (bind_data, ret_body) = match pdu.body() {
+) -> Result<Option<Pdu>, ProcessError> {
+    let (bind_data, session_state) = match pdu.body() {
         PduBody::BindReceiver(body) => {
-            let mut logic = smsc_logic.lock().await;
-            Ok((
-                body.bind_data(),
-                match logic.bind(body.bind_data()).await {
-                    Ok(()) => BindReceiverRespPdu::new(&config.system_id)
-                        .unwrap()
-                        .into(),
-                    Err(e) => {
-                        command_status = e.into();
-                        BindReceiverRespPdu::new_error().into()
-                    }
-                },
-            ))
+            (body.bind_data(), SessionState::BoundRx)
         }
         PduBody::BindTransceiver(body) => {
-            let mut logic = smsc_logic.lock().await;
-            Ok((
-                body.bind_data(),
-                match logic.bind(body.bind_data()).await {
-                    Ok(()) => BindTransceiverRespPdu::new(&config.system_id)
-                        .unwrap()
-                        .into(),
-                    Err(e) => {
-                        command_status = e.into();
-                        BindTransceiverRespPdu::new_error().into()
-                    }
-                },
-            ))
+            (body.bind_data(), SessionState::BoundTrx)
         }
         PduBody::BindTransmitter(body) => {
-            let mut logic = smsc_logic.lock().await;
-            Ok((
-                body.bind_data(),
-                match logic.bind(body.bind_data()).await {
-                    Ok(()) => BindTransmitterRespPdu::new(&config.system_id)
-                        .unwrap()
-                        .into(),
-                    Err(e) => {
-                        command_status = e.into();
-                        BindTransmitterRespPdu::new_error().into()
-                    }
-                },
-            ))
+            (body.bind_data(), SessionState::BoundTx)
         }
         // This function should only be called with a Bind PDU
-        _ => Err(ProcessError::new_internal_error(
-            "handle_bind_pdu called with non-bind PDU!",
-        )),
-    }?;
+        _ => {
+            return Err(ProcessError::new_internal_error(
+                "handle_bind_pdu called with non-bind PDU!",
+            ))
+        }
+    };
+
+    let context = BindContext {
+        bind_data,
+        session_state,
+        peer_addr: &connection.socket_addr,
+    };
+    let mut result = smsc_logic.lock().await.bind_with_context(&context).await;
+
+    if let Some(delay) = config.bind_resp_delay {
+        sleep(delay).await;
+    }
 
     // If we successfully bound, register this connection so we
-    // know to use it when we receive deliver_sm PDUs later
-    if command_status == PduStatus::ESME_ROK {
-        connection
-            .bind(
-                bind_data.system_id.value.clone(),
-                bind_data.system_type.value.clone(),
-            )
-            .await;
-        // TODO: we only need to know about this connection if it can transmit,
-        // right?
-        smsc.lock().await.add_connection(connection);
+    // know to use it when we receive deliver_sm PDUs later.  We check
+    // max_binds_per_system_id while holding the lock, so that two binds
+    // at once can't both take the last place.
+    if result.is_ok() {
+        let mut smsc = smsc.lock().await;
+        if smsc.may_bind(&bind_data.system_id.value) {
+            connection
+                .bind(
+                    bind_data.system_id.value.clone(),
+                    bind_data.system_type.value.clone(),
+                    session_state,
+                    bind_data.interface_version().negotiate(),
+                )
+                .await;
+            // We register transmitter binds too, but refuse to deliver
+            // messages to them in receive_pdu_for_message.
+            smsc.metrics.bound();
+            smsc.add_connection(Arc::clone(&connection));
+        } else {
+            warn!(
+                "Connection {} - refusing bind because system_id='{}' \
+                already has {} sessions bound",
+                connection,
+                bind_data.system_id.value,
+                config.max_binds_per_system_id.unwrap_or(0)
+            );
+            result = Err(BindError::NotAllowed);
+        }
     }
 
-    Pdu::new(command_status as u32, pdu.sequence_number.value, ret_body)
-        .map_err(|e| e.into())
+    let command_status = match result {
+        Ok(()) => PduStatus::ESME_ROK as u32,
+        Err(e) => e.command_status(),
+    };
+    let ret_body: PduBody = match (session_state, result) {
+        (SessionState::BoundRx, Ok(())) => {
+            BindReceiverRespPdu::new(&config.system_id).unwrap().into()
+        }
+        (SessionState::BoundRx, Err(_)) => {
+            BindReceiverRespPdu::new_error().into()
+        }
+        (SessionState::BoundTrx, Ok(())) => {
+            BindTransceiverRespPdu::new(&config.system_id)
+                .unwrap()
+                .into()
+        }
+        (SessionState::BoundTrx, Err(_)) => {
+            BindTransceiverRespPdu::new_error().into()
+        }
+        (_, Ok(())) => BindTransmitterRespPdu::new(&config.system_id)
+            .unwrap()
+            .into(),
+        (_, Err(_)) => BindTransmitterRespPdu::new_error().into(),
+    };
+
+    let response =
+        Pdu::new(command_status, pdu.sequence_number.value, ret_body)?;
+    if command_status == PduStatus::ESME_ROK as u32 {
+        // Send the bind_resp before any DRs we held while the client was
+        // not connected
+        connection.write_pdu(&response).await?;
+        smsc.lock().await.deliver_held_drs(&connection).await;
+        return Ok(None);
+    }
+    Ok(Some(response))
 }
 
-async fn handle_submit_sm_pdu<L: SmscLogic>(
+/// Checks on a submit_sm that we make before passing it to the SmscLogic.
+/// If it fails, returns the command_status we should respond with.
+fn validate_submit_sm(
+    body: &SubmitSmPdu,
+    esme_id: &EsmeId,
+    config: &SmscConfig,
+) -> Result<(), u32> {
+    if EsmClass::for_submit_sm(body.0.esm_class.value).is_err() {
+        return Err(PduStatus::ESME_RINVESMCLASS as u32);
+    }
+    if let Some(allowed) =
+        config.allowed_source_addrs_for(esme_id.system_id.as_str())
+    {
+        if !allowed.contains(&body.source_addr().as_str()) {
+            return Err(PduStatus::ESME_RINVSRCADR as u32);
+        }
+    }
+    if !config.allowed_protocol_ids.is_empty()
+        && !config
+            .allowed_protocol_ids
+            .contains(&body.0.protocol_id.value)
+    {
+        // SMPP has no status specific to protocol_id
+        return Err(PduStatus::ESME_RSUBMITFAIL as u32);
+    }
+    if let Some(message_payload) = body.0.tlvs.get(KnownTlvTag::message_payload)
+    {
+        if message_payload.value.len() > config.max_message_payload_len {
+            return Err(PduStatus::ESME_RINVMSGLEN as u32);
+        }
+    }
+    if config.validate_data_coding {
+        // We pass through data_codings we don't understand, e.g. binary
+        if let Ok(data_coding) = DataCoding::try_from(body.0.data_coding.value)
+        {
+            if data_coding.validate(&body.0.short_message.value).is_err() {
+                return Err(command_status::ESME_RINVDCS);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_submit_sm_pdu<L: SmscLogic + Send>(
     body: &SubmitSmPdu,
     sequence_number: u32,
     connection: Arc<SmppConnection>,
+    config: &SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
-) -> Result<Pdu, ProcessError> {
-    // Later: Issue#15: only do this if bound as a receiver or transceiver -
-    // find out using connection.bound_esme_id
-
+) -> Result<Option<Pdu>, ProcessError> {
     if let Some(esme_id) = connection.bound_esme_id() {
-        let mut command_status = PduStatus::ESME_ROK;
-        let resp = match smsc_logic
+        let metrics = smsc.lock().await.metrics();
+        metrics.submit_sm_received();
+
+        let rejection = match validate_submit_sm(body, &esme_id, config) {
+            Err(command_status) => Some(command_status),
+            Ok(())
+                if connection.deferred_request_in_flight(sequence_number) =>
+            {
+                warn!(
+                    "Connection {} - rejecting submit_sm with \
+                    sequence_number={:#010X}, because we have not yet \
+                    responded to an earlier one with the same number",
+                    connection, sequence_number
+                );
+                Some(config.duplicate_sequence_number_status)
+            }
+            Ok(()) if !smsc.lock().await.allow_submit(&esme_id.system_id) => {
+                Some(PduStatus::ESME_RTHROTTLED as u32)
+            }
+            Ok(()) => None,
+        };
+        if let Some(command_status) = rejection {
+            metrics.submit_sm_responded(false);
+            return Pdu::new(
+                command_status,
+                sequence_number,
+                SubmitSmRespPdu::new_error().into(),
+            )
+            .map(Some)
+            .map_err(|e| e.into());
+        }
+
+        let submitted = Submitted {
+            esme_id,
+            connection: connection.id,
+            sequence_number,
+            source_addr: body.source_addr(),
+            submitted_at: SystemTime::now(),
+        };
+        let outcome = smsc_logic
             .lock()
             .await
-            .submit_sm(smsc.clone(), body, sequence_number)
-            .await
-        {
-            Ok((resp, message_unique_key)) => {
-                smsc.lock().await.add_message(message_unique_key, esme_id);
-                resp
+            .submit_sm_deferrable(smsc.clone(), body, sequence_number)
+            .await;
+        match outcome {
+            SubmitOutcome::Done(result) => {
+                submit_sm_resp(result, submitted, &smsc).await.map(Some)
             }
-            Err(e) => {
-                command_status = e.into();
-                SubmitSmRespPdu::new_error().into()
+            SubmitOutcome::Deferred(future) => {
+                // Carry on processing other PDUs, and respond when the
+                // logic has finished with this one.
+                connection.deferred_request_started(sequence_number);
+                tokio::spawn(async move {
+                    // We catch panics here for the same reason as in
+                    // process_loop: the client must still get a response.
+                    let resp =
+                        match AssertUnwindSafe(future).catch_unwind().await {
+                            Ok(result) => {
+                                submit_sm_resp(result, submitted, &smsc).await
+                            }
+                            Err(_) => {
+                                error!(
+                                    "Connection {} - panicked while handling \
+                                    deferred submit_sm with \
+                                    sequence_number={:#010X}",
+                                    connection, sequence_number
+                                );
+                                Ok(error_response_for(
+                                    Some(command_id::SUBMIT_SM),
+                                    PduStatus::ESME_RSYSERR as u32,
+                                    sequence_number,
+                                ))
+                            }
+                        };
+                    let written = match resp {
+                        Ok(resp) => connection
+                            .write_pdu(&resp)
+                            .await
+                            .map_err(ProcessError::from),
+                        Err(e) => Err(e),
+                    };
+                    connection.deferred_request_finished(sequence_number);
+                    if let Err(e) = written {
+                        error!(
+                            "Connection {} - failed to send deferred \
+                            submit_sm_resp with sequence_number={:#010X}: {}",
+                            connection, sequence_number, e
+                        );
+                    }
+                });
+                Ok(None)
             }
-        };
-        Pdu::new(command_status as u32, sequence_number, resp.into())
-            .map_err(|e| e.into())
+        }
     } else {
-        // Later: Issue#15: check this is not a receiver
         Err(ProcessError::new_connection_not_bound_as_transmitter())
     }
 }
 
-async fn handle_pdu<L: SmscLogic>(
+/// What we know about a submit_sm before the logic has given it a
+/// MessageUniqueKey
+struct Submitted {
+    esme_id: EsmeId,
+    connection: ConnectionId,
+    sequence_number: u32,
+    source_addr: String,
+    submitted_at: SystemTime,
+}
+
+/// Build the response to a submit_sm from what the logic made of it,
+/// remembering who sent the message if it was accepted.
+async fn submit_sm_resp(
+    result: Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>,
+    submitted: Submitted,
+    smsc: &Mutex<Smsc>,
+) -> Result<Pdu, ProcessError> {
+    let sequence_number = submitted.sequence_number;
+    let mut smsc = smsc.lock().await;
+    smsc.metrics.submit_sm_responded(result.is_ok());
+    let (command_status, resp) = match result {
+        Ok((resp, key)) => {
+            smsc.add_message(PendingMessage {
+                key,
+                esme_id: submitted.esme_id,
+                connection: submitted.connection,
+                sequence_number,
+                source_addr: submitted.source_addr,
+                submitted_at: submitted.submitted_at,
+            });
+            (PduStatus::ESME_ROK as u32, resp)
+        }
+        Err(e) => (e.command_status(), SubmitSmRespPdu::new_error()),
+    };
+    Pdu::new(command_status, sequence_number, resp.into()).map_err(|e| e.into())
+}
+
+async fn handle_pdu<L: SmscLogic + Send>(
     pdu: Pdu,
     connection: Arc<SmppConnection>,
     config: &SmscConfig,
     smsc_logic: Arc<Mutex<L>>,
     smsc: Arc<Mutex<Smsc>>,
-) -> Result<Pdu, ProcessError> {
-    info!("<= {} {:?}", connection.socket_addr, pdu);
+) -> Result<Option<Pdu>, ProcessError> {
+    info!("<= {} {:?}", connection, pdu);
+    if let Err(e) = check_session_state(&pdu, connection.session_state()) {
+        warn!("{} {}", connection, e);
+        return Ok(Some(error_response(&pdu, e.command_status())));
+    }
+
     let sequence_number = pdu.sequence_number.value;
     match pdu.body() {
         PduBody::BindReceiver(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
         PduBody::BindTransmitter(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
         PduBody::BindTransceiver(_body) => {
-            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc)
-                .await
-                .map_err(|e| e.into())
+            handle_bind_pdu(pdu, connection, config, smsc_logic, smsc).await
         }
 
-        PduBody::EnquireLink(_body) => Pdu::new(
-            PduStatus::ESME_ROK as u32,
-            pdu.sequence_number.value,
-            EnquireLinkRespPdu::new().into(),
-        )
-        .map_err(|e| e.into()),
+        PduBody::EnquireLink(_body) => {
+            if let Some(delay) = config.enquire_link_resp_delay {
+                sleep(delay).await;
+            }
+            if let Some(banner) = &config.banner {
+                // smpp-pdu can't write TLVs in enquire_link_resp, so we
+                // write it ourselves, as an ExtraPdu.
+                let tlv = Tlv::new_unknown(BANNER_TLV_TAG, banner.as_bytes());
+                connection
+                    .write_extra_pdu(&ExtraPdu::new(
+                        PduStatus::ESME_ROK as u32,
+                        sequence_number,
+                        ExtraPduBody::EnquireLinkResp(Tlvs::from(&[tlv])),
+                    ))
+                    .await?;
+                return Ok(None);
+            }
+            Pdu::new(
+                PduStatus::ESME_ROK as u32,
+                pdu.sequence_number.value,
+                EnquireLinkRespPdu::new().into(),
+            )
+            .map(Some)
+            .map_err(|e| e.into())
+        }
+
+        PduBody::EnquireLinkResp(_body) => {
+            if !connection.enquire_link_resp_received(sequence_number) {
+                warn!(
+                    "Connection {} - enquire_link_resp with \
+                    sequence_number={:#010X} does not match any \
+                    enquire_link we sent",
+                    connection, sequence_number
+                );
+            }
+            // This is a response, so we don't respond to it
+            Ok(None)
+        }
 
         PduBody::SubmitSm(body) => {
             handle_submit_sm_pdu(
                 body,
                 sequence_number,
                 connection,
+                config,
                 smsc_logic,
                 smsc,
             )
             .await
         }
+        PduBody::DeliverSm(_) => {
+            decline(&connection, pdu.command_id().value, sequence_number);
+            // smpp-pdu has no deliver_sm_resp, so we write our own
+            connection
+                .write_extra_pdu(&ExtraPdu::new(
+                    PduStatus::ESME_RINVCMDID as u32,
+                    sequence_number,
+                    ExtraPduBody::DeliverSmResp(None),
+                ))
+                .await?;
+            Ok(None)
+        }
         _ => Err(ProcessError::new_unexpected_pdu_type(
             pdu.command_id().value,
             pdu.sequence_number.value,
         )),
     }
 }
+
+/// Log that we are rejecting a request we recognise but do not handle,
+/// e.g. a deliver_sm sent to us by an ESME.  Unlike an unexpected PDU type,
+/// this gets its own *_resp and leaves the session open.
+fn decline(connection: &SmppConnection, command_id: u32, sequence_number: u32) {
+    warn!(
+        "Connection {} - declining {} with sequence_number={:#010X}: \
+        rejected with {}",
+        connection,
+        command_id::command_name(command_id).unwrap_or("PDU"),
+        sequence_number,
+        status_name(PduStatus::ESME_RINVCMDID as u32)
+    );
+}
+
+/// Handle one of the PDUs that smpp-pdu does not support, so we parse
+/// ourselves.
+async fn handle_extra_pdu<L: SmscLogic + Send>(
+    pdu: ExtraPdu,
+    connection: Arc<SmppConnection>,
+    smsc_logic: Arc<Mutex<L>>,
+    smsc: Arc<Mutex<Smsc>>,
+) -> Result<Option<AnyPdu>, ProcessError> {
+    info!("<= {} {:?}", connection, pdu);
+    let sequence_number = pdu.sequence_number.value;
+    let state = connection.session_state();
+    if let Err(command_status) = pdu.validate_for_session(&state) {
+        warn!(
+            "{} {}",
+            connection,
+            ProcessError::new_session_state_violation(
+                pdu.command_id().value,
+                state,
+                sequence_number,
+            )
+        );
+        return Ok(Some(extra_error_response(&pdu, command_status)));
+    }
+
+    match pdu.body() {
+        ExtraPduBody::CancelSm(body) => {
+            let command_status = match smsc_logic
+                .lock()
+                .await
+                .cancel_sm(Arc::clone(&smsc), body)
+                .await
+            {
+                Ok(()) => PduStatus::ESME_ROK as u32,
+                Err(e) => e.command_status(),
+            };
+            Ok(Some(AnyPdu::Extra(ExtraPdu::new(
+                command_status,
+                sequence_number,
+                ExtraPduBody::CancelSmResp,
+            ))))
+        }
+        ExtraPduBody::QuerySm(body) => {
+            let (command_status, resp) = match smsc_logic
+                .lock()
+                .await
+                .query_sm(Arc::clone(&smsc), body)
+                .await
+            {
+                Ok(resp) => (PduStatus::ESME_ROK as u32, Some(resp)),
+                Err(e) => (e.command_status(), None),
+            };
+            Ok(Some(AnyPdu::Extra(ExtraPdu::new(
+                command_status,
+                sequence_number,
+                ExtraPduBody::QuerySmResp(resp),
+            ))))
+        }
+        ExtraPduBody::Unbind => {
+            connection.unbind();
+            Ok(Some(AnyPdu::Extra(ExtraPdu::new(
+                PduStatus::ESME_ROK as u32,
+                sequence_number,
+                ExtraPduBody::UnbindResp,
+            ))))
+        }
+        // The response to an unbind we sent while stopping
+        ExtraPduBody::UnbindResp => Ok(None),
+        ExtraPduBody::DeliverSmResp(_) | ExtraPduBody::DataSmResp(_) => {
+            let known = smsc.lock().await.deliver_sm_resp_received(
+                connection.id,
+                sequence_number,
+                pdu.command_status.value,
+            );
+            if !connection
+                .windowed_response_received(sequence_number)
+                .await?
+                && !known
+            {
+                warn!(
+                    "Connection {} - {} with sequence_number={:#010X} does \
+                    not match any DR we sent",
+                    connection,
+                    command_id::command_name(pdu.command_id().value)
+                        .unwrap_or("response"),
+                    sequence_number
+                );
+            }
+            // This is a response, so we don't respond to it
+            Ok(None)
+        }
+        // Later: pass submit_multi and data_sm to the SmscLogic
+        ExtraPduBody::SubmitMulti(_) | ExtraPduBody::DataSm(_) => {
+            decline(&connection, pdu.command_id().value, sequence_number);
+            Ok(Some(extra_error_response(&pdu, PduStatus::ESME_RINVCMDID)))
+        }
+        ExtraPduBody::AlertNotification(_)
+        | ExtraPduBody::Outbind(_)
+        | ExtraPduBody::CancelSmResp
+        | ExtraPduBody::EnquireLinkResp(_)
+        | ExtraPduBody::QuerySmResp(_)
+        | ExtraPduBody::SubmitMultiResp(_) => {
+            Err(ProcessError::new_unexpected_pdu_type(
+                pdu.command_id().value,
+                sequence_number,
+            ))
+        }
+    }
+}
+
+/// Like error_response, for the PDUs we parse ourselves.
+fn extra_error_response(pdu: &ExtraPdu, command_status: PduStatus) -> AnyPdu {
+    let body = match pdu.body() {
+        ExtraPduBody::CancelSm(_) => ExtraPduBody::CancelSmResp,
+        ExtraPduBody::QuerySm(_) => ExtraPduBody::QuerySmResp(None),
+        ExtraPduBody::SubmitMulti(_) => ExtraPduBody::SubmitMultiResp(None),
+        ExtraPduBody::DataSm(_) => ExtraPduBody::DataSmResp(None),
+        ExtraPduBody::Unbind => ExtraPduBody::UnbindResp,
+        _ => {
+            return AnyPdu::Pdu(Pdu::generic_nack(
+                command_status as u32,
+                pdu.sequence_number.value,
+            ))
+        }
+    };
+    AnyPdu::Extra(ExtraPdu::new(
+        command_status as u32,
+        pdu.sequence_number.value,
+        body,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smpp_pdu::pdu::PduParseErrorBody;
+
+    fn parse(bytes: &[u8]) -> Pdu {
+        Pdu::parse(&mut io::BufReader::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn submit_sm_before_bind_is_a_session_state_violation() {
+        let submit_sm = parse(
+            b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi",
+        );
+
+        let err =
+            check_session_state(&submit_sm, SessionState::Open).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "PDU not allowed in session state open \
+            (command_id=0x00000004, sequence_number=0x00000003)"
+        );
+        assert!(err.command_status() == PduStatus::ESME_RINVBNDSTS);
+        match err {
+            ProcessError::SessionStateViolation(e) => {
+                assert_eq!(e.command_id, 0x00000004);
+                assert_eq!(e.state, SessionState::Open);
+                assert_eq!(e.sequence_number, 3);
+            }
+            _ => panic!("Expected SessionStateViolation, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn bind_when_bound_is_rejected_as_already_bound() {
+        let bind_receiver = parse(
+            b"\x00\x00\x00\x29\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x08\
+            esmeid\0password\0type\0\x34\x00\x00\0",
+        );
+
+        match check_session_state(&bind_receiver, SessionState::BoundTx) {
+            Err(ProcessError::SessionStateViolation(e)) => {
+                assert!(e.command_status() == PduStatus::ESME_RALYBND);
+            }
+            other => panic!("Expected SessionStateViolation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn only_stream_errors_and_panics_are_fatal() {
+        assert!(ProcessError::from(io::Error::from(
+            io::ErrorKind::UnexpectedEof
+        ))
+        .is_fatal());
+        assert!(
+            !ProcessError::new_unexpected_pdu_type(0x80000004, 7).is_fatal()
+        );
+        assert!(
+            !ProcessError::new_connection_not_bound_as_transmitter().is_fatal()
+        );
+        assert!(!ProcessError::new_internal_error("oops").is_fatal());
+        assert!(ProcessError::LogicPanicked.is_fatal());
+        assert!(ProcessError::from(ReadPduError::Framing(PduParseError::new(
+            PduParseErrorBody::NotEnoughBytes
+        )))
+        .is_fatal());
+        assert!(!ProcessError::from(ReadPduError::BadBody(
+            PduParseError::new(PduParseErrorBody::NotEnoughBytes)
+        ))
+        .is_fatal());
+    }
+
+    #[test]
+    fn unexpected_pdu_type_names_the_status_it_was_rejected_with() {
+        let err = ProcessError::new_unexpected_pdu_type(0x80000004, 7);
+        assert_eq!(
+            err.to_string(),
+            "Unexpected PDU type \
+            (command_id=0x80000004, sequence_number=0x00000007), \
+            rejected with ESME_RINVCMDID"
+        );
+    }
+
+    #[test]
+    fn unnamed_statuses_are_shown_in_hex() {
+        assert_eq!(status_name(0x00000003), "ESME_RINVCMDID");
+        assert_eq!(status_name(0x00000400), "command_status=0x00000400");
+    }
+}
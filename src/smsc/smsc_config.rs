@@ -1,18 +1,304 @@
 use clap::Parser;
+use smpp_pdu::pdu::{PduStatus, MAX_PDU_LENGTH, MIN_PDU_LENGTH};
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Short Message Service Center (SMSC) in Rust
 #[derive(Parser, Clone, Debug)]
 #[clap(name = "smsc")]
 pub struct SmscConfig {
-    /// Address to bind on
+    /// Address to bind on: host:port, or unix:path to listen on a Unix
+    /// domain socket
     #[clap(short, long, default_value = "0.0.0.0:8080", env = "BIND_ADDRESS")]
     pub bind_address: String,
 
+    /// If provided, accept only TLS (smpps) connections, using the PEM
+    /// certificate chain and private key files given, separated by a comma
+    /// (e.g. "cert.pem,key.pem")
+    #[clap(long, value_parser = parse_tls_config, env = "TLS")]
+    pub tls: Option<TlsConfig>,
+
+    /// With --tls, close the connection of a client that has not finished
+    /// the TLS handshake after this long
+    #[clap(
+        long,
+        default_value = "10s",
+        value_parser = humantime::parse_duration,
+        env = "TLS_HANDSHAKE_TIMEOUT"
+    )]
+    pub tls_handshake_timeout: Duration,
+
     /// Maximum number of sockets that can be open
     #[clap(short, long, default_value = "100", env = "MAX_OPEN_SOCKETS")]
     pub max_open_sockets: usize,
 
+    /// If provided, when max_open_sockets is reached, wait this long for a
+    /// new client's bind and reject it with ESME_RBINDFAIL, instead of
+    /// closing the connection straight away (e.g. "1s")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "OVERLOAD_BIND_WAIT"
+    )]
+    pub overload_bind_wait: Option<Duration>,
+
+    /// If provided, reject binds with ESME_RBINDFAIL from a system_id that
+    /// already has this many sessions bound
+    #[clap(long, env = "MAX_BINDS_PER_SYSTEM_ID")]
+    pub max_binds_per_system_id: Option<usize>,
+
     /// system_id used as an identifier of the SMSC
     #[clap(short, long, default_value = "rust_smpp", env = "SYSTEM_ID")]
     pub system_id: String,
+
+    /// Number the PDUs we send to each system_id (e.g. deliver_sm) from a
+    /// counter that continues across reconnects, instead of using the
+    /// sequence_number supplied with the PDU
+    #[clap(long, env = "PRESERVE_SEQUENCE_NUMBERS")]
+    pub preserve_sequence_numbers: bool,
+
+    /// If provided, send an enquire_link to each client when we have sent
+    /// it nothing for this long (e.g. "30s")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "ENQUIRE_LINK_INTERVAL"
+    )]
+    pub enquire_link_interval: Option<Duration>,
+
+    /// Close the connection if this many enquire_links we sent in a row go
+    /// unanswered
+    #[clap(long, default_value = "3", env = "ENQUIRE_LINK_MAX_UNANSWERED")]
+    pub enquire_link_max_unanswered: usize,
+
+    /// If provided, close the connection to a client that has sent us
+    /// nothing for this long (e.g. "5m")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "IDLE_TIMEOUT"
+    )]
+    pub idle_timeout: Option<Duration>,
+
+    /// If provided, send each client at most this many deliver_sm PDUs
+    /// that it has not yet responded to, and queue the rest
+    #[clap(long, env = "WINDOW_SIZE")]
+    pub window_size: Option<usize>,
+
+    /// If provided, send a deliver_sm again when the client has not
+    /// responded to it within this long (e.g. "10s")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "DELIVER_SM_RESP_TIMEOUT"
+    )]
+    pub deliver_sm_resp_timeout: Option<Duration>,
+
+    /// Give up on a deliver_sm after sending it again this many times
+    /// without a response
+    #[clap(long, default_value = "3", env = "DELIVER_SM_MAX_RETRIES")]
+    pub deliver_sm_max_retries: usize,
+
+    /// If provided, respond ESME_RTHROTTLED to submit_sm PDUs beyond this
+    /// many per second from each system_id, across all its connections
+    #[clap(long, env = "MAX_SUBMITS_PER_SECOND")]
+    pub max_submits_per_second: Option<u32>,
+
+    /// The command_status to reject a submit_sm with when an earlier one
+    /// with the same sequence_number is still waiting for its (deferred)
+    /// response, since the client could not tell the responses apart.  By
+    /// default, ESME_RINVCMDLEN (2).
+    #[clap(
+        long,
+        default_value = "2",
+        env = "DUPLICATE_SEQUENCE_NUMBER_STATUS"
+    )]
+    pub duplicate_sequence_number_status: u32,
+
+    /// When a DR arrives for a client that is not connected, keep it for
+    /// this long, and deliver it if the client binds again in that time
+    /// (e.g. "1m").  After that, drop it and log a warning.  By default,
+    /// such DRs are refused straight away.
+    #[clap(
+        long,
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+        env = "DR_DELIVERY_DEADLINE"
+    )]
+    pub dr_delivery_deadline: Duration,
+
+    /// Reject submit_sm PDUs whose message_payload TLV is longer than this
+    /// many bytes
+    #[clap(long, default_value = "65535", env = "MAX_MESSAGE_PAYLOAD_LEN")]
+    pub max_message_payload_len: usize,
+
+    /// Reject submit_sm PDUs whose short_message is not valid in the
+    /// data_coding they declare (e.g. GSM 7-bit or UCS2), instead of passing
+    /// them through
+    #[clap(long, env = "VALIDATE_DATA_CODING")]
+    pub validate_data_coding: bool,
+
+    /// Reject submit_sm from a system_id unless its source_addr is one of
+    /// those listed for it, e.g. "esme1=12345,67890".  May be repeated (or
+    /// separated with ";").  system_ids not listed may use any source_addr.
+    #[clap(
+        long,
+        value_parser = parse_allowed_source_addrs,
+        value_delimiter = ';',
+        env = "ALLOWED_SOURCE_ADDRS"
+    )]
+    pub allowed_source_addrs: Vec<(String, Vec<String>)>,
+
+    /// If provided, reject submit_sm whose protocol_id is not one of these,
+    /// separated with "," (e.g. "0,64")
+    #[clap(long, value_delimiter = ',', env = "ALLOWED_PROTOCOL_IDS")]
+    pub allowed_protocol_ids: Vec<u8>,
+
+    /// Reject PDUs whose command_length is shorter than this.  May not be
+    /// less than 8 (MIN_PDU_LENGTH).
+    #[clap(long, default_value = "8", env = "MIN_PDU_LENGTH")]
+    pub min_pdu_length: usize,
+
+    /// Reject PDUs whose command_length is longer than this, before reading
+    /// the rest of them.  May not be more than 70000 (MAX_PDU_LENGTH).
+    #[clap(long, default_value = "70000", env = "MAX_PDU_LENGTH")]
+    pub max_pdu_length: usize,
+
+    /// Reject PDUs containing more than this many TLVs
+    #[clap(long, default_value = "64", env = "MAX_TLVS_PER_PDU")]
+    pub max_tlvs_per_pdu: usize,
+
+    /// Log (at debug level) the tag and length of each TLV we don't
+    /// recognise in received submit_sm and deliver_sm PDUs.  These TLVs are
+    /// kept, so they are passed on with the rest of the PDU.
+    #[clap(long, env = "LOG_UNKNOWN_TLVS")]
+    pub log_unknown_tlvs: bool,
+
+    /// If provided, identify ourselves with this string (e.g. a build
+    /// version) in a vendor-specific TLV (0x1400) in each enquire_link_resp
+    #[clap(long, env = "BANNER")]
+    pub banner: Option<String>,
+
+    /// When shutting down, wait this long for clients to close their
+    /// connections after we send them unbind
+    #[clap(
+        long,
+        default_value = "5s",
+        value_parser = humantime::parse_duration,
+        env = "SHUTDOWN_GRACE_PERIOD"
+    )]
+    pub shutdown_grace_period: Duration,
+
+    /// For testing clients only: wait this long before responding to each
+    /// enquire_link (e.g. "5s")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "ENQUIRE_LINK_RESP_DELAY"
+    )]
+    pub enquire_link_resp_delay: Option<Duration>,
+
+    /// For testing clients only: wait this long before responding to each
+    /// bind (e.g. "5s")
+    #[clap(
+        long,
+        value_parser = humantime::parse_duration,
+        env = "BIND_RESP_DELAY"
+    )]
+    pub bind_resp_delay: Option<Duration>,
+
+    /// If provided, stop reading from a client that sends more than this
+    /// many PDUs of any type in a second, until the second is up
+    #[clap(long, env = "MAX_PDUS_PER_SECOND")]
+    pub max_pdus_per_second: Option<u32>,
+}
+
+/// Where to find the certificate and key to accept TLS connections with
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// PEM file containing our certificate, followed by any intermediate
+    /// certificates
+    pub cert_path: PathBuf,
+    /// PEM file containing the certificate's private key
+    pub key_path: PathBuf,
+}
+
+impl Default for SmscConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: String::from("0.0.0.0:8080"),
+            tls: None,
+            tls_handshake_timeout: Duration::from_secs(10),
+            max_open_sockets: 100,
+            overload_bind_wait: None,
+            max_binds_per_system_id: None,
+            system_id: String::from("rust_smpp"),
+            preserve_sequence_numbers: false,
+            enquire_link_interval: None,
+            enquire_link_max_unanswered: 3,
+            idle_timeout: None,
+            window_size: None,
+            deliver_sm_resp_timeout: None,
+            deliver_sm_max_retries: 3,
+            max_submits_per_second: None,
+            duplicate_sequence_number_status: PduStatus::ESME_RINVCMDLEN as u32,
+            dr_delivery_deadline: Duration::ZERO,
+            max_message_payload_len: 65535,
+            validate_data_coding: false,
+            allowed_source_addrs: Vec::new(),
+            allowed_protocol_ids: Vec::new(),
+            min_pdu_length: MIN_PDU_LENGTH,
+            max_pdu_length: MAX_PDU_LENGTH,
+            max_tlvs_per_pdu: 64,
+            log_unknown_tlvs: false,
+            banner: None,
+            shutdown_grace_period: Duration::from_secs(5),
+            enquire_link_resp_delay: None,
+            bind_resp_delay: None,
+            max_pdus_per_second: None,
+        }
+    }
+}
+
+impl SmscConfig {
+    /// The source_addrs this system_id may use in submit_sm, or None if it
+    /// may use any.
+    pub fn allowed_source_addrs_for(
+        &self,
+        system_id: &str,
+    ) -> Option<Vec<&str>> {
+        let mut entries = self
+            .allowed_source_addrs
+            .iter()
+            .filter(|(id, _)| id == system_id)
+            .peekable();
+        entries.peek()?;
+        Some(
+            entries
+                .flat_map(|(_, addrs)| addrs.iter().map(String::as_str))
+                .collect(),
+        )
+    }
+}
+
+fn parse_allowed_source_addrs(
+    s: &str,
+) -> Result<(String, Vec<String>), String> {
+    let (system_id, addrs) = s.split_once('=').ok_or_else(|| {
+        format!("Expected system_id=source_addr,..., but got \"{}\"", s)
+    })?;
+    Ok((
+        String::from(system_id),
+        addrs.split(',').map(String::from).collect(),
+    ))
+}
+
+fn parse_tls_config(s: &str) -> Result<TlsConfig, String> {
+    let (cert_path, key_path) = s.split_once(',').ok_or_else(|| {
+        format!("Expected cert_path,key_path, but got \"{}\"", s)
+    })?;
+    Ok(TlsConfig {
+        cert_path: PathBuf::from(cert_path),
+        key_path: PathBuf::from(key_path),
+    })
 }
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use futures::future::BoxFuture;
 use smpp_pdu::pdu::data::bind_data::BindData;
 use smpp_pdu::pdu::PduStatus;
 use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
@@ -6,10 +7,30 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::message_unique_key::MessageUniqueKey;
+use crate::pdu::command_status::ToCommandStatus;
+use crate::pdu::{CancelSmPdu, QuerySmPdu, QuerySmRespPdu};
+use crate::session_state::SessionState;
+use crate::smpp_connection::PeerAddr;
 use crate::smsc::Smsc;
 
+/// What SmscLogic::bind_with_context() is told about a bind
+pub struct BindContext<'a> {
+    pub bind_data: &'a BindData,
+    /// The state the connection will be in if the bind succeeds, which
+    /// says whether the ESME is binding as a transmitter, receiver or
+    /// transceiver
+    pub session_state: SessionState,
+    /// Where the ESME connected from
+    pub peer_addr: &'a PeerAddr,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum BindError {
     IncorrectPassword,
+    /// The ESME's interface_version is not one we support
+    IncorrectInterfaceVersion,
+    /// The ESME may not bind, e.g. from the address it connected from
+    NotAllowed,
     InternalError,
 }
 
@@ -17,11 +38,20 @@ impl From<BindError> for PduStatus {
     fn from(e: BindError) -> PduStatus {
         match e {
             BindError::IncorrectPassword => PduStatus::ESME_RINVPASWD,
+            BindError::IncorrectInterfaceVersion => PduStatus::ESME_RBINDFAIL,
+            BindError::NotAllowed => PduStatus::ESME_RBINDFAIL,
             BindError::InternalError => PduStatus::ESME_RSYSERR,
         }
     }
 }
 
+impl ToCommandStatus for BindError {
+    fn command_status(&self) -> u32 {
+        PduStatus::from(*self) as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum SubmitSmError {
     InternalError,
 }
@@ -34,13 +64,129 @@ impl From<SubmitSmError> for PduStatus {
     }
 }
 
+impl ToCommandStatus for SubmitSmError {
+    fn command_status(&self) -> u32 {
+        PduStatus::from(*self) as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum QuerySmError {
+    /// We don't know the message, or can't report its state
+    QueryFailed,
+    InternalError,
+}
+
+impl From<QuerySmError> for PduStatus {
+    fn from(e: QuerySmError) -> PduStatus {
+        match e {
+            QuerySmError::QueryFailed => PduStatus::ESME_RQUERYFAIL,
+            QuerySmError::InternalError => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
+impl ToCommandStatus for QuerySmError {
+    fn command_status(&self) -> u32 {
+        PduStatus::from(*self) as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum CancelSmError {
+    /// We don't know the message, or it can no longer be cancelled
+    CancelFailed,
+    InternalError,
+}
+
+impl From<CancelSmError> for PduStatus {
+    fn from(e: CancelSmError) -> PduStatus {
+        match e {
+            CancelSmError::CancelFailed => PduStatus::ESME_RCANCELFAIL,
+            CancelSmError::InternalError => PduStatus::ESME_RSYSERR,
+        }
+    }
+}
+
+impl ToCommandStatus for CancelSmError {
+    fn command_status(&self) -> u32 {
+        PduStatus::from(*self) as u32
+    }
+}
+
+/// What became of a submit_sm passed to SmscLogic::submit_sm_deferrable().
+pub enum SubmitOutcome {
+    /// Respond straight away
+    Done(Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>),
+    /// Respond when this completes (e.g. after a database write), while
+    /// carrying on processing other PDUs from the client
+    Deferred(
+        BoxFuture<
+            'static,
+            Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>,
+        >,
+    ),
+}
+
 #[async_trait]
 pub trait SmscLogic {
     async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError>;
+
+    /// Like bind, but also told where the ESME connected from and how it
+    /// is binding, e.g. to allow binds only from known IP addresses.  By
+    /// default, calls bind.
+    async fn bind_with_context(
+        &mut self,
+        context: &BindContext<'_>,
+    ) -> Result<(), BindError>
+    where
+        Self: Send,
+    {
+        self.bind(context.bind_data).await
+    }
+
+    /// Accept or reject a message.  If the message is accepted and will
+    /// later get a DR (via Smsc::receive_pdu), check
+    /// RegisteredDelivery::wants_delivery_receipt_for() first: clients
+    /// that did not ask for a DR may not expect one.
     async fn submit_sm(
         &mut self,
         smsc: Arc<Mutex<Smsc>>,
         pdu: &SubmitSmPdu,
         sequence_number: u32,
     ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>;
+
+    /// Like submit_sm, but may return SubmitOutcome::Deferred to accept
+    /// the message now and respond later.  By default, calls submit_sm.
+    async fn submit_sm_deferrable(
+        &mut self,
+        smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        sequence_number: u32,
+    ) -> SubmitOutcome
+    where
+        Self: Send,
+    {
+        SubmitOutcome::Done(self.submit_sm(smsc, pdu, sequence_number).await)
+    }
+
+    /// Report the state of a message we accepted earlier.  By default,
+    /// every query fails.
+    async fn query_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, QuerySmError> {
+        Err(QuerySmError::QueryFailed)
+    }
+
+    /// Cancel a message we accepted earlier but have not yet delivered.
+    /// By default, every cancellation fails.
+    async fn cancel_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &CancelSmPdu,
+    ) -> Result<(), CancelSmError> {
+        Err(CancelSmError::CancelFailed)
+    }
 }
@@ -0,0 +1,38 @@
+use tokio::time::Instant;
+
+/// A token bucket limiting how many submit_sm PDUs one system_id may send
+/// per second, across all its connections.  It holds up to a second's
+/// worth of tokens, so a client that has been quiet may send a burst.
+pub struct SubmitThrottle {
+    max_per_second: u32,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl SubmitThrottle {
+    pub fn new(max_per_second: u32) -> Self {
+        Self {
+            max_per_second,
+            tokens: f64::from(max_per_second),
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token if there is one.  Returns false if the client has used
+    /// up its allowance, so this submit_sm should be throttled.
+    pub fn try_take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill);
+        self.last_refill = now;
+
+        let capacity = f64::from(self.max_per_second);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * capacity).min(capacity);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use rustls_pemfile::{certs, private_key};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::sync::Arc;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+use crate::smsc::TlsConfig;
+
+/// Load the certificate and key named in the config, ready to accept TLS
+/// connections.
+pub fn load_acceptor(config: &TlsConfig) -> io::Result<TlsAcceptor> {
+    let certs = certs(&mut BufReader::new(File::open(&config.cert_path)?))
+        .collect::<io::Result<Vec<_>>>()?;
+    let key = private_key(&mut BufReader::new(File::open(&config.key_path)?))?
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("No private key found in {:?}", config.key_path),
+            )
+        })?;
+    let server_config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
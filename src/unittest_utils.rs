@@ -1,6 +1,9 @@
 #![cfg(test)]
+#![allow(dead_code)]
 
+use log::{LevelFilter, Log, Metadata, Record};
 use std::io;
+use std::sync::{Mutex, Once};
 
 pub struct FailingRead {}
 
@@ -15,3 +18,37 @@ impl io::Read for FailingRead {
         Err(FailingRead::error())
     }
 }
+
+static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Remembers every log line, so tests can check what was logged
+pub struct CapturingLogger;
+
+impl CapturingLogger {
+    /// Start capturing
+    pub fn install() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CapturingLogger)
+                .expect("Another logger was installed first");
+            log::set_max_level(LevelFilter::Trace);
+        });
+    }
+
+    /// Every line logged so far
+    pub fn lines() -> Vec<String> {
+        LOGS.lock().unwrap().clone()
+    }
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOGS.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
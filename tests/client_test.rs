@@ -0,0 +1,70 @@
+use smpp::client::{ClientError, SmppClient};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{PduStatus, SubmitEsmClass, SubmitSmPdu};
+
+mod test_utils;
+
+use test_utils::{AcceptAll, TestServer};
+
+#[tokio::test]
+async fn client_can_bind_and_submit() {
+    let server = TestServer::start_with_logic(AcceptAll {}).await.unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+    let resp = client.submit_sm(submit_sm()).await.unwrap();
+
+    assert_eq!(resp.message_id().as_deref(), Some("mymessage"));
+}
+
+#[tokio::test]
+async fn client_reports_rejected_submit_sm_as_error_status() {
+    // DefaultLogic fails every submit_sm with ESME_RSYSERR
+    let server = TestServer::start().await.unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+
+    client.bind_transceiver("esmeid", "password").await.unwrap();
+    let err = client.submit_sm(submit_sm()).await.unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ClientError>(),
+        Some(ClientError::ErrorStatus(s)) if *s == PduStatus::ESME_RSYSERR as u32
+    ));
+}
+
+#[tokio::test]
+async fn client_reports_failed_bind_as_error_status() {
+    let server = TestServer::start_with_logic(AcceptAll {}).await.unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+
+    let err = client.bind_receiver("esmeid", "wrong").await.unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<ClientError>(),
+        Some(ClientError::ErrorStatus(s)) if *s == PduStatus::ESME_RINVPASWD as u32
+    ));
+}
+
+fn submit_sm() -> SubmitSmPdu {
+    SubmitSmPdu::new(
+        "",
+        0,
+        0,
+        "447000123123",
+        0,
+        0,
+        "447111222222",
+        SubmitEsmClass::Default as u8,
+        0x34,
+        1,
+        "",
+        "",
+        1,
+        0,
+        3,
+        0,
+        b"hihi",
+        Tlvs::new(),
+    )
+    .unwrap()
+}
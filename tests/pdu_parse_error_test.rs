@@ -0,0 +1,179 @@
+//! The messages and command_statuses of parse errors are seen by clients,
+//! so these tests pin them down for every kind of error.
+
+use smpp::pdu::command_status::ToCommandStatus;
+use smpp_pdu::pdu::{
+    OctetStringCreationError, PduParseError, PduParseErrorBody, PduStatus,
+};
+use std::io;
+
+fn error(body: PduParseErrorBody) -> PduParseError {
+    PduParseError::new(body)
+        .into_with_header(Some(0x04), Some(0x08), Some(0x07))
+        .into_with_field_name("short_message")
+}
+
+fn assert_error(
+    body: PduParseErrorBody,
+    message: &str,
+    command_status: PduStatus,
+) {
+    let e = error(body);
+    assert_eq!(
+        e.to_string(),
+        format!(
+            "Error parsing PDU (command_id=0x00000004, \
+            command_status=0x00000008, sequence_number=0x00000007, \
+            field_name=short_message): {}",
+            message
+        )
+    );
+    assert_eq!(e.command_status(), command_status as u32);
+}
+
+#[test]
+fn without_header_or_field_name_they_are_unknown() {
+    let e = PduParseError::new(PduParseErrorBody::NotEnoughBytes);
+    assert_eq!(
+        e.to_string(),
+        "Error parsing PDU (command_id=UNKNOWN, command_status=UNKNOWN, \
+        sequence_number=UNKNOWN, field_name=UNKNOWN): Reached end of PDU \
+        length (or end of input) before finding all fields of the PDU."
+    );
+}
+
+#[test]
+fn body_not_allowed_when_status_is_not_zero() {
+    assert_error(
+        PduParseErrorBody::BodyNotAllowedWhenStatusIsNotZero,
+        "PDU body must not be supplied when status is not zero, but \
+        command_status is 0x00000008.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn body_required_when_status_is_zero() {
+    assert_error(
+        PduParseErrorBody::BodyRequiredWhenStatusIsZero,
+        "PDU body must be supplied when status is zero, but it is missing.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn length_longer_than_pdu() {
+    assert_error(
+        PduParseErrorBody::LengthLongerThanPdu(0x20),
+        "Finished parsing PDU but its length (32) suggested it was longer.",
+        PduStatus::ESME_RINVCMDLEN,
+    );
+}
+
+#[test]
+fn length_too_long() {
+    assert_error(
+        PduParseErrorBody::LengthTooLong(70001),
+        "Length (70001) too long.  Max allowed is 70000 octets.",
+        PduStatus::ESME_RINVCMDLEN,
+    );
+}
+
+#[test]
+fn length_too_short() {
+    assert_error(
+        PduParseErrorBody::LengthTooShort(4),
+        "Length (4) too short.  Min allowed is 8 octets.",
+        PduStatus::ESME_RINVCMDLEN,
+    );
+}
+
+#[test]
+fn incorrect_length() {
+    assert_error(
+        PduParseErrorBody::IncorrectLength(
+            5,
+            String::from("sm_length does not match short_message."),
+        ),
+        "Length 5 was incorrect: sm_length does not match short_message.",
+        PduStatus::ESME_RINVMSGLEN,
+    );
+}
+
+#[test]
+fn invalid_sequence_number() {
+    assert_error(
+        PduParseErrorBody::InvalidSequenceNumber,
+        "Sequence number 0x00000007 is not allowed: must be 0x00000001 to \
+        0x7FFFFFFF.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn not_enough_bytes() {
+    assert_error(
+        PduParseErrorBody::NotEnoughBytes,
+        "Reached end of PDU length (or end of input) before finding all \
+        fields of the PDU.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn octet_string_creation_error() {
+    assert_error(
+        PduParseErrorBody::OctetStringCreationError(
+            OctetStringCreationError::DoesNotEndWithZeroByte,
+        ),
+        "C-Octet String does not end with the NULL character.",
+        PduStatus::ESME_RSYSERR,
+    );
+    assert_error(
+        PduParseErrorBody::OctetStringCreationError(
+            OctetStringCreationError::TooLong(16),
+        ),
+        "Octet String is too long.  Max length is 16, including final zero \
+        byte.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn other_io_error() {
+    assert_error(
+        PduParseErrorBody::OtherIoError(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "bad data",
+        )),
+        "IO error: bad data",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn status_is_not_zero() {
+    assert_error(
+        PduParseErrorBody::StatusIsNotZero,
+        "command_status must be 0, but was 0x00000008.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn status_is_zero() {
+    assert_error(
+        PduParseErrorBody::StatusIsZero,
+        "command_status must not be non-zero, but was 0.",
+        PduStatus::ESME_RSYSERR,
+    );
+}
+
+#[test]
+fn unknown_command_id() {
+    assert_error(
+        PduParseErrorBody::UnknownCommandId,
+        "Supplied command_id is unknown.",
+        PduStatus::ESME_RINVCMDID,
+    );
+}
@@ -0,0 +1,134 @@
+use futures::FutureExt;
+use smpp::pdu::{AnyPdu, DeliverSmRespPdu, ExtraPdu, ExtraPduBody};
+use smpp::smpp_connection::SmppConnection;
+use smpp_pdu::pdu::{EnquireLinkPdu, Pdu, SubmitSmRespPdu};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
+use tokio::net::{TcpListener, TcpStream};
+
+#[tokio::test]
+async fn responses_arriving_out_of_order_resolve_their_own_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut peer = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, addr) = listener.accept().await.unwrap();
+    let connection = SmppConnection::new(stream, addr);
+
+    // Given two requests waiting for responses
+    let first = connection
+        .write_request(EnquireLinkPdu::new().into())
+        .await
+        .unwrap();
+    let second = connection
+        .write_request(EnquireLinkPdu::new().into())
+        .await
+        .unwrap();
+    let mut requests = [0; 32];
+    peer.read_exact(&mut requests).await.unwrap();
+
+    // When the responses arrive in the opposite order
+    peer.write_all(
+        b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x02\
+        \x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+    )
+    .await
+    .unwrap();
+    for _ in 0..2 {
+        match connection.read_pdu().await.unwrap() {
+            Some(AnyPdu::Pdu(pdu)) => {
+                assert!(connection.response_received(pdu).is_none())
+            }
+            other => panic!("Expected enquire_link_resp, got {:?}", other),
+        }
+    }
+
+    // Then each request gets its own response
+    assert_eq!(first.await.unwrap().sequence_number.value, 1);
+    assert_eq!(second.await.unwrap().sequence_number.value, 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_writes_never_interleave_their_pdus() {
+    const WRITES: u32 = 200;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let peer = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let peer_addr = peer.local_addr().unwrap();
+    let peer = SmppConnection::new(peer, peer_addr);
+    let (stream, addr) = listener.accept().await.unwrap();
+    let connection = Arc::new(SmppConnection::new(stream, addr));
+
+    // When many tasks write PDUs of different types and lengths at once
+    let writers: Vec<_> = (1..=WRITES)
+        .map(|sequence_number| {
+            let connection = Arc::clone(&connection);
+            tokio::spawn(async move {
+                let message_id = "x".repeat(sequence_number as usize % 60);
+                if sequence_number % 2 == 0 {
+                    let pdu = Pdu::new(
+                        0,
+                        sequence_number,
+                        SubmitSmRespPdu::new(&message_id).unwrap().into(),
+                    )
+                    .unwrap();
+                    connection.write_pdu(&pdu).await.unwrap();
+                } else {
+                    let pdu = ExtraPdu::new(
+                        0,
+                        sequence_number,
+                        ExtraPduBody::DeliverSmResp(Some(
+                            DeliverSmRespPdu::new(&message_id).unwrap(),
+                        )),
+                    );
+                    connection.write_extra_pdu(&pdu).await.unwrap();
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await.unwrap();
+    }
+
+    // Then every one of them is read back whole
+    let mut sequence_numbers = HashSet::new();
+    for _ in 0..WRITES {
+        let pdu = read_pdu(&peer).await;
+        sequence_numbers.insert(pdu.sequence_number());
+    }
+    assert_eq!(sequence_numbers, (1..=WRITES).collect());
+}
+
+async fn read_pdu(connection: &SmppConnection) -> AnyPdu {
+    match connection.read_pdu().await {
+        Ok(Some(pdu)) => pdu,
+        other => panic!("Expected a PDU, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn write_pdu_flushes_buffered_streams() {
+    // A stream that holds on to writes until it is flushed
+    let (ours, mut peer) = tokio::io::duplex(1024);
+    let connection = SmppConnection::new(
+        BufWriter::new(ours),
+        "127.0.0.1:0".parse::<SocketAddr>().unwrap(),
+    );
+
+    let pdu = Pdu::new(0, 0x12, EnquireLinkPdu::new().into()).unwrap();
+    connection.write_pdu(&pdu).await.unwrap();
+
+    // The whole PDU is there to read as soon as write_pdu returns
+    let mut bytes = [0; 16];
+    peer.read_exact(&mut bytes)
+        .now_or_never()
+        .expect("The PDU should already have been written")
+        .unwrap();
+    assert_eq!(
+        &bytes,
+        b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12"
+    );
+}
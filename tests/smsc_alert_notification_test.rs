@@ -0,0 +1,72 @@
+use ascii::AsciiString;
+use smpp::pdu::AlertNotificationPdu;
+use smpp::smpp_connection::EsmeId;
+use std::str::FromStr;
+
+mod test_utils;
+
+use test_utils::TestSetup;
+
+fn esme_id() -> EsmeId {
+    EsmeId {
+        system_id: AsciiString::from_str("esmeid").unwrap(),
+        system_type: AsciiString::from_str("type").unwrap(),
+    }
+}
+
+fn alert_notification() -> AlertNotificationPdu {
+    AlertNotificationPdu::new(
+        1,
+        1,
+        "447000123123",
+        1,
+        1,
+        "447111222222",
+        Some(0),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn bound_receiver_receives_alert_notification() {
+    // Given a client bound as a receiver
+    let mut setup = TestSetup::new().await;
+    setup.client.bind_receiver().await;
+
+    // When we send it an alert_notification
+    setup
+        .server
+        .smsc
+        .lock()
+        .await
+        .send_alert_notification(&esme_id(), alert_notification())
+        .await
+        .unwrap();
+
+    // Then it receives it
+    setup
+        .client
+        .expect_to_receive(
+            b"\x00\x00\x00\x33\x00\x00\x01\x02\x00\x00\x00\x00\x00\x00\x00\x01\
+            \x01\x01447000123123\x00\x01\x01447111222222\x00\
+            \x04\x22\x00\x01\x00",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn alert_notification_is_refused_for_transmitters() {
+    let mut setup = TestSetup::new().await;
+    setup.client.bind_transmitter().await;
+
+    let err = setup
+        .server
+        .smsc
+        .lock()
+        .await
+        .send_alert_notification(&esme_id(), alert_notification())
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("transmitter"), "{}", err);
+}
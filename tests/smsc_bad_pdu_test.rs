@@ -4,21 +4,53 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 mod test_utils;
 
-use test_utils::{bytes_as_string, TestClient, TestServer, TestSetup};
+use smpp::smsc::SmscConfig;
+use test_utils::{
+    bytes_as_string, AcceptAll, DefaultLogic, TestClient, TestServer, TestSetup,
+};
 
 #[tokio::test]
 async fn when_we_receive_a_bad_pdu_we_respond_with_failure_resp_pdu() {
-    TestSetup::new()
-        .await
-        .client
-        .send_and_expect_error_response(
+    let mut client = TestSetup::new().await.client;
+
+    client
+        .send_and_expect_response(
             b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x14\
         e\xf0\x9f\x92\xa9d\0password\0type\0\x34\x00\x00\0",
             //  ^^^^ non-ascii
             b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x08\x00\x00\x00\x14",
             //                               system error ^^^^        seq ^^^^
             // Note: no body part because this is an error response
-            "unexpected end of file",
+        )
+        .await;
+
+    // Its command_length was right, so the same connection can still bind
+    client.bind_transmitter().await;
+}
+
+#[tokio::test]
+async fn when_we_receive_a_truncated_submit_sm_we_respond_submit_sm_resp() {
+    let mut client = TestSetup::new_with_logic(AcceptAll {}).await.client;
+    client.bind_transmitter().await;
+
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x14\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00\x00\x004",
+            //  ^^^^ source_addr is cut off by the command_length
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x03",
+            //  submit_sm_resp ^^^^  system error ^^^^        seq ^^^^
+        )
+        .await;
+
+    // A valid submit_sm on the same connection is accepted
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi",
+            b"\x00\x00\x00\x1a\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\
+            mymessage\0",
         )
         .await;
 }
@@ -33,7 +65,7 @@ async fn when_client_disconnects_within_pdu_we_continue_accepting_new_connection
     let server = TestServer::start().await.unwrap();
     // When ESME sends partial data then disconnects
     let mut client1 = TestClient::connect_to(&server).await.unwrap();
-    client1.stream.write(PDU).await.unwrap();
+    client1.stream.write_all(PDU).await.unwrap();
     client1.stream.shutdown().await.unwrap();
 
     // Another client is free to connect afterwards
@@ -56,17 +88,73 @@ async fn when_sent_bad_pdu_header_we_respond_generic_nack() {
 }
 
 #[tokio::test]
-async fn when_we_receive_wrong_type_of_pdu_we_respond_generic_nack() {
-    TestSetup::new()
-        .await
-        .client
-        .send_and_expect_error_response(
+async fn when_we_receive_wrong_type_of_pdu_we_respond_generic_nack_and_carry_on(
+) {
+    let mut client = TestSetup::new().await.client;
+
+    client
+        .send_and_expect_response(
             b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
         TestServer\0",
             // bind_transmitter_resp ^^^^^^^^^^^^^ - doesn't make sense
             b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x02",
             //       generic_nack ^^^^          invalid cmdid ^^^^        seq ^^^^
-            "unexpected end of file",
+        )
+        .await;
+
+    // The same connection can still bind
+    client.bind_transmitter().await;
+}
+
+#[tokio::test]
+async fn when_we_receive_a_request_we_do_not_handle_we_reject_it_and_carry_on()
+{
+    let mut client = TestSetup::new().await.client;
+    client.bind_transmitter().await;
+
+    // submit_multi is a PDU type we know, but we don't deliver it yet
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x46\x00\x00\x00\x21\x00\x00\x00\x00\x00\x00\x00\x24\
+            \x00\x01\x01447000123123\x00\
+            \x02\
+            \x01\x01\x01447111222222\x00\
+            \x02friends\x00\
+            \x00\x00\x00\x00\x00\x01\x00\x00\x00\x02hi",
+            b"\x00\x00\x00\x10\x80\x00\x00\x21\x00\x00\x00\x03\x00\x00\x00\x24",
+            //  submit_multi_resp ^^^^         invalid cmdid ^^^^        seq ^^^^
+        )
+        .await;
+
+    // The session is still open
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x25",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x25",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_a_client_sends_us_deliver_sm_we_reject_it_and_carry_on() {
+    let mut client = TestSetup::new().await.client;
+    client.bind_transceiver().await;
+
+    // Only SMSCs send deliver_sm
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x3d\x00\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi",
+            b"\x00\x00\x00\x10\x80\x00\x00\x05\x00\x00\x00\x03\x00\x00\x00\x03",
+            //    deliver_sm_resp ^^^^         invalid cmdid ^^^^        seq ^^^^
+        )
+        .await;
+
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x04",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x04",
         )
         .await;
 }
@@ -85,16 +173,36 @@ async fn when_we_receive_nontlv_pdu_with_too_long_length_return_an_error() {
         .take(100_000)
         .collect();
 
-    TestSetup::new()
-        .await
-        .client
-        .send_and_expect_error_response(
+    let mut client = TestSetup::new().await.client;
+
+    // The bind is followed by zeros up to its command_length, so we reject
+    // it...
+    client
+        .send_and_expect_response(
             &many_bytes,
             b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x02",
             //      bind_transmitter_resp ^^^^              ^^ cmd len invalid
-            "Connection reset by peer (os error 104)",
         )
         .await;
+
+    // ...and then read the zeros after it as a PDU whose command_length is
+    // 0, so we reject that and disconnect (resetting the connection if
+    // some of the zeros are still unread)
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00",
+            //       generic_nack ^^^^           cmd len invalid ^^
+        )
+        .await;
+    let e = client.stream.read_u8().await.unwrap_err();
+    assert!(
+        matches!(
+            e.kind(),
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset
+        ),
+        "{}",
+        e
+    );
 }
 
 #[tokio::test]
@@ -105,13 +213,12 @@ async fn when_we_receive_a_pdu_with_very_long_length_we_respond_generic_nack() {
     // very long length
 
     const RESP: &[u8; 0x10] =
-        b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x01";
-    //       generic_nack ^^^^        cmd len invalid ^^^^        seq ^^^^
+        b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x02";
+    //  bind_transmitter_resp ^^^^     cmd len invalid ^^^^        seq ^^^^
 
-    // Note: we don't provide the correct sequence number here: we could, but
-    // we would have to read the PDU header before we notice the invalid
-    // PDU length.  Since a huge length is likely to indicate a catastrophic
-    // error, or malicious traffic, we are not too bothered.
+    // Note: we can only provide the right PDU type and sequence_number
+    // because the header has arrived by the time we notice the invalid PDU
+    // length.
 
     let many_bytes: Vec<u8> = PDU
         .iter()
@@ -124,7 +231,7 @@ async fn when_we_receive_a_pdu_with_very_long_length_we_respond_generic_nack() {
     let server = TestServer::start().await.unwrap();
     // When we send a huge PDU with huge length
     let mut client = TestClient::connect_to(&server).await.unwrap();
-    client.stream.write(&many_bytes).await.unwrap();
+    let _written = client.stream.write(&many_bytes).await.unwrap();
 
     // Then SMSC either ...
     let resp = client.read_n_maybe(RESP.len()).await;
@@ -155,32 +262,94 @@ async fn when_receive_pdu_with_short_length_but_long_string_we_respond_with_erro
     // Our PDU will contain 100,000 letter 'e's within a COctetString
     let mut many_bytes: Vec<u8> = vec![];
     many_bytes.extend(BEGIN.iter());
-    many_bytes.extend(iter::repeat('e' as u8).take(100_000));
+    many_bytes.extend(iter::repeat_n(b'e', 100_000));
     many_bytes.extend(END.iter());
 
-    TestSetup::new()
-        .await
-        .client
-        .send_and_expect_error_response(
+    let mut client = TestSetup::new().await.client;
+
+    // The PDU ends before the string does, so we reject it...
+    client
+        .send_and_expect_response(
             &many_bytes,
             b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x08\x00\x00\x00\x02",
             //      bind_transmitter_resp ^^^^ system error ^^
-            "Connection reset by peer (os error 104)",
         )
         .await;
+
+    // ...and then read the rest of the string as the next PDU, whose
+    // command_length is far too long, so we reject that and disconnect
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x02\x65\x65\x65\x65",
+            //       generic_nack ^^^^           cmd len invalid ^^
+        )
+        .await;
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::ConnectionReset
+    );
 }
 
 #[tokio::test]
 async fn when_we_receive_invalid_pdu_type_we_respond_with_error() {
-    TestSetup::new()
-        .await
-        .client
-        .send_and_expect_error_response(
+    let mut client = TestSetup::new().await.client;
+
+    client
+        .send_and_expect_response(
             b"\x00\x00\x00\x10\xff\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x22",
             //    this is invalid! ^^^^^^^^^^^^^^^                    seq ^^^^
             b"\x00\x00\x00\x10\x80\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x22",
             //   generic_nack ^^^^          invalid cmdid ^^^^        seq ^^^^
-            "unexpected end of file",
+        )
+        .await;
+
+    // The same connection can still bind
+    client.bind_transmitter().await;
+}
+
+#[tokio::test]
+async fn when_we_receive_a_pdu_with_too_many_tlvs_we_respond_with_error() {
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            max_tlvs_per_pdu: 2,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    // 2 TLVs is allowed: this reaches the SmscLogic, which fails it
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x47\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi\
+            \x02\x04\x00\x02\x00\x01\x04\x24\x00\x00",
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x03",
+            //  submit_sm_resp ^^^^
+        )
+        .await;
+
+    // 3 TLVs is rejected before parsing
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x4c\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi\
+            \x02\x04\x00\x02\x00\x01\x04\x24\x00\x00\x14\x2e\x00\x01\x00",
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x04",
+            //  submit_sm_resp ^^^^
+        )
+        .await;
+
+    // The session is still open
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x05",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x05",
         )
         .await;
 }
@@ -1,13 +1,23 @@
 use async_trait::async_trait;
 use smpp::message_unique_key::MessageUniqueKey;
-use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::pdu::{BindDataExt, INTERFACE_VERSION_34};
+use smpp::session_state::SessionState;
+use smpp::smpp_connection::PeerAddr;
+use smpp::smsc::{
+    BindContext, BindData, BindError, Smsc, SmscConfig, SmscLogic,
+    SubmitSmError,
+};
 use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout};
 
 mod test_utils;
 
-use test_utils::TestSetup;
+use test_utils::{DefaultLogic, TestClient, TestServer, TestSetup};
 
 #[tokio::test]
 async fn when_we_receive_bind_transmitter_we_respond_with_resp() {
@@ -197,9 +207,221 @@ async fn when_we_receive_multiple_binds_we_can_keep_track() {
     assert_eq!(*num_binds.lock().unwrap(), 3);
 }
 
+#[tokio::test]
+async fn when_logic_rejects_interface_version_we_receive_bind_fail() {
+    struct RequiresV34 {}
+
+    #[async_trait]
+    impl SmscLogic for RequiresV34 {
+        async fn bind(
+            &mut self,
+            bind_data: &BindData,
+        ) -> Result<(), BindError> {
+            if bind_data.interface_version.value < INTERFACE_VERSION_34 {
+                Err(BindError::IncorrectInterfaceVersion)
+            } else {
+                assert!(bind_data.is_v34());
+                Ok(())
+            }
+        }
+
+        async fn submit_sm(
+            &mut self,
+            _smsc: Arc<Mutex<Smsc>>,
+            _pdu: &SubmitSmPdu,
+            _sequence_number: u32,
+        ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>
+        {
+            panic!("submit_sm not implemented");
+        }
+    }
+
+    let mut t = TestSetup::new_with_logic(RequiresV34 {}).await;
+    t.client
+        .send_and_expect_response(
+            // bind_transceiver, interface_version=0x33
+            b"\x00\x00\x00\x29\x00\x00\x00\x09\x00\x00\x00\x00\x00\x00\x00\x06\
+        esmeid\0password\0type\0\x33\x00\x00\0",
+            // command_status=ESME_RBINDFAIL
+            b"\x00\x00\x00\x10\x80\x00\x00\x09\x00\x00\x00\x0d\x00\x00\x00\x06",
+        )
+        .await;
+
+    t.new_client().await;
+    t.client.bind_transceiver().await;
+}
+
+#[tokio::test]
+async fn when_logic_rejects_the_peer_address_we_receive_bind_fail() {
+    // Given logic that only allows binds from one IP address
+    struct AllowOnly(IpAddr);
+
+    #[async_trait]
+    impl SmscLogic for AllowOnly {
+        async fn bind(
+            &mut self,
+            _bind_data: &BindData,
+        ) -> Result<(), BindError> {
+            panic!("bind_with_context should be called instead");
+        }
+
+        async fn bind_with_context(
+            &mut self,
+            context: &BindContext<'_>,
+        ) -> Result<(), BindError> {
+            assert_eq!(context.session_state, SessionState::BoundTrx);
+            match context.peer_addr {
+                PeerAddr::Tcp(addr) if addr.ip() == self.0 => Ok(()),
+                _ => Err(BindError::NotAllowed),
+            }
+        }
+
+        async fn submit_sm(
+            &mut self,
+            _smsc: Arc<Mutex<Smsc>>,
+            _pdu: &SubmitSmPdu,
+            _sequence_number: u32,
+        ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>
+        {
+            panic!("submit_sm not implemented");
+        }
+    }
+
+    // When we connect from 127.0.0.1 but only 10.1.2.3 is allowed
+    let mut t =
+        TestSetup::new_with_logic(AllowOnly(IpAddr::from([10, 1, 2, 3]))).await;
+    t.client
+        .send_and_expect_response(
+            // bind_transceiver
+            b"\x00\x00\x00\x29\x00\x00\x00\x09\x00\x00\x00\x00\x00\x00\x00\x06\
+        esmeid\0password\0type\0\x34\x00\x00\0",
+            // Then we get command_status=ESME_RBINDFAIL
+            b"\x00\x00\x00\x10\x80\x00\x00\x09\x00\x00\x00\x0d\x00\x00\x00\x06",
+        )
+        .await;
+
+    // But when our address is allowed, we can bind
+    let mut t =
+        TestSetup::new_with_logic(AllowOnly(IpAddr::from([127, 0, 0, 1])))
+            .await;
+    t.client.bind_transceiver().await;
+}
+
+#[tokio::test]
+async fn when_we_submit_before_binding_we_receive_invalid_bind_status() {
+    TestSetup::new()
+        .await
+        .client
+        .send_and_expect_response(
+            // submit_sm, sequence_number = 3
+            b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+            \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+            \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi",
+            // command_status=ESME_RINVBNDSTS
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x04\x00\x00\x00\x03",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_we_bind_twice_we_receive_already_bound() {
+    let mut t = TestSetup::new().await;
+    t.client.bind_transmitter().await;
+    t.client
+        .send_and_expect_response(
+            // bind_receiver, sequence_number = 8
+            b"\x00\x00\x00\x29\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x08\
+        esmeid\0password\0type\0\x34\x00\x00\0",
+            // command_status=ESME_RALYBND
+            b"\x00\x00\x00\x10\x80\x00\x00\x01\x00\x00\x00\x05\x00\x00\x00\x08",
+        )
+        .await;
+}
+
 // Later: Issue#12: return MO
 // Later: Issue#9: client app + system test that allows to compare w CloudHopper
-// Later: Issue#8: smpp session states (spec 2.2)
 // Later: Issue#2: sc_interface_version TLV in bind response
 // Later: Issue#10: Check interface versions in binds and responses, submit_sm
 // Later: Issue#11: all PDU types and formats
+
+#[tokio::test(start_paused = true)]
+async fn when_configured_we_delay_bind_resp() {
+    // Given a server configured to delay bind responses by 200ms
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            bind_resp_delay: Some(Duration::from_millis(200)),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When we bind
+    client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            esmeid\0password\0type\0\x34\x00\x00\0",
+        )
+        .await
+        .unwrap();
+
+    // Then nothing arrives before the delay is up
+    let mut buf = [0; 1];
+    assert!(
+        timeout(Duration::from_millis(190), client.stream.peek(&mut buf))
+            .await
+            .is_err()
+    );
+
+    // And the bind_resp arrives once it is
+    timeout(
+        Duration::from_millis(20),
+        client.expect_to_receive(
+            b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            TestServer\0",
+        ),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn when_a_system_id_has_max_binds_further_binds_fail() {
+    // Given a server allowing 2 binds per system_id
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            max_binds_per_system_id: Some(2),
+            max_open_sockets: 10,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transmitter().await;
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transmitter().await;
+
+    // When the same system_id binds a third time, it gets ESME_RBINDFAIL
+    let mut client3 = TestClient::connect_to(&server).await.unwrap();
+    client3
+        .send_and_expect_response(
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+        esmeid\0password\0type\0\x34\x00\x00\0",
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x0d\x00\x00\x00\x07",
+        )
+        .await;
+
+    // But another system_id may still bind
+    let mut other = TestClient::connect_to(&server).await.unwrap();
+    other.bind_transceiver_as("other").await;
+
+    // And once one of the first sessions closes, the place is free again
+    drop(client1);
+    sleep(Duration::from_millis(100)).await;
+    client3.bind_transmitter().await;
+}
@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::pdu::CancelSmPdu;
+use smpp::smsc::{
+    BindData, BindError, CancelSmError, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestSetup};
+
+const SUBMIT_SM: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+const SUBMIT_SM_RESP: &[u8; 0x1a] =
+    b"\x00\x00\x00\x1a\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+    mymessage\x00";
+
+const CANCEL_SM: &[u8; 0x39] =
+    b"\x00\x00\x00\x39\x00\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x04\
+    \x00mymessage\x00\x00\x00447000123123\x00\x00\x00447111222222\x00";
+
+/// Accepts every message as "mymessage", and lets it be cancelled
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("mymessage").unwrap(),
+            MessageUniqueKey::new(
+                String::from("canceltest"),
+                String::from("mymessage"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+
+    async fn cancel_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &CancelSmPdu,
+    ) -> Result<(), CancelSmError> {
+        if pdu.message_id.value == "mymessage" {
+            Ok(())
+        } else {
+            Err(CancelSmError::CancelFailed)
+        }
+    }
+}
+
+#[tokio::test]
+async fn when_we_receive_cancel_sm_we_respond_with_resp() {
+    let mut client = TestSetup::new_with_logic(Logic {})
+        .await
+        .client
+        .into_bound_transmitter()
+        .await;
+    client
+        .send_and_expect_response(SUBMIT_SM, SUBMIT_SM_RESP)
+        .await;
+
+    client
+        .send_and_expect_response(
+            CANCEL_SM,
+            b"\x00\x00\x00\x10\x80\x00\x00\x08\x00\x00\x00\x00\x00\x00\x00\x04",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_logic_does_not_support_cancel_sm_we_respond_with_error() {
+    let mut client = TestSetup::new_with_logic(DefaultLogic {})
+        .await
+        .client
+        .into_bound_transmitter()
+        .await;
+
+    client
+        .send_and_expect_response(
+            CANCEL_SM,
+            b"\x00\x00\x00\x10\x80\x00\x00\x08\x00\x00\x00\x11\x00\x00\x00\x04",
+            //                       ESME_RCANCELFAIL ^^^^
+        )
+        .await;
+}
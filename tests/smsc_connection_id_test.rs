@@ -0,0 +1,29 @@
+use std::collections::HashSet;
+
+mod test_utils;
+
+use test_utils::{CapturingLogger, TestClient, TestServer};
+
+#[tokio::test]
+async fn each_connection_is_logged_with_its_own_id() {
+    CapturingLogger::install();
+
+    // Given two clients connected and bound
+    let server = TestServer::start().await.unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transmitter().await;
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transmitter().await;
+
+    // Then the logs for their binds carry different connection ids
+    let ids: HashSet<String> = CapturingLogger::lines()
+        .iter()
+        .filter(|line| line.contains("bound as transmitter"))
+        .map(|line| {
+            let start = line.find("[conn#").expect(line);
+            let end = start + line[start..].find(']').unwrap();
+            String::from(&line[start..=end])
+        })
+        .collect();
+    assert_eq!(ids.len(), 2, "{:?}", ids);
+}
@@ -0,0 +1,183 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, Smsc, SmscLogic, SubmitOutcome, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::TestSetup;
+
+const SUBMIT_SM: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x2f\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+#[tokio::test]
+async fn when_logic_defers_submit_sm_we_respond_when_it_completes() {
+    // Given logic that takes 200ms to accept each message
+    let mut t = TestSetup::new_with_logic(SlowLogic {}).await;
+    t.client.bind_transmitter().await;
+
+    // When we submit a message, then send an enquire_link
+    t.client.stream.write_all(SUBMIT_SM).await.unwrap();
+    t.client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await
+        .unwrap();
+
+    // Then the enquire_link is answered while the submit_sm is pending
+    t.client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // And the submit_sm_resp arrives afterwards, with the submit_sm's
+    // sequence_number
+    t.client
+        .expect_to_receive(
+            b"\x00\x00\x00\x16\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x2f\
+            later\x00",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_a_sequence_number_is_reused_while_in_flight_we_reject_it() {
+    // Given logic that takes 200ms to accept each message
+    let mut t = TestSetup::new_with_logic(SlowLogic {}).await;
+    t.client.bind_transmitter().await;
+
+    // When we send two submit_sms with the same sequence_number, without
+    // waiting for the first response
+    t.client.stream.write_all(SUBMIT_SM).await.unwrap();
+    t.client.stream.write_all(SUBMIT_SM).await.unwrap();
+
+    // Then the second is rejected with ESME_RINVCMDLEN straight away
+    t.client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x02\x00\x00\x00\x2f",
+        )
+        .await;
+
+    // And the first is accepted when the logic has finished with it
+    t.client
+        .expect_to_receive(
+            b"\x00\x00\x00\x16\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x2f\
+            later\x00",
+        )
+        .await;
+
+    // And afterwards the sequence_number may be used again
+    t.client.stream.write_all(SUBMIT_SM).await.unwrap();
+    t.client
+        .expect_to_receive(
+            b"\x00\x00\x00\x16\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x2f\
+            later\x00",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_deferred_submit_sm_panics_we_respond_with_syserr() {
+    // Given logic whose deferred submit_sm panics
+    let mut t = TestSetup::new_with_logic(PanickingLogic {}).await;
+    t.client.bind_transmitter().await;
+
+    // When we submit a message, we get ESME_RSYSERR
+    t.client
+        .send_and_expect_response(
+            SUBMIT_SM,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x2f",
+        )
+        .await;
+
+    // And the session carries on
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+}
+
+struct SlowLogic {}
+
+#[async_trait]
+impl SmscLogic for SlowLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok(accept(pdu))
+    }
+
+    async fn submit_sm_deferrable(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> SubmitOutcome {
+        let accepted = accept(pdu);
+        SubmitOutcome::Deferred(Box::pin(async move {
+            sleep(Duration::from_millis(200)).await;
+            Ok(accepted)
+        }))
+    }
+}
+
+struct PanickingLogic {}
+
+#[async_trait]
+impl SmscLogic for PanickingLogic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok(accept(pdu))
+    }
+
+    async fn submit_sm_deferrable(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> SubmitOutcome {
+        SubmitOutcome::Deferred(Box::pin(async move {
+            panic!("deferred submit_sm failed badly");
+        }))
+    }
+}
+
+fn accept(pdu: &SubmitSmPdu) -> (SubmitSmRespPdu, MessageUniqueKey) {
+    (
+        SubmitSmRespPdu::new("later").unwrap(),
+        MessageUniqueKey::new(
+            String::from("deferred"),
+            String::from("later"),
+            pdu.destination_addr(),
+        ),
+    )
+}
@@ -0,0 +1,43 @@
+use std::io;
+use tokio::io::AsyncReadExt;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestServer};
+
+#[tokio::test]
+async fn disconnecting_a_system_id_closes_only_its_connections() {
+    // Given two clients bound with different system_ids
+    let server = TestServer::start().await.unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transceiver_as("esme1").await;
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transceiver_as("esme2").await;
+
+    // When we disconnect one system_id
+    let count = server.smsc.lock().await.disconnect_system_id("esme1");
+
+    // Then its connection is closed
+    assert_eq!(count, 1);
+    assert_eq!(
+        client1.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+
+    // And the other client is still connected
+    client2
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn disconnecting_an_unknown_system_id_closes_nothing() {
+    let server = TestServer::start().await.unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver_as("esme1").await;
+
+    assert_eq!(server.smsc.lock().await.disconnect_system_id("other"), 0);
+}
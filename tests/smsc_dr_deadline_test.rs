@@ -0,0 +1,118 @@
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::pdu::{
+    write_pdu, DeliverSmPduBuilder, MessageState, SubmitSmPduBuilder,
+};
+use smpp::smsc::{DeliveryStatus, SmscConfig};
+use smpp_pdu::pdu::Pdu;
+use std::time::Duration;
+use tokio::time::{advance, sleep};
+
+mod test_utils;
+
+use test_utils::{
+    AcceptAll, CapturingLogger, TestClient, TestServer, ACCEPTED_MESSAGE_ID,
+    ACCEPT_ALL_NAMESPACE,
+};
+
+#[tokio::test(start_paused = true)]
+async fn when_client_does_not_bind_again_in_time_we_drop_its_dr() {
+    CapturingLogger::install();
+
+    // Given a server that holds DRs for 100ms
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            dr_delivery_deadline: Duration::from_millis(100),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+
+    // And a client that submits a message, then goes away
+    {
+        let mut client = TestClient::connect_to(&server).await.unwrap();
+        client.bind_transceiver().await;
+        client
+            .send_and_expect_response(
+                &write(submit_sm()).await,
+                b"\x00\x00\x00\x1a\x80\x00\x00\x04\x00\x00\x00\x00\
+                \x00\x00\x00\x2fmymessage\x00",
+            )
+            .await;
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    // When its DR arrives, we hold it
+    let key = MessageUniqueKey::new(
+        String::from(ACCEPT_ALL_NAMESPACE),
+        String::from(ACCEPTED_MESSAGE_ID),
+        String::from("447111222222"),
+    );
+    server
+        .receive_pdu(ACCEPT_ALL_NAMESPACE, dr())
+        .await
+        .unwrap();
+    assert_eq!(
+        server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Pending)
+    );
+
+    // And when the deadline passes
+    advance(Duration::from_millis(300)).await;
+
+    // Then we log that we dropped it
+    let logs = CapturingLogger::lines();
+    assert!(
+        logs.iter().any(|line| line
+            .starts_with("Dropping DR for message_id='mymessage' because")),
+        "{:?}",
+        logs
+    );
+
+    // And mark its delivery as expired
+    assert_eq!(
+        server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Expired)
+    );
+    let metrics = server.smsc.lock().await.metrics_snapshot();
+    assert_eq!(metrics.deliver_sm_failed, 1);
+
+    // And the client gets nothing when it binds again: the next thing it
+    // receives is the response to its enquire_link
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+}
+
+fn submit_sm() -> Pdu {
+    let body = SubmitSmPduBuilder::new()
+        .source_addr("447000123123")
+        .destination_addr("447111222222")
+        .registered_delivery(1)
+        .short_message(b"hi")
+        .build()
+        .unwrap();
+    Pdu::new(0, 0x2f, body.into()).unwrap()
+}
+
+fn dr() -> Pdu {
+    let body = DeliverSmPduBuilder::new()
+        .source_addr("447111222222")
+        .destination_addr("447000123123")
+        .delivery_receipt(ACCEPTED_MESSAGE_ID, MessageState::Delivered)
+        .build()
+        .unwrap();
+    Pdu::new(0, 0x30, body.into()).unwrap()
+}
+
+async fn write(pdu: Pdu) -> Vec<u8> {
+    let mut ret = Vec::new();
+    write_pdu(&pdu, &mut ret).await.unwrap();
+    ret
+}
@@ -1,17 +1,24 @@
 use async_trait::async_trait;
 use smpp::message_unique_key::MessageUniqueKey;
-use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp::pdu::command_status::ToCommandStatus;
+use smpp::smsc::{
+    BindData, BindError, DeliveryStatus, ReceivePduError, Smsc, SmscConfig,
+    SmscLogic, SubmitSmError,
+};
 use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
 use smpp_pdu::pdu::{
-    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitEsmClass, SubmitSmPdu,
-    SubmitSmRespPdu,
+    DeliverEsmClass, DeliverSmPdu, EnquireLinkPdu, Pdu, PduBody, PduStatus,
+    SubmitEsmClass, SubmitSmPdu, SubmitSmRespPdu,
 };
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
+use tokio::time::advance;
 
 mod test_utils;
 
-use test_utils::{bytes_as_string, TestSetup};
+use test_utils::{bytes_as_string, TestClient, TestServer, TestSetup};
 
 #[tokio::test]
 async fn when_we_receive_deliver_sm_for_a_message_we_provide_it_to_client() {
@@ -79,6 +86,367 @@ async fn when_we_receive_deliver_sm_with_tlv_for_a_message_we_provide_it() {
     assert_eq!(bytes_as_string(&resp), bytes_as_string(&deliver_sm));
 }
 
+#[tokio::test]
+async fn when_client_submits_we_remember_it_until_its_dr_arrives() {
+    let msgid = "ab87J";
+    let submit_sm = new_submit_sm(0x2f).await;
+    let submit_sm_resp = new_submit_sm_resp(0x2f, msgid).await;
+    let logic = Logic {
+        msgid: String::from(msgid),
+    };
+    let key = MessageUniqueKey::new(
+        String::from("testsystem"),
+        String::from(msgid),
+        String::from("447777222222"),
+    );
+
+    let mut t = TestSetup::new_with_logic(logic).await;
+    t.client.bind_transceiver().await;
+
+    // When the client submits a message
+    t.client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp)
+        .await;
+
+    // Then we remember who submitted it, and how
+    let pending = t
+        .server
+        .smsc
+        .lock()
+        .await
+        .pending_message(&key)
+        .cloned()
+        .unwrap();
+    assert_eq!(pending.key, key);
+    assert_eq!(pending.esme_id.system_id, "esmeid");
+    assert_eq!(pending.sequence_number, 0x2f);
+    assert_eq!(pending.source_addr, "MyCompany");
+
+    // And a DR for it resolves to the same message
+    let deliver_sm_pdu = new_deliver_sm_pdu(
+        format!("id:{} submit date:2103301649", msgid).as_bytes(),
+    );
+    let resolved = match deliver_sm_pdu.body() {
+        PduBody::DeliverSm(body) => t
+            .server
+            .smsc
+            .lock()
+            .await
+            .pending_message_for_dr("testsystem", body)
+            .map(|pending| pending.connection),
+        _ => None,
+    };
+    assert_eq!(resolved, Some(pending.connection));
+}
+
+#[tokio::test]
+async fn when_client_is_bound_as_transmitter_we_do_not_deliver_dr() {
+    let msgid = "ab87J";
+    let submit_sm = new_submit_sm(0x2f).await;
+    let submit_sm_resp = new_submit_sm_resp(0x2f, msgid).await;
+    let logic = Logic {
+        msgid: String::from(msgid),
+    };
+
+    let mut t = TestSetup::new_with_logic(logic).await;
+    t.client.bind_transmitter().await;
+
+    t.client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp)
+        .await;
+
+    let deliver_sm_pdu = new_deliver_sm_pdu(
+        format!("id:{} submit date:2103301649", msgid).as_bytes(),
+    );
+
+    let err = t
+        .server
+        .receive_pdu("testsystem", deliver_sm_pdu)
+        .await
+        .unwrap_err();
+
+    // The error tells us which message could not be delivered, and why
+    assert!(
+        matches!(err, ReceivePduError::WrongBindType { .. }),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.command_status(),
+        PduStatus::ESME_RDELIVERYFAILURE as u32
+    );
+    let err = err.to_string();
+    assert!(err.contains(msgid), "{}", err);
+    assert!(err.contains("transmitter"), "{}", err);
+
+    // Nothing was sent to the client: the next thing it receives is the
+    // response to its enquire_link
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_no_client_submitted_the_message_we_report_unknown_message_id() {
+    let mut t = TestSetup::new_with_logic(Logic {
+        msgid: String::from("ab87J"),
+    })
+    .await;
+    t.client.bind_transceiver().await;
+
+    let err = t
+        .server
+        .receive_pdu(
+            "testsystem",
+            new_deliver_sm_pdu(b"id:nosuchid submit date:2103301649"),
+        )
+        .await
+        .unwrap_err();
+
+    match &err {
+        ReceivePduError::UnknownMessageId(key) => {
+            assert_eq!(key.message_id, "nosuchid")
+        }
+        other => panic!("Expected UnknownMessageId, got {:?}", other),
+    }
+    assert_eq!(err.command_status(), PduStatus::ESME_RINVMSGID as u32);
+}
+
+#[tokio::test]
+async fn when_dr_has_no_message_id_we_report_it() {
+    let t = TestSetup::new().await;
+
+    let err = t
+        .server
+        .receive_pdu("testsystem", new_deliver_sm_pdu(b"not a receipt"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, ReceivePduError::NoMessageId), "{:?}", err);
+}
+
+#[tokio::test]
+async fn when_asked_to_deliver_something_other_than_deliver_sm_we_refuse() {
+    let t = TestSetup::new().await;
+
+    let err = t
+        .server
+        .receive_pdu(
+            "testsystem",
+            Pdu::new(0, 1, EnquireLinkPdu::new().into()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, ReceivePduError::UnexpectedPduType(0x00000015)),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        "Unexpected PDU type (enquire_link).  Currently we can only handle \
+        deliver_sm and data_sm PDUs."
+    );
+}
+
+#[tokio::test]
+async fn when_client_responds_to_deliver_sm_we_mark_it_acknowledged() {
+    let msgid = "ab87J";
+    let submit_sm = new_submit_sm(0x2f).await;
+    let submit_sm_resp = new_submit_sm_resp(0x2f, msgid).await;
+    let logic = Logic {
+        msgid: String::from(msgid),
+    };
+    let key = MessageUniqueKey::new(
+        String::from("testsystem"),
+        String::from(msgid),
+        String::from("447777222222"),
+    );
+
+    let mut t = TestSetup::new_with_logic(logic).await;
+    t.client.bind_transceiver().await;
+
+    t.client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp)
+        .await;
+
+    // Given we have delivered a DR to the client
+    let deliver_sm_pdu = new_deliver_sm_pdu(
+        format!("id:{} submit date:2103301649", msgid).as_bytes(),
+    );
+    let mut deliver_sm = Vec::new();
+    deliver_sm_pdu.write(&mut deliver_sm).await.unwrap();
+
+    t.server
+        .receive_pdu("testsystem", deliver_sm_pdu)
+        .await
+        .unwrap();
+    t.client.expect_to_receive(&deliver_sm).await;
+    assert_eq!(
+        t.server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Pending)
+    );
+
+    // When the client responds with deliver_sm_resp
+    t.client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x6d\
+            \x00",
+        )
+        .await
+        .unwrap();
+
+    // (Wait until the server has processed it)
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // Then the delivery is marked complete
+    assert_eq!(
+        t.server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Acknowledged)
+    );
+}
+
+#[tokio::test]
+async fn when_client_rejects_deliver_sm_we_mark_it_rejected_and_stay_bound() {
+    let msgid = "ab87J";
+    let submit_sm = new_submit_sm(0x2f).await;
+    let submit_sm_resp = new_submit_sm_resp(0x2f, msgid).await;
+    let logic = Logic {
+        msgid: String::from(msgid),
+    };
+    let key = MessageUniqueKey::new(
+        String::from("testsystem"),
+        String::from(msgid),
+        String::from("447777222222"),
+    );
+
+    let mut t = TestSetup::new_with_logic(logic).await;
+    t.client.bind_transceiver().await;
+
+    t.client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp)
+        .await;
+
+    // Given we have delivered a DR to the client
+    let deliver_sm_pdu = new_deliver_sm_pdu(
+        format!("id:{} submit date:2103301649", msgid).as_bytes(),
+    );
+    let mut deliver_sm = Vec::new();
+    deliver_sm_pdu.write(&mut deliver_sm).await.unwrap();
+
+    t.server
+        .receive_pdu("testsystem", deliver_sm_pdu)
+        .await
+        .unwrap();
+    t.client.expect_to_receive(&deliver_sm).await;
+
+    // When the client rejects it with a deliver_sm_resp carrying a NULL
+    // message_id
+    t.client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x64\x00\x00\x00\x6d\
+            \x00",
+        )
+        .await
+        .unwrap();
+
+    // Then the session survives
+    t.client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // And the delivery is marked rejected
+    assert_eq!(
+        t.server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Rejected(0x64))
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn when_client_does_not_respond_we_resend_deliver_sm_then_give_up() {
+    let msgid = "ab87J";
+    let submit_sm = new_submit_sm(0x2f).await;
+    let submit_sm_resp = new_submit_sm_resp(0x2f, msgid).await;
+
+    let key = MessageUniqueKey::new(
+        String::from("testsystem"),
+        String::from(msgid),
+        String::from("447777222222"),
+    );
+
+    // Given a server that retries deliver_sm twice, 50ms apart
+    let server = TestServer::start_with_logic_and_smsc_config(
+        Logic {
+            msgid: String::from(msgid),
+        },
+        SmscConfig {
+            deliver_sm_resp_timeout: Some(Duration::from_millis(50)),
+            deliver_sm_max_retries: 2,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+    client
+        .send_and_expect_response(&submit_sm, &submit_sm_resp)
+        .await;
+
+    // When we deliver a DR, and the client never responds
+    let deliver_sm_pdu = new_deliver_sm_pdu(
+        format!("id:{} submit date:2103301649", msgid).as_bytes(),
+    );
+    let mut deliver_sm = Vec::new();
+    deliver_sm_pdu.write(&mut deliver_sm).await.unwrap();
+    server
+        .receive_pdu("testsystem", deliver_sm_pdu)
+        .await
+        .unwrap();
+
+    // Then the client receives it once, and then once per retry
+    client.expect_to_receive(&deliver_sm).await;
+    advance(Duration::from_millis(50)).await;
+    client.expect_to_receive(&deliver_sm).await;
+    advance(Duration::from_millis(50)).await;
+    client.expect_to_receive(&deliver_sm).await;
+
+    // And after that, nothing: the next thing it receives is the response
+    // to its enquire_link
+    advance(Duration::from_millis(200)).await;
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // And the delivery is marked as failed
+    assert_eq!(
+        server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Expired)
+    );
+    let metrics = server.smsc.lock().await.metrics_snapshot();
+    assert_eq!(metrics.deliver_sm_failed, 1);
+
+    // And each time we sent it is counted
+    assert_eq!(metrics.deliver_sm_sent, 3);
+}
+
 struct Logic {
     msgid: String,
 }
@@ -189,5 +557,3 @@ async fn new_submit_sm_resp(sequence_number: u32, msgid: &str) -> Vec<u8> {
 
     ret
 }
-
-// Later: Issue#5: Retry or fail deliver_sm or submit_sm when don't receive resp
@@ -0,0 +1,157 @@
+use smpp::smsc::SmscConfig;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+async fn start_server(max_unanswered: usize) -> TestServer {
+    TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            enquire_link_interval: Some(Duration::from_millis(50)),
+            enquire_link_max_unanswered: max_unanswered,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn when_client_is_idle_we_send_enquire_link() {
+    // Given a server that sends enquire_link every 50ms
+    let server = start_server(3).await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+
+    // When the client sends nothing, it receives an enquire_link
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_client_answers_enquire_links_we_stay_connected() {
+    // Given a server that disconnects after 1 unanswered enquire_link
+    let server = start_server(1).await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+
+    // When the client responds to each enquire_link
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+            // Then it keeps receiving them, with new sequence numbers
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x02",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x02",
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x03",
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_enquire_links_go_unanswered_we_close_the_connection() {
+    // Given a server that disconnects after 2 unanswered enquire_links
+    let server = start_server(2).await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+
+    // When the client ignores the enquire_links it receives
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await;
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x02",
+        )
+        .await;
+
+    // Then the server closes the connection
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn when_configured_we_delay_enquire_link_resp() {
+    // Given a server configured to delay enquire_link_resp by 200ms
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            enquire_link_resp_delay: Some(Duration::from_millis(200)),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When we send an enquire_link
+    client
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await
+        .unwrap();
+
+    // Then nothing arrives before the delay is up
+    let mut buf = [0; 1];
+    assert!(
+        timeout(Duration::from_millis(190), client.stream.peek(&mut buf))
+            .await
+            .is_err()
+    );
+
+    // And the response arrives once it is
+    timeout(
+        Duration::from_millis(20),
+        client.expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        ),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn when_configured_we_include_banner_in_enquire_link_resp() {
+    // Given a server configured with a banner
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            banner: Some(String::from("smsc 1.0")),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When we send an enquire_link, the response carries it in TLV 0x1400
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x1c\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12\
+            \x14\x00\x00\x08smsc 1.0",
+        )
+        .await;
+}
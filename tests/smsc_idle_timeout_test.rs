@@ -0,0 +1,57 @@
+use smpp::smsc::SmscConfig;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::time::sleep;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+async fn start_server() -> TestServer {
+    TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            idle_timeout: Some(Duration::from_millis(100)),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn when_client_sends_nothing_we_close_the_connection() {
+    // Given a server that closes connections idle for 100ms
+    let server = start_server().await;
+
+    // When a client connects but sends nothing
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    let start = Instant::now();
+
+    // Then the server closes the connection after the timeout
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn when_client_keeps_sending_we_keep_the_connection_open() {
+    let server = start_server().await;
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When the client sends a PDU every 60ms, for longer than the timeout
+    for _ in 0..4 {
+        sleep(Duration::from_millis(60)).await;
+
+        // Then each one is answered
+        client
+            .send_and_expect_response(
+                b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+                b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            )
+            .await;
+    }
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::io;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestServer};
+
+struct PanicOnSubmit {}
+
+#[async_trait]
+impl SmscLogic for PanicOnSubmit {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        _pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        panic!("submit_sm failed badly");
+    }
+}
+
+const SUBMIT_SM: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+#[tokio::test]
+async fn when_logic_panics_we_respond_with_syserr_and_disconnect() {
+    let server = TestServer::start_with_logic(PanicOnSubmit {})
+        .await
+        .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transmitter().await;
+
+    // When the logic panics handling a submit_sm, we get ESME_RSYSERR
+    client
+        .send_and_expect_response(
+            SUBMIT_SM,
+            b"\x00\x00\x00\x10\x80\x00\x00\x04\x00\x00\x00\x08\x00\x00\x00\x03",
+        )
+        .await;
+
+    // Then the server closes the connection
+    assert_eq!(
+        client.stream.read_u8().await.unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+
+    // And other clients can still use the server
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transmitter().await;
+}
@@ -0,0 +1,46 @@
+use smpp::client::SmppClient;
+use smpp::pdu::SubmitSmPduBuilder;
+use smpp::smsc::{MetricsSnapshot, SmscConfig};
+use smpp_pdu::pdu::SubmitSmPdu;
+
+mod test_utils;
+
+use test_utils::{AcceptAll, TestServer};
+
+#[tokio::test]
+async fn counters_reflect_binds_and_submits() {
+    // Given a server that accepts one message per second from each client
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            max_submits_per_second: Some(1),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When the client submits one message that is accepted and one that
+    // is rejected
+    client.submit_sm(submit_sm()).await.unwrap();
+    client.submit_sm(submit_sm()).await.unwrap_err();
+
+    // Then the counters say so
+    assert_eq!(
+        server.smsc.lock().await.metrics_snapshot(),
+        MetricsSnapshot {
+            open_connections: 1,
+            binds: 1,
+            submit_sm_received: 2,
+            submit_sm_accepted: 1,
+            submit_sm_rejected: 1,
+            ..MetricsSnapshot::default()
+        }
+    );
+}
+
+fn submit_sm() -> SubmitSmPdu {
+    SubmitSmPduBuilder::new().build().unwrap()
+}
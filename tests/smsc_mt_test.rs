@@ -1,13 +1,20 @@
 use async_trait::async_trait;
+use smpp::async_result::AsyncResult;
+use smpp::client::{ClientError, SmppClient};
 use smpp::message_unique_key::MessageUniqueKey;
-use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
-use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use smpp::pdu::command_status::ESME_RINVDCS;
+use smpp::pdu::{SmFields, SubmitSmPduBuilder};
+use smpp::smsc::{
+    BindData, BindError, Smsc, SmscConfig, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv};
+use smpp_pdu::pdu::{PduStatus, SubmitSmPdu, SubmitSmRespPdu};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 mod test_utils;
 
-use test_utils::TestSetup;
+use test_utils::{AcceptAll, TestServer, TestSetup};
 
 #[tokio::test]
 async fn when_we_receive_submit_sm_we_respond_with_resp() {
@@ -82,3 +89,250 @@ async fn when_we_receive_submit_sm_we_respond_with_resp() {
         .send_and_expect_response(&pdu, &resp)
         .await;
 }
+
+#[tokio::test]
+async fn when_message_payload_is_too_long_we_reject_submit_sm() {
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            max_message_payload_len: 100,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // A payload exactly at the limit is accepted
+    let resp = client.submit_sm(submit_sm_with_payload(&[b'x'; 100])).await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+
+    // One byte over is rejected with ESME_RINVMSGLEN
+    let resp = client.submit_sm(submit_sm_with_payload(&[b'x'; 101])).await;
+    assert_eq!(error_status(resp), PduStatus::ESME_RINVMSGLEN as u32);
+}
+
+/// "1", an escape to a septet that is not in the extension table, "A"
+const INVALID_GSM7: &[u8] = b"\xb1\x4d\x10";
+
+/// "hellohello"
+const VALID_GSM7: &[u8] = b"\xe8\x32\x9b\xfd\x46\x97\xd9\xec\x37";
+
+async fn start_server(validate_data_coding: bool) -> SmppClient {
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            validate_data_coding,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn when_validating_data_coding_we_reject_invalid_gsm7() {
+    let mut client = start_server(true).await;
+
+    let resp = client
+        .submit_sm(submit_sm_with_short_message(0x00, VALID_GSM7))
+        .await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+
+    let resp = client
+        .submit_sm(submit_sm_with_short_message(0x00, INVALID_GSM7))
+        .await;
+    assert_eq!(error_status(resp), ESME_RINVDCS);
+}
+
+#[tokio::test]
+async fn when_validating_data_coding_we_reject_odd_length_ucs2() {
+    let mut client = start_server(true).await;
+
+    let resp = client
+        .submit_sm(submit_sm_with_short_message(0x08, b"\x00H\x00"))
+        .await;
+    assert_eq!(error_status(resp), ESME_RINVDCS);
+}
+
+#[tokio::test]
+async fn when_not_validating_data_coding_we_pass_invalid_gsm7_through() {
+    let mut client = start_server(false).await;
+
+    let resp = client
+        .submit_sm(submit_sm_with_short_message(0x00, VALID_GSM7))
+        .await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+
+    let resp = client
+        .submit_sm(submit_sm_with_short_message(0x00, INVALID_GSM7))
+        .await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+}
+
+#[tokio::test]
+async fn when_source_addrs_are_restricted_we_reject_other_source_addrs() {
+    // Given a client only allowed to send from "12345"
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            allowed_source_addrs: vec![(
+                String::from("esmeid"),
+                vec![String::from("12345")],
+            )],
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When it sends from "12345", the message is accepted
+    let resp = client.submit_sm(submit_sm_from("12345")).await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+
+    // But when it sends from "99999", it is rejected
+    let resp = client.submit_sm(submit_sm_from("99999")).await;
+    assert_eq!(error_status(resp), PduStatus::ESME_RINVSRCADR as u32);
+}
+
+#[tokio::test]
+async fn when_protocol_ids_are_restricted_we_reject_other_protocol_ids() {
+    // Given a server that only allows protocol_id 0
+    let server = TestServer::start_with_logic_and_smsc_config(
+        AcceptAll {},
+        SmscConfig {
+            allowed_protocol_ids: vec![0],
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When a client sends with protocol_id 0, the message is accepted
+    let resp = client.submit_sm(submit_sm_with_protocol_id(0)).await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("mymessage"));
+
+    // But when it sends with protocol_id 0x41 (replace type 1), it is
+    // rejected
+    let resp = client.submit_sm(submit_sm_with_protocol_id(0x41)).await;
+    assert_eq!(error_status(resp), PduStatus::ESME_RSUBMITFAIL as u32);
+}
+
+#[tokio::test]
+async fn when_esm_class_has_a_reserved_message_type_we_reject_submit_sm() {
+    let server = TestServer::start_with_logic(AcceptAll {}).await.unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When a client sends with message type 0b1111, which is reserved
+    let pdu = SubmitSmPduBuilder::new().esm_class(0b00111100).build();
+    let resp = client.submit_sm(pdu.unwrap()).await;
+
+    // Then it is rejected
+    assert_eq!(error_status(resp), PduStatus::ESME_RINVESMCLASS as u32);
+}
+
+#[tokio::test]
+async fn logic_can_choose_its_response_by_destination_addr() {
+    // Given logic that only accepts messages to UK numbers
+    struct UkOnly {}
+
+    #[async_trait]
+    impl SmscLogic for UkOnly {
+        async fn bind(
+            &mut self,
+            _bind_data: &BindData,
+        ) -> Result<(), BindError> {
+            Ok(())
+        }
+
+        async fn submit_sm(
+            &mut self,
+            _smsc: Arc<Mutex<Smsc>>,
+            pdu: &SubmitSmPdu,
+            _sequence_number: u32,
+        ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError>
+        {
+            if !pdu.destination_addr().starts_with("44") {
+                return Err(SubmitSmError::InternalError);
+            }
+            let msgid = format!("uk{}", pdu.short_message().len());
+            Ok((
+                SubmitSmRespPdu::new(&msgid).unwrap(),
+                MessageUniqueKey::new(
+                    String::from("mttest"),
+                    msgid,
+                    pdu.destination_addr(),
+                ),
+            ))
+        }
+    }
+
+    let server = TestServer::start_with_logic(UkOnly {}).await.unwrap();
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When a client sends to a UK number, the logic accepts it
+    let resp = client.submit_sm(submit_sm_to("447111222222")).await;
+    assert_eq!(resp.unwrap().message_id().as_deref(), Some("uk2"));
+
+    // But when it sends elsewhere, the logic rejects it
+    let resp = client.submit_sm(submit_sm_to("33612345678")).await;
+    assert_eq!(error_status(resp), PduStatus::ESME_RSYSERR as u32);
+}
+
+/// The command_status the SMSC rejected a request with
+fn error_status<T>(resp: AsyncResult<T>) -> u32 {
+    match resp.err().unwrap().downcast_ref::<ClientError>() {
+        Some(ClientError::ErrorStatus(command_status)) => *command_status,
+        other => panic!("Expected an error status, got {:?}", other),
+    }
+}
+
+fn submit_sm_with_payload(message_payload: &[u8]) -> SubmitSmPdu {
+    SubmitSmPduBuilder::new()
+        .tlv(Tlv::new(KnownTlvTag::message_payload, message_payload))
+        .build()
+        .unwrap()
+}
+
+fn submit_sm_with_short_message(
+    data_coding: u8,
+    short_message: &[u8],
+) -> SubmitSmPdu {
+    SubmitSmPduBuilder::new()
+        .data_coding(data_coding)
+        .short_message(short_message)
+        .build()
+        .unwrap()
+}
+
+fn submit_sm_from(source_addr: &str) -> SubmitSmPdu {
+    SubmitSmPduBuilder::new()
+        .source_addr(source_addr)
+        .build()
+        .unwrap()
+}
+
+fn submit_sm_with_protocol_id(protocol_id: u8) -> SubmitSmPdu {
+    SubmitSmPduBuilder::new()
+        .protocol_id(protocol_id)
+        .build()
+        .unwrap()
+}
+
+fn submit_sm_to(destination_addr: &str) -> SubmitSmPdu {
+    SubmitSmPduBuilder::new()
+        .destination_addr(destination_addr)
+        .short_message(b"hi")
+        .build()
+        .unwrap()
+}
@@ -1,13 +1,20 @@
 use async_trait::async_trait;
 use smpp::message_unique_key::MessageUniqueKey;
-use smpp::smsc::{BindData, BindError, Smsc, SmscLogic, SubmitSmError};
-use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp::pdu::{DataSmPdu, ExtraPdu, ExtraPduBody};
+use smpp::smsc::{
+    BindData, BindError, DeliveryStatus, ReceivePduError, Smsc, SmscConfig,
+    SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::tlvs::{KnownTlvTag, Tlv, Tlvs};
 use smpp_pdu::pdu::{
     DeliverEsmClass, DeliverSmPdu, Pdu, SubmitEsmClass, SubmitSmPdu,
     SubmitSmRespPdu,
 };
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
+use tokio::time::sleep;
 
 mod test_utils;
 
@@ -70,6 +77,89 @@ async fn when_multiple_clients_send_mts_we_deliver_drs_to_the_right_one() {
     client2.expect_to_receive(&write(dr(4)).await).await;
 }
 
+#[tokio::test]
+async fn when_a_dr_arrives_as_data_sm_we_deliver_it_to_the_right_client() {
+    let logic = Logic::new(vec![1, 2]);
+    let server = TestServer::start_with_logic_and_config(logic, 2)
+        .await
+        .unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transceiver_as("client1").await;
+    client2.bind_transceiver_as("client2").await;
+    client1
+        .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+        .await;
+    client2
+        .send_and_expect_response(&mt(2).await, &mt_resp(2).await)
+        .await;
+
+    // When the DR for client2's message arrives as a data_sm
+    server
+        .receive_pdu("multiclienttestsystem", data_sm_dr(2))
+        .await
+        .unwrap();
+
+    // Then client2 receives it
+    client2
+        .expect_to_receive(&write_extra(data_sm_dr(2)).await)
+        .await;
+
+    // And when it responds with data_sm_resp
+    client2
+        .stream
+        .write_all(
+            b"\x00\x00\x00\x11\x80\x00\x01\x03\x00\x00\x00\x00\x00\x00\x00\x02\
+            \x00",
+        )
+        .await
+        .unwrap();
+    // (Wait until the server has processed it)
+    client2
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+
+    // Then the delivery is marked complete
+    let key = MessageUniqueKey::new(
+        String::from("multiclienttestsystem"),
+        String::from("2"),
+        String::from("4477711111"),
+    );
+    assert_eq!(
+        server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Acknowledged)
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn when_several_drs_go_to_one_client_they_arrive_whole_and_in_order() {
+    let logic = Logic::new(vec![1, 2, 3]);
+    let server = TestServer::start_with_logic(logic).await.unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver_as("client1").await;
+    for i in 1..=3 {
+        client
+            .send_and_expect_response(&mt(i).await, &mt_resp(i).await)
+            .await;
+    }
+
+    // The DRs come back one after another
+    for i in 1..=3 {
+        server
+            .receive_pdu("multiclienttestsystem", dr(i))
+            .await
+            .unwrap();
+    }
+
+    // And the client receives each in full, in the same order
+    for i in 1..=3 {
+        client.expect_to_receive(&write(dr(i)).await).await;
+    }
+}
+
 #[tokio::test]
 async fn when_client_disconnects_and_reconnects_they_can_receive_drs() {
     let logic = Logic::new(vec![1, 2]);
@@ -110,6 +200,185 @@ async fn when_client_disconnects_and_reconnects_they_can_receive_drs() {
     client2.expect_to_receive(&write(dr(2)).await).await;
 }
 
+#[tokio::test]
+async fn when_client_has_disconnected_its_dr_fails_with_a_clear_error() {
+    let logic = Logic::new(vec![1]);
+    let server = TestServer::start_with_logic(logic).await.unwrap();
+    {
+        let mut client1 = TestClient::connect_to(&server).await.unwrap();
+        client1.bind_transceiver_as("client1").await;
+        client1
+            .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+            .await;
+
+        // Client 1 disconnects because we let it go out of scope here
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    // When its DR comes back, we report that it has gone
+    let e = server
+        .receive_pdu("multiclienttestsystem", dr(1))
+        .await
+        .unwrap_err();
+    assert!(matches!(e, ReceivePduError::NoConnection(_)), "{:?}", e);
+    assert_eq!(
+        e.to_string(),
+        "No client connection found with system_id='client1' \
+        system_type='type'."
+    );
+}
+
+#[tokio::test]
+async fn when_dr_arrives_while_client_is_away_we_deliver_it_when_they_rebind() {
+    let logic = Logic::new(vec![1]);
+    let server = TestServer::start_with_logic_and_smsc_config(
+        logic,
+        SmscConfig {
+            dr_delivery_deadline: Duration::from_secs(60),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    {
+        let mut client1 = TestClient::connect_to(&server).await.unwrap();
+        client1.bind_transceiver_as("client1").await;
+        client1
+            .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+            .await;
+
+        // Client 1 disconnects because we let it go out of scope here
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    // The DR arrives while nobody is connected, and we keep it
+    server
+        .receive_pdu("multiclienttestsystem", dr(1))
+        .await
+        .unwrap();
+    let key = MessageUniqueKey::new(
+        String::from("multiclienttestsystem"),
+        String::from("1"),
+        String::from("4477711111"),
+    );
+    assert_eq!(
+        server.smsc.lock().await.delivery_status(&key),
+        Some(DeliveryStatus::Pending)
+    );
+
+    // When the same ESME binds again, it receives the DR after its bind_resp
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transceiver_as("client1").await;
+    client2.expect_to_receive(&write(dr(1)).await).await;
+}
+
+#[tokio::test]
+async fn when_client_disconnects_before_acknowledging_a_dr_we_send_it_again_when_they_rebind(
+) {
+    let logic = Logic::new(vec![1]);
+    let server = TestServer::start_with_logic_and_smsc_config(
+        logic,
+        SmscConfig {
+            dr_delivery_deadline: Duration::from_secs(60),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    {
+        let mut client1 = TestClient::connect_to(&server).await.unwrap();
+        client1.bind_transceiver_as("client1").await;
+        client1
+            .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+            .await;
+
+        // The DR arrives, but client 1 disconnects without responding
+        server
+            .receive_pdu("multiclienttestsystem", dr(1))
+            .await
+            .unwrap();
+        client1.expect_to_receive(&write(dr(1)).await).await;
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    // When the same ESME binds again, it receives the DR again
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transceiver_as("client1").await;
+    client2.expect_to_receive(&write(dr(1)).await).await;
+}
+
+#[tokio::test]
+async fn when_one_of_two_sessions_disconnects_drs_go_to_the_other() {
+    let logic = Logic::new(vec![1]);
+    let server = TestServer::start_with_logic(logic).await.unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transceiver_as("client1").await;
+    client1
+        .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+        .await;
+    {
+        // The same ESME binds a second session, then closes it
+        let mut client1b = TestClient::connect_to(&server).await.unwrap();
+        client1b.bind_transceiver_as("client1").await;
+    }
+    sleep(Duration::from_millis(100)).await;
+
+    // The DR goes to the session that is still open
+    server
+        .receive_pdu("multiclienttestsystem", dr(1))
+        .await
+        .unwrap();
+    client1.expect_to_receive(&write(dr(1)).await).await;
+}
+
+#[tokio::test]
+async fn when_preserving_sequence_numbers_they_continue_after_reconnect() {
+    let logic = Logic::new(vec![1, 2]);
+    let server = TestServer::start_with_logic_and_smsc_config(
+        logic,
+        SmscConfig {
+            preserve_sequence_numbers: true,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    {
+        let mut client1 = TestClient::connect_to(&server).await.unwrap();
+        client1.bind_transceiver_as("client1").await;
+        client1
+            .send_and_expect_response(&mt(1).await, &mt_resp(1).await)
+            .await;
+        client1
+            .send_and_expect_response(&mt(2).await, &mt_resp(2).await)
+            .await;
+
+        // The first DR is numbered by the SMSC, not by whoever supplied it
+        server
+            .receive_pdu("multiclienttestsystem", dr_with_seq(1, 0x50))
+            .await
+            .unwrap();
+        client1
+            .expect_to_receive(&write(dr_with_seq(1, 1)).await)
+            .await;
+
+        // Client 1 disconnects because we let it go out of scope here
+    }
+
+    // The same system_id binds again
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_transceiver_as("client1").await;
+
+    // The next DR continues the numbering from before the reconnect
+    server
+        .receive_pdu("multiclienttestsystem", dr_with_seq(2, 0x50))
+        .await
+        .unwrap();
+    client2
+        .expect_to_receive(&write(dr_with_seq(2, 2)).await)
+        .await;
+}
+
 struct Logic {
     msgids: Vec<u32>,
 }
@@ -150,6 +419,10 @@ impl SmscLogic for Logic {
 }
 
 fn dr(sequence_number: u32) -> Pdu {
+    dr_with_seq(sequence_number, sequence_number)
+}
+
+fn dr_with_seq(msgid: u32, sequence_number: u32) -> Pdu {
     Pdu::new(
         0x00,
         sequence_number,
@@ -170,7 +443,7 @@ fn dr(sequence_number: u32) -> Pdu {
             0,
             3,
             0,
-            format!("id:{}", sequence_number).as_bytes(),
+            format!("id:{}", msgid).as_bytes(),
             Tlvs::new(),
         )
         .unwrap()
@@ -179,6 +452,34 @@ fn dr(sequence_number: u32) -> Pdu {
     .unwrap()
 }
 
+fn data_sm_dr(msgid: u32) -> ExtraPdu {
+    ExtraPdu::new(
+        0x00,
+        msgid,
+        ExtraPduBody::DataSm(
+            DataSmPdu::new(
+                "",
+                0,
+                0,
+                "4477711111",
+                0,
+                0,
+                "MyComp",
+                DeliverEsmClass::SmscDeliveryReceipt as u8,
+                0,
+                3,
+                Tlvs::from(
+                    &[Tlv::new(
+                        KnownTlvTag::receipted_message_id,
+                        msgid.to_string().as_bytes(),
+                    )][..],
+                ),
+            )
+            .unwrap(),
+        ),
+    )
+}
+
 async fn mt(sequence_number: u32) -> Vec<u8> {
     let pdu: Pdu = Pdu::new(
         0,
@@ -224,6 +525,12 @@ async fn mt_resp(sequence_number: u32) -> Vec<u8> {
     write(pdu).await
 }
 
+async fn write_extra(pdu: ExtraPdu) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::new();
+    pdu.write(&mut ret).await.unwrap();
+    ret
+}
+
 async fn write(pdu: Pdu) -> Vec<u8> {
     let mut ret: Vec<u8> = Vec::new();
     pdu.write(&mut ret).await.unwrap();
@@ -231,4 +538,3 @@ async fn write(pdu: Pdu) -> Vec<u8> {
 }
 
 // Later: Issue#15: send DR over a receiver connection when bound as transmitter
-// Later: Issue#5: drop DRs after some time trying to deliver
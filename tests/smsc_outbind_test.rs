@@ -0,0 +1,33 @@
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+
+mod test_utils;
+
+use test_utils::TestServer;
+
+#[tokio::test]
+async fn outbind_connects_to_the_esme_and_sends_outbind() {
+    // Given an ESME listening for connections
+    let esme = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let esme_addr = esme.local_addr().unwrap().to_string();
+    let server = TestServer::start().await.unwrap();
+
+    // When the SMSC outbinds to it
+    let _connection = server
+        .smsc
+        .lock()
+        .await
+        .outbind(&esme_addr, "rust_smpp", "pw")
+        .await
+        .unwrap();
+
+    // Then the ESME receives an outbind
+    let (mut stream, _) = esme.accept().await.unwrap();
+    let mut buf = [0; 0x1d];
+    stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(
+        &buf,
+        b"\x00\x00\x00\x1d\x00\x00\x00\x0b\x00\x00\x00\x00\x00\x00\x00\x01\
+        rust_smpp\x00pw\x00"
+    );
+}
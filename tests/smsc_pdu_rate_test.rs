@@ -0,0 +1,39 @@
+use smpp::smsc::SmscConfig;
+use std::time::{Duration, Instant};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestClient, TestServer};
+
+#[tokio::test]
+async fn when_client_sends_too_many_pdus_we_slow_down_reading() {
+    // Given a server that reads at most 5 PDUs per second
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            max_pdus_per_second: Some(5),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When the client floods it with 10 enquire_links
+    let start = Instant::now();
+    for seq in 1..=10u8 {
+        let mut enquire_link =
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x00"
+                .to_vec();
+        enquire_link[15] = seq;
+        let mut enquire_link_resp = enquire_link.clone();
+        enquire_link_resp[4] = 0x80;
+        client
+            .send_and_expect_response(&enquire_link, &enquire_link_resp)
+            .await;
+    }
+
+    // Then they are all answered, but the later ones wait for the next
+    // second
+    assert!(start.elapsed() >= Duration::from_secs(1));
+}
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::pdu::{MessageState, QuerySmPdu, QuerySmRespPdu};
+use smpp::smsc::{
+    BindData, BindError, QuerySmError, Smsc, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestSetup};
+
+const SUBMIT_SM: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+const SUBMIT_SM_RESP: &[u8; 0x1a] =
+    b"\x00\x00\x00\x1a\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x03\
+    mymessage\x00";
+
+/// Accepts every message as "mymessage" and reports it delivered
+struct Logic {}
+
+#[async_trait]
+impl SmscLogic for Logic {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new("mymessage").unwrap(),
+            MessageUniqueKey::new(
+                String::from("querytest"),
+                String::from("mymessage"),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+
+    async fn query_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &QuerySmPdu,
+    ) -> Result<QuerySmRespPdu, QuerySmError> {
+        if pdu.message_id.value == "mymessage" {
+            Ok(QuerySmRespPdu::new(
+                "mymessage",
+                "210330164900000+",
+                MessageState::Delivered,
+                0,
+            )
+            .unwrap())
+        } else {
+            Err(QuerySmError::QueryFailed)
+        }
+    }
+}
+
+async fn submitted() -> TestClient {
+    let mut client = TestSetup::new_with_logic(Logic {})
+        .await
+        .client
+        .into_bound_transmitter()
+        .await;
+    client
+        .send_and_expect_response(SUBMIT_SM, SUBMIT_SM_RESP)
+        .await;
+    client
+}
+
+#[tokio::test]
+async fn when_we_receive_query_sm_we_respond_with_message_state() {
+    let mut client = submitted().await;
+
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x29\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x04\
+            mymessage\x00\x00\x00447000123123\x00",
+            b"\x00\x00\x00\x2d\x80\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x04\
+            mymessage\x00210330164900000+\x00\x02\x00",
+            //              message_state = DELIVERED ^^^^
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn when_query_sm_fails_we_respond_with_error() {
+    let mut client = submitted().await;
+
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x27\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x04\
+            unknown\x00\x00\x00447000123123\x00",
+            b"\x00\x00\x00\x10\x80\x00\x00\x03\x00\x00\x00\x67\x00\x00\x00\x04",
+            //                        ESME_RQUERYFAIL ^^^^
+        )
+        .await;
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestServer};
+
+/// bind_transmitter, then enquire_links and submit_sms, then unbind
+const TRANSMITTER_SESSION: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/data/transmitter_session.smpp"
+);
+
+#[tokio::test]
+async fn replaying_a_captured_stream_gets_a_response_to_every_pdu() {
+    let server = TestServer::start().await.unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+
+    // When we replay a captured session at 100 PDUs per second
+    let stats = client.replay(TRANSMITTER_SESSION, Some(100)).await;
+
+    // Then every PDU was processed, in order
+    assert_eq!(stats.pdus_sent, 7);
+    let sequence_numbers: Vec<u32> = stats
+        .responses
+        .iter()
+        .map(|pdu| pdu.sequence_number())
+        .collect();
+    assert_eq!(sequence_numbers, vec![1, 2, 3, 4, 5, 6, 7]);
+
+    // And we sent no faster than we were asked to
+    assert!(stats.elapsed >= Duration::from_millis(60));
+    assert!(stats.pdus_per_second() <= 7.0 / 0.06);
+}
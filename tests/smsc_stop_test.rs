@@ -0,0 +1,81 @@
+use smpp::smsc;
+use smpp::smsc::SmscConfig;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+
+mod test_utils;
+
+use test_utils::{next_bind_address, DefaultLogic, TestClient, TestServer};
+
+#[tokio::test]
+async fn when_we_stop_bound_clients_receive_unbind_and_are_disconnected() {
+    // Given two bound clients
+    let server = TestServer::start().await.unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transceiver().await;
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2.bind_receiver().await;
+
+    // When we stop the SMSC
+    server.smsc.lock().await.stop().await;
+
+    // Then each client receives an unbind
+    for client in [&mut client1, &mut client2] {
+        client
+            .expect_to_receive(
+                b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x01",
+            )
+            .await;
+
+        // And then its connection is closed
+        assert_eq!(
+            client.stream.read_u8().await.unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    assert!(server.smsc.lock().await.is_stopped());
+}
+
+#[tokio::test]
+async fn when_shutdown_is_signalled_clients_are_unbound_and_run_completes() {
+    // Given an SMSC running until we signal it to shut down
+    let bind_address = next_bind_address();
+    let config = SmscConfig {
+        bind_address: bind_address.clone(),
+        shutdown_grace_period: Duration::from_secs(1),
+        ..TestServer::default_config()
+    };
+    let shutdown = Arc::new(Notify::new());
+    let signal = Arc::clone(&shutdown);
+    let run = tokio::spawn(async move {
+        smsc::run_until(config, DefaultLogic {}, signal.notified())
+            .await
+            .unwrap()
+    });
+
+    // And a bound client
+    let mut client = TestClient::connect(&bind_address).await.unwrap();
+    client.bind_transceiver().await;
+
+    // When we signal it to shut down
+    shutdown.notify_one();
+
+    // Then the client receives an unbind
+    client
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x00\x00\x00\x06\x00\x00\x00\x00\x00\x00\x00\x01",
+        )
+        .await;
+
+    // And when it disconnects, run completes
+    drop(client);
+    timeout(Duration::from_millis(500), run)
+        .await
+        .expect("run did not complete")
+        .unwrap();
+}
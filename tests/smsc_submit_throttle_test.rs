@@ -0,0 +1,112 @@
+use smpp::async_result::AsyncResult;
+use smpp::client::{ClientError, SmppClient};
+use smpp::smsc::SmscConfig;
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{PduStatus, SubmitEsmClass, SubmitSmPdu};
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestServer};
+
+#[tokio::test]
+async fn when_system_id_submits_too_fast_we_throttle_it() {
+    // Given a server allowing 5 submit_sm per second per system_id
+    let server = start_server().await;
+    let mut client = SmppClient::connect(&server.bind_address).await.unwrap();
+    client.bind_transmitter("esmeid", "password").await.unwrap();
+
+    // When the client submits 20 messages as fast as it can
+    let mut throttled = 0;
+    for _ in 0..20 {
+        if command_status(client.submit_sm(submit_sm()).await)
+            == PduStatus::ESME_RTHROTTLED as u32
+        {
+            throttled += 1;
+        }
+    }
+
+    // Then some of them are throttled
+    assert!(throttled > 0);
+    assert!(throttled < 20);
+}
+
+#[tokio::test]
+async fn throttling_is_shared_by_connections_with_the_same_system_id() {
+    let server = start_server().await;
+    let mut client1 = SmppClient::connect(&server.bind_address).await.unwrap();
+    client1
+        .bind_transmitter("esmeid", "password")
+        .await
+        .unwrap();
+    let mut client2 = SmppClient::connect(&server.bind_address).await.unwrap();
+    client2
+        .bind_transmitter("esmeid", "password")
+        .await
+        .unwrap();
+    let mut other = SmppClient::connect(&server.bind_address).await.unwrap();
+    other.bind_transmitter("otherid", "password").await.unwrap();
+
+    // Given one connection has used up the system_id's allowance
+    for _ in 0..5 {
+        client1.submit_sm(submit_sm()).await.unwrap_err();
+    }
+
+    // When another connection with the same system_id submits, it is
+    // throttled
+    assert_eq!(
+        command_status(client2.submit_sm(submit_sm()).await),
+        PduStatus::ESME_RTHROTTLED as u32
+    );
+
+    // But a different system_id is not
+    assert_ne!(
+        command_status(other.submit_sm(submit_sm()).await),
+        PduStatus::ESME_RTHROTTLED as u32
+    );
+}
+
+async fn start_server() -> TestServer {
+    TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            max_submits_per_second: Some(5),
+            max_open_sockets: 3,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap()
+}
+
+/// The command_status the SMSC responded with.  DefaultLogic rejects every
+/// message it sees, so unthrottled messages get ESME_RSYSERR.
+fn command_status<T>(resp: AsyncResult<T>) -> u32 {
+    match resp.err().unwrap().downcast_ref::<ClientError>() {
+        Some(ClientError::ErrorStatus(command_status)) => *command_status,
+        other => panic!("Expected an error status, got {:?}", other),
+    }
+}
+
+fn submit_sm() -> SubmitSmPdu {
+    SubmitSmPdu::new(
+        "",
+        0,
+        0,
+        "447000123123",
+        0,
+        0,
+        "447111222222",
+        SubmitEsmClass::Default as u8,
+        0x34,
+        1,
+        "",
+        "",
+        1,
+        0,
+        3,
+        0,
+        b"hi",
+        Tlvs::new(),
+    )
+    .unwrap()
+}
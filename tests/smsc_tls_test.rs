@@ -0,0 +1,163 @@
+use rustls_pemfile::certs;
+use smpp::smsc::{SmscConfig, TlsConfig};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestServer};
+
+const BIND_TRANSMITTER: &[u8] =
+    b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+    esmeid\0password\0type\0\x34\x00\x00\0";
+
+const BIND_TRANSMITTER_RESP: &[u8] =
+    b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\
+    \x00\x00\x00\x07TestServer\0";
+
+fn tls_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/tls")
+        .join(name)
+}
+
+/// A TLS client that trusts our test certificate
+fn trusting_connector() -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    for cert in certs(&mut BufReader::new(
+        File::open(tls_file("cert.pem")).unwrap(),
+    )) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    TlsConnector::from(Arc::new(
+        ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    ))
+}
+
+#[tokio::test]
+async fn when_tls_is_configured_clients_can_bind_over_tls() {
+    // Given a server accepting TLS connections
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            tls: Some(TlsConfig {
+                cert_path: tls_file("cert.pem"),
+                key_path: tls_file("key.pem"),
+            }),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+
+    // And a client that trusts its certificate
+    let connector = trusting_connector();
+
+    // When the client connects over TLS and binds
+    let tcp_stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let mut stream = connector
+        .connect(ServerName::try_from("localhost").unwrap(), tcp_stream)
+        .await
+        .unwrap();
+    stream.write_all(BIND_TRANSMITTER).await.unwrap();
+
+    // Then it receives a bind_transmitter_resp
+    let mut resp = vec![0; BIND_TRANSMITTER_RESP.len()];
+    stream.read_exact(&mut resp).await.unwrap();
+    assert_eq!(resp, BIND_TRANSMITTER_RESP);
+}
+
+#[tokio::test]
+async fn when_tls_is_configured_plain_clients_cannot_bind() {
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            tls: Some(TlsConfig {
+                cert_path: tls_file("cert.pem"),
+                key_path: tls_file("key.pem"),
+            }),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+
+    // When a client sends a bind without TLS
+    let mut stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    stream.write_all(BIND_TRANSMITTER).await.unwrap();
+
+    // Then the server closes the connection without a bind_transmitter_resp
+    let mut resp = Vec::new();
+    stream.read_to_end(&mut resp).await.unwrap_or_default();
+    assert!(!resp.starts_with(b"\x00\x00\x00\x1b\x80\x00\x00\x02"));
+}
+
+#[tokio::test]
+async fn a_stalled_tls_handshake_holds_a_socket_until_it_times_out() {
+    // Given a server with room for one connection, which gives clients
+    // 300ms to finish the TLS handshake
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            tls: Some(TlsConfig {
+                cert_path: tls_file("cert.pem"),
+                key_path: tls_file("key.pem"),
+            }),
+            tls_handshake_timeout: Duration::from_millis(300),
+            max_open_sockets: 1,
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let connector = trusting_connector();
+
+    // When a client opens a connection and never starts the handshake
+    let mut stalled = TcpStream::connect(&server.bind_address).await.unwrap();
+
+    // Then other clients can still connect, but the stalled one is using
+    // the only socket, so they are refused
+    let tcp_stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let mut stream = connector
+        .connect(ServerName::try_from("localhost").unwrap(), tcp_stream)
+        .await
+        .unwrap();
+    stream.write_all(BIND_TRANSMITTER).await.unwrap();
+    let mut resp = Vec::new();
+    timeout(Duration::from_secs(1), stream.read_to_end(&mut resp))
+        .await
+        .unwrap()
+        .unwrap_or_default();
+    assert_eq!(resp, b"");
+
+    // And when the handshake times out, the stalled client is disconnected
+    let mut resp = Vec::new();
+    timeout(Duration::from_secs(1), stalled.read_to_end(&mut resp))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(resp, b"");
+
+    // And its socket is free for the next client
+    let tcp_stream = TcpStream::connect(&server.bind_address).await.unwrap();
+    let mut stream = connector
+        .connect(ServerName::try_from("localhost").unwrap(), tcp_stream)
+        .await
+        .unwrap();
+    stream.write_all(BIND_TRANSMITTER).await.unwrap();
+    let mut resp = vec![0; BIND_TRANSMITTER_RESP.len()];
+    stream.read_exact(&mut resp).await.unwrap();
+    assert_eq!(resp, BIND_TRANSMITTER_RESP);
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use smpp::message_unique_key::MessageUniqueKey;
+use smpp::smsc::{
+    BindData, BindError, Smsc, SmscConfig, SmscLogic, SubmitSmError,
+};
+use smpp_pdu::pdu::tlvs::Tlvs;
+use smpp_pdu::pdu::{
+    DeliverEsmClass, DeliverSmPdu, Pdu, SubmitSmPdu, SubmitSmRespPdu,
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+mod test_utils;
+
+use test_utils::{TestClient, TestServer};
+
+const SUBMIT_SM_1: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x01\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+const SUBMIT_SM_2: &[u8; 0x3d] =
+    b"\x00\x00\x00\x3d\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x02\
+    \x00\x00\x00447000123123\x00\x00\x00447111222222\x00\
+    \x00\x01\x01\x00\x00\x01\x00\x03\x00\x04hihi";
+
+#[tokio::test]
+async fn when_window_is_full_we_hold_deliver_sm_until_a_resp_arrives() {
+    // Given a server allowing 1 outstanding deliver_sm per client
+    let server = TestServer::start_with_logic_and_smsc_config(
+        NumberedMessages { next: 1 },
+        SmscConfig {
+            window_size: Some(1),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client = TestClient::connect_to(&server).await.unwrap();
+    client.bind_transceiver().await;
+
+    // And a client that has sent two messages
+    client
+        .send_and_expect_response(
+            SUBMIT_SM_1,
+            b"\x00\x00\x00\x15\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x01\
+            msg1\x00",
+        )
+        .await;
+    client
+        .send_and_expect_response(
+            SUBMIT_SM_2,
+            b"\x00\x00\x00\x15\x80\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x02\
+            msg2\x00",
+        )
+        .await;
+
+    // When both DRs arrive
+    server
+        .receive_pdu("window", deliver_sm(0x10, "msg1"))
+        .await
+        .unwrap();
+    client
+        .expect_to_receive(&bytes(deliver_sm(0x10, "msg1")).await)
+        .await;
+    server
+        .receive_pdu("window", deliver_sm(0x11, "msg2"))
+        .await
+        .unwrap();
+
+    // Then the second is held: the next thing the client receives is the
+    // response to its enquire_link
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x10\x00\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+            b"\x00\x00\x00\x10\x80\x00\x00\x15\x00\x00\x00\x00\x00\x00\x00\x12",
+        )
+        .await;
+    // And is not counted as sent
+    assert_eq!(
+        server.smsc.lock().await.metrics_snapshot().deliver_sm_sent,
+        1
+    );
+
+    // Until the client responds to the first
+    client
+        .send_and_expect_response(
+            b"\x00\x00\x00\x11\x80\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x10\
+            \x00",
+            &bytes(deliver_sm(0x11, "msg2")).await,
+        )
+        .await;
+    assert_eq!(
+        server.smsc.lock().await.metrics_snapshot().deliver_sm_sent,
+        2
+    );
+}
+
+/// Gives each message an id "msg1", "msg2" etc.
+struct NumberedMessages {
+    next: u32,
+}
+
+#[async_trait]
+impl SmscLogic for NumberedMessages {
+    async fn bind(&mut self, _bind_data: &BindData) -> Result<(), BindError> {
+        Ok(())
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        let msgid = format!("msg{}", self.next);
+        self.next += 1;
+        Ok((
+            SubmitSmRespPdu::new(&msgid).unwrap(),
+            MessageUniqueKey::new(
+                String::from("window"),
+                msgid,
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+fn deliver_sm(sequence_number: u32, msgid: &str) -> Pdu {
+    Pdu::new(
+        0x00,
+        sequence_number,
+        DeliverSmPdu::new(
+            "",
+            0,
+            0,
+            "447111222222",
+            0,
+            0,
+            "447000123123",
+            DeliverEsmClass::SmscDeliveryReceipt as u8,
+            0x34,
+            1,
+            "",
+            "",
+            1,
+            0,
+            3,
+            0,
+            format!("id:{} submit date:2103301649", msgid).as_bytes(),
+            Tlvs::new(),
+        )
+        .unwrap()
+        .into(),
+    )
+    .unwrap()
+}
+
+async fn bytes(pdu: Pdu) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::new();
+    pdu.write(&mut ret).await.unwrap();
+    ret
+}
@@ -1,8 +1,10 @@
+use smpp::smsc::SmscConfig;
+use std::time::Duration;
 use tokio::io::AsyncWriteExt;
 
 mod test_utils;
 
-use test_utils::{TestClient, TestServer};
+use test_utils::{DefaultLogic, TestClient, TestServer};
 
 const BIND_TRANSMITTER_PDU: &[u8; 0x29] =
     b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
@@ -15,9 +17,9 @@ async fn listens_on_tcp_port() {
     // When we connect
     let mut client = TestClient::connect_to(&server).await.unwrap();
     // Then we can write and read to it
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
+    assert!(!resp.is_empty());
 }
 
 #[tokio::test]
@@ -27,12 +29,12 @@ async fn stays_connected() {
     // When we connect
     let mut client = TestClient::connect_to(&server).await.unwrap();
     // Then we can write and read to it multiple times
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
-    client.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    assert!(!resp.is_empty());
+    client.stream.write_all(BIND_TRANSMITTER_PDU).await.unwrap();
     let resp = client.read_string().await.unwrap();
-    assert!(resp.len() > 0);
+    assert!(!resp.is_empty());
 }
 
 #[tokio::test]
@@ -43,18 +45,67 @@ async fn disconnects_clients_when_overloaded() {
     let mut client1 = TestClient::connect_to(&server).await.unwrap();
     let mut client2 = TestClient::connect_to(&server).await.unwrap();
     let mut client3 = TestClient::connect_to(&server).await.unwrap();
-    client1.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
-    client2.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
-    client3.stream.write(BIND_TRANSMITTER_PDU).await.unwrap();
+    client1
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
+    client2
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
+    client3
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
     let resp1 = client1.read_string().await.unwrap();
     let resp2 = client2.read_string().await.unwrap();
     let resp3_or_err = client3.read_string().await;
 
     // Then two of them are able to stay connected
-    assert!(resp1.len() > 0);
-    assert!(resp2.len() > 0);
+    assert!(!resp1.is_empty());
+    assert!(!resp2.is_empty());
 
     // And the third gets immediately disconnected
     let resp3 = resp3_or_err.unwrap_or(String::from(""));
     assert_eq!(resp3, "");
 }
+
+#[tokio::test]
+async fn when_configured_rejects_binds_when_overloaded() {
+    // Given a server that allows 1 client, and rejects binds beyond that
+    let server = TestServer::start_with_logic_and_smsc_config(
+        DefaultLogic {},
+        SmscConfig {
+            max_open_sockets: 1,
+            overload_bind_wait: Some(Duration::from_secs(1)),
+            ..TestServer::default_config()
+        },
+    )
+    .await
+    .unwrap();
+    let mut client1 = TestClient::connect_to(&server).await.unwrap();
+    client1.bind_transmitter().await;
+
+    // When another client connects and binds
+    let mut client2 = TestClient::connect_to(&server).await.unwrap();
+    client2
+        .stream
+        .write_all(BIND_TRANSMITTER_PDU)
+        .await
+        .unwrap();
+
+    // Then it gets bind_transmitter_resp with ESME_RBINDFAIL
+    client2
+        .expect_to_receive(
+            b"\x00\x00\x00\x10\x80\x00\x00\x02\x00\x00\x00\x0d\
+            \x00\x00\x00\x02",
+        )
+        .await;
+
+    // And is then disconnected
+    let rest = client2.read_string().await.unwrap_or(String::from(""));
+    assert_eq!(rest, "");
+}
@@ -1,18 +1,23 @@
 use async_trait::async_trait;
+use log::{LevelFilter, Log, Metadata, Record};
 use once_cell::sync::Lazy;
 use smpp::async_result::AsyncResult;
 use smpp::message_unique_key::MessageUniqueKey;
+use smpp::pdu::AnyPdu;
+use smpp::pdu_reader::PduReader;
 use smpp::smsc::{
-    BindData, BindError, Smsc, SmscConfig, SmscLogic, SubmitSmError,
+    BindData, BindError, ReceivePduError, Smsc, SmscConfig, SmscLogic,
+    SubmitSmError,
 };
-use smpp_pdu::pdu::{Pdu, SubmitSmPdu, SubmitSmRespPdu};
+use smpp_pdu::pdu::{SubmitSmPdu, SubmitSmRespPdu};
 use std::io;
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Once};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
 const TEST_BIND_URL: &str = "127.0.0.1";
 
@@ -36,6 +41,84 @@ impl SmscLogic for DefaultLogic {
     }
 }
 
+/// The message_id AcceptAll gives every message
+#[allow(dead_code)]
+pub const ACCEPTED_MESSAGE_ID: &str = "mymessage";
+
+/// The namespace of the MessageUniqueKeys AcceptAll makes, which DRs for
+/// its messages must be received with
+#[allow(dead_code)]
+pub const ACCEPT_ALL_NAMESPACE: &str = "acceptall";
+
+/// Accepts binds with the password "password", and every submit_sm, giving
+/// each message the message_id ACCEPTED_MESSAGE_ID
+#[allow(dead_code)]
+pub struct AcceptAll {}
+
+#[async_trait]
+impl SmscLogic for AcceptAll {
+    async fn bind(&mut self, bind_data: &BindData) -> Result<(), BindError> {
+        if bind_data.password.value == "password" {
+            Ok(())
+        } else {
+            Err(BindError::IncorrectPassword)
+        }
+    }
+
+    async fn submit_sm(
+        &mut self,
+        _smsc: Arc<Mutex<Smsc>>,
+        pdu: &SubmitSmPdu,
+        _sequence_number: u32,
+    ) -> Result<(SubmitSmRespPdu, MessageUniqueKey), SubmitSmError> {
+        Ok((
+            SubmitSmRespPdu::new(ACCEPTED_MESSAGE_ID).unwrap(),
+            MessageUniqueKey::new(
+                String::from(ACCEPT_ALL_NAMESPACE),
+                String::from(ACCEPTED_MESSAGE_ID),
+                pdu.destination_addr(),
+            ),
+        ))
+    }
+}
+
+static LOGS: Lazy<std::sync::Mutex<Vec<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(Vec::new()));
+
+/// Remembers every log line, so tests can check what was logged
+pub struct CapturingLogger;
+
+#[allow(dead_code)]
+impl CapturingLogger {
+    /// Start capturing.  Call before starting a TestServer, which would
+    /// otherwise install its own logger.
+    pub fn install() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&CapturingLogger)
+                .expect("Another logger was installed first");
+            log::set_max_level(LevelFilter::Trace);
+        });
+    }
+
+    /// Every line logged so far
+    pub fn lines() -> Vec<String> {
+        LOGS.lock().unwrap().clone()
+    }
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        LOGS.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
 /// Setup for running tests that send and receive PDUs
 pub struct TestSetup {
     pub server: TestServer,
@@ -65,7 +148,12 @@ impl TestSetup {
 }
 
 fn next_port() -> usize {
-    return PORT.fetch_add(1, Ordering::Relaxed);
+    PORT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// An address for a test server to listen on, different each time
+pub fn next_bind_address() -> String {
+    format!("{}:{}", TEST_BIND_URL, next_port())
 }
 
 /// A test server listening on the test port
@@ -92,18 +180,43 @@ impl TestServer {
     >(
         smsc_logic: L,
         max_open_sockets: usize,
+    ) -> AsyncResult<Self> {
+        TestServer::start_with_logic_and_smsc_config(
+            smsc_logic,
+            SmscConfig {
+                max_open_sockets,
+                ..TestServer::default_config()
+            },
+        )
+        .await
+    }
+
+    /// The config used by tests unless they supply their own.  The
+    /// bind_address is ignored: we always listen on a fresh test port.
+    pub fn default_config() -> SmscConfig {
+        SmscConfig {
+            max_open_sockets: 2,
+            system_id: String::from("TestServer"),
+            ..SmscConfig::default()
+        }
+    }
+
+    pub async fn start_with_logic_and_smsc_config<
+        L: SmscLogic + Send + Sync + 'static,
+    >(
+        smsc_logic: L,
+        smsc_config: SmscConfig,
     ) -> AsyncResult<Self> {
         let _ = env_logger::builder()
             .filter_level(log::LevelFilter::Trace)
             .is_test(true)
             .try_init();
 
-        let bind_address = format!("{}:{}", TEST_BIND_URL, next_port());
+        let bind_address = next_bind_address();
 
         let smsc_config = SmscConfig {
             bind_address: String::from(&bind_address),
-            max_open_sockets,
-            system_id: String::from("TestServer"),
+            ..smsc_config
         };
 
         let smsc = Smsc::start(smsc_config, smsc_logic).await.unwrap();
@@ -121,8 +234,8 @@ impl TestServer {
     pub async fn receive_pdu(
         &self,
         namespace_id: &str,
-        pdu: Pdu,
-    ) -> AsyncResult<()> {
+        pdu: impl Into<AnyPdu>,
+    ) -> Result<(), ReceivePduError> {
         self.smsc.lock().await.receive_pdu(namespace_id, pdu).await
     }
 }
@@ -135,10 +248,14 @@ pub struct TestClient {
 #[allow(dead_code)]
 impl TestClient {
     pub async fn connect_to(server: &TestServer) -> AsyncResult<TestClient> {
+        TestClient::connect(&server.bind_address).await
+    }
+
+    pub async fn connect(bind_address: &str) -> AsyncResult<TestClient> {
         // Connect to the server, retrying with 10ms delay if we fail
         let mut i: u8 = 0;
         loop {
-            match TcpStream::connect(&server.bind_address).await {
+            match TcpStream::connect(bind_address).await {
                 Ok(stream) => return Ok(TestClient { stream }),
                 Err(e) => {
                     i += 1;
@@ -208,7 +325,7 @@ impl TestClient {
     }
 
     async fn send_exp(&mut self, input: &[u8], expected_output: &[u8]) {
-        self.stream.write(input).await.unwrap();
+        self.stream.write_all(input).await.unwrap();
         self.expect_to_receive(expected_output).await;
     }
 
@@ -274,6 +391,87 @@ impl TestClient {
     }
 }
 
+/// What happened when we replayed a captured stream of PDUs
+#[allow(dead_code)]
+pub struct ReplayStats {
+    pub pdus_sent: usize,
+    /// The responses we received, in the order they arrived
+    pub responses: Vec<AnyPdu>,
+    /// From sending the first PDU to receiving the last response
+    pub elapsed: Duration,
+}
+
+#[allow(dead_code)]
+impl ReplayStats {
+    pub fn pdus_per_second(&self) -> f64 {
+        self.pdus_sent as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+#[allow(dead_code)]
+impl TestClient {
+    /// Send every PDU from a file containing a captured stream of PDUs
+    /// (each prefixed by its command_length, as on the wire), at most
+    /// pdus_per_second of them, then wait for a response to each one.
+    pub async fn replay(
+        &mut self,
+        path: impl AsRef<Path>,
+        pdus_per_second: Option<u32>,
+    ) -> ReplayStats {
+        let captured = std::fs::read(path).unwrap();
+        let pdus = split_pdus(&captured);
+        let gap = pdus_per_second
+            .map(|rate| Duration::from_secs(1) / rate)
+            .unwrap_or_default();
+
+        let start = Instant::now();
+        for (i, pdu) in pdus.iter().enumerate() {
+            if i > 0 {
+                sleep(gap).await;
+            }
+            self.stream.write_all(pdu).await.unwrap();
+        }
+
+        let mut reader = PduReader::new(&mut self.stream);
+        let mut responses = Vec::with_capacity(pdus.len());
+        while responses.len() < pdus.len() {
+            match reader.read_pdu().await.unwrap() {
+                Some(pdu) => responses.push(pdu),
+                None => break,
+            }
+        }
+
+        ReplayStats {
+            pdus_sent: pdus.len(),
+            responses,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// Split a captured stream of PDUs into individual PDUs, using the
+/// command_length at the start of each one.
+#[allow(dead_code)]
+pub fn split_pdus(mut bytes: &[u8]) -> Vec<&[u8]> {
+    let mut ret = Vec::new();
+    while bytes.len() >= 4 {
+        let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            as usize;
+        assert!(
+            len >= 16 && len <= bytes.len(),
+            "Captured stream contains a truncated PDU"
+        );
+        let (pdu, rest) = bytes.split_at(len);
+        ret.push(pdu);
+        bytes = rest;
+    }
+    assert!(
+        bytes.is_empty(),
+        "Captured stream ends part-way through a PDU"
+    );
+    ret
+}
+
 #[allow(dead_code)]
 pub fn bytes_as_string(arr: &[u8]) -> String {
     arr.iter()
@@ -0,0 +1,50 @@
+#![cfg(unix)]
+
+use smpp::smsc::{Smsc, SmscConfig};
+use std::path::PathBuf;
+use std::process;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+
+mod test_utils;
+
+use test_utils::{DefaultLogic, TestServer};
+
+#[tokio::test]
+async fn when_bind_address_is_a_unix_path_clients_can_bind_over_it() {
+    // Given a server listening on a Unix domain socket
+    let path: PathBuf = std::env::temp_dir().join(format!(
+        "rust_smpp_unix_listener_test_{}.sock",
+        process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let smsc = Smsc::start(
+        SmscConfig {
+            bind_address: format!("unix:{}", path.display()),
+            ..TestServer::default_config()
+        },
+        DefaultLogic {},
+    )
+    .await
+    .unwrap();
+
+    // When a client connects to it and binds
+    let mut stream = UnixStream::connect(&path).await.unwrap();
+    stream
+        .write_all(
+            b"\x00\x00\x00\x29\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x07\
+            esmeid\0password\0type\0\x34\x00\x00\0",
+        )
+        .await
+        .unwrap();
+
+    // Then it receives a bind_transmitter_resp
+    let expected = b"\x00\x00\x00\x1b\x80\x00\x00\x02\x00\x00\x00\x00\
+        \x00\x00\x00\x07TestServer\0";
+    let mut resp = vec![0; expected.len()];
+    stream.read_exact(&mut resp).await.unwrap();
+    assert_eq!(resp, expected);
+
+    smsc.lock().await.stop().await;
+    std::fs::remove_file(&path).unwrap();
+}